@@ -2,11 +2,12 @@ use crate::{
     app::{App, AppExit},
     event::Events,
     plugin::Plugin,
-    stage, startup_stage, PluginGroup, PluginGroupBuilder,
+    stage, startup_stage, PluginGroup, PluginGroupBuilder, SystemErrorPolicy,
 };
 use bevy_ecs::{
-    clear_trackers_system, FromResources, IntoSystem, Resource, Resources, RunOnce, Schedule,
-    Stage, StateStage, System, SystemStage, World,
+    apply_state_transition, clear_trackers_system, Component, FromResources, IntoSystem, Resource,
+    Resources, RunOnce, Schedule, Stage, State, StateStage, StorageType, SystemDescriptor,
+    SystemError, SystemSet, SystemStage, World,
 };
 use bevy_utils::tracing::debug;
 
@@ -125,11 +126,25 @@ impl AppBuilder {
         self
     }
 
-    pub fn add_system<S: System<In = (), Out = ()>>(&mut self, system: S) -> &mut Self {
+    pub fn add_system<S: Into<SystemDescriptor>>(&mut self, system: S) -> &mut Self {
         self.add_system_to_stage(stage::UPDATE, system)
     }
 
-    pub fn on_state_enter<T: Clone + Resource, S: System<In = (), Out = ()>>(
+    pub fn add_system_set(&mut self, system_set: SystemSet) -> &mut Self {
+        self.add_system_set_to_stage(stage::UPDATE, system_set)
+    }
+
+    /// Adds the `State<T>` resource, starting in `initial`, and applies its transitions in the
+    /// `STATE_TRANSITIONS` stage. Systems in any stage can then be gated with the run criteria of
+    /// `State<T>`, like `State::in_state`.
+    pub fn add_state<T: Clone + Resource>(&mut self, initial: T) -> &mut Self {
+        self.add_resource(State::new(initial)).add_system_to_stage(
+            stage::STATE_TRANSITIONS,
+            apply_state_transition::<T>.system(),
+        )
+    }
+
+    pub fn on_state_enter<T: Clone + Resource, S: Into<SystemDescriptor>>(
         &mut self,
         stage: &str,
         state: T,
@@ -140,7 +155,7 @@ impl AppBuilder {
         })
     }
 
-    pub fn on_state_update<T: Clone + Resource, S: System<In = (), Out = ()>>(
+    pub fn on_state_update<T: Clone + Resource, S: Into<SystemDescriptor>>(
         &mut self,
         stage: &str,
         state: T,
@@ -151,7 +166,7 @@ impl AppBuilder {
         })
     }
 
-    pub fn on_state_exit<T: Clone + Resource, S: System<In = (), Out = ()>>(
+    pub fn on_state_exit<T: Clone + Resource, S: Into<SystemDescriptor>>(
         &mut self,
         stage: &str,
         state: T,
@@ -162,7 +177,29 @@ impl AppBuilder {
         })
     }
 
-    pub fn add_startup_system_to_stage<S: System<In = (), Out = ()>>(
+    pub fn on_state_pause<T: Clone + Resource, S: Into<SystemDescriptor>>(
+        &mut self,
+        stage: &str,
+        state: T,
+        system: S,
+    ) -> &mut Self {
+        self.stage(stage, |stage: &mut StateStage<T>| {
+            stage.on_state_pause(state, system)
+        })
+    }
+
+    pub fn on_state_resume<T: Clone + Resource, S: Into<SystemDescriptor>>(
+        &mut self,
+        stage: &str,
+        state: T,
+        system: S,
+    ) -> &mut Self {
+        self.stage(stage, |stage: &mut StateStage<T>| {
+            stage.on_state_resume(state, system)
+        })
+    }
+
+    pub fn add_startup_system_to_stage<S: Into<SystemDescriptor>>(
         &mut self,
         stage_name: &'static str,
         system: S,
@@ -175,7 +212,7 @@ impl AppBuilder {
         self
     }
 
-    pub fn add_startup_system<S: System<In = (), Out = ()>>(&mut self, system: S) -> &mut Self {
+    pub fn add_startup_system<S: Into<SystemDescriptor>>(&mut self, system: S) -> &mut Self {
         self.add_startup_system_to_stage(startup_stage::STARTUP, system)
     }
 
@@ -189,6 +226,7 @@ impl AppBuilder {
                 .with_stage(startup_stage::POST_STARTUP, SystemStage::parallel()),
         )
         .add_stage(stage::FIRST, SystemStage::parallel())
+        .add_stage(stage::STATE_TRANSITIONS, SystemStage::parallel())
         .add_stage(stage::PRE_EVENT, SystemStage::parallel())
         .add_stage(stage::EVENT, SystemStage::parallel())
         .add_stage(stage::PRE_UPDATE, SystemStage::parallel())
@@ -197,7 +235,7 @@ impl AppBuilder {
         .add_stage(stage::LAST, SystemStage::parallel())
     }
 
-    pub fn add_system_to_stage<S: System<In = (), Out = ()>>(
+    pub fn add_system_to_stage<S: Into<SystemDescriptor>>(
         &mut self,
         stage_name: &'static str,
         system: S,
@@ -206,6 +244,17 @@ impl AppBuilder {
         self
     }
 
+    pub fn add_system_set_to_stage(
+        &mut self,
+        stage_name: &'static str,
+        system_set: SystemSet,
+    ) -> &mut Self {
+        self.app
+            .schedule
+            .add_system_set_to_stage(stage_name, system_set);
+        self
+    }
+
     pub fn add_event<T>(&mut self) -> &mut Self
     where
         T: Send + Sync + 'static,
@@ -223,6 +272,17 @@ impl AppBuilder {
         self
     }
 
+    /// Sets what happens to the errors returned by systems returning `anyhow::Result<()>`.
+    /// Errors are handled when the stage of the failed system flushes.
+    pub fn set_system_error_policy(&mut self, policy: SystemErrorPolicy) -> &mut Self {
+        if policy == SystemErrorPolicy::SendEvent
+            && !self.resources().contains::<Events<SystemError>>()
+        {
+            self.add_event::<SystemError>();
+        }
+        self.add_resource(policy.handler())
+    }
+
     pub fn add_thread_local_resource<T>(&mut self, resource: T) -> &mut Self
     where
         T: 'static,
@@ -251,6 +311,34 @@ impl AppBuilder {
         self
     }
 
+    /// Chooses how components of type `T` are stored in the [App]'s world. See
+    /// [World::register_component].
+    pub fn register_component<T: Component>(&mut self, storage_type: StorageType) -> &mut Self {
+        self.app.world.register_component::<T>(storage_type);
+        self
+    }
+
+    /// Makes the [App]'s world keep a reverse index of the `Relation<K>` components. See
+    /// [World::register_relation].
+    pub fn register_relation<K: 'static>(&mut self) -> &mut Self {
+        self.app.world.register_relation::<K>();
+        self
+    }
+
+    /// Makes [World::clone_entity] copy components of type `T` in the [App]'s world. See
+    /// [World::register_clone].
+    pub fn register_clone<T: Component + Clone>(&mut self) -> &mut Self {
+        self.app.world.register_clone::<T>();
+        self
+    }
+
+    /// Makes [World::clone_entity] leave out components of type `T` in the [App]'s world. See
+    /// [World::ignore_on_clone].
+    pub fn ignore_on_clone<T: Component>(&mut self) -> &mut Self {
+        self.app.world.ignore_on_clone::<T>();
+        self
+    }
+
     pub fn set_runner(&mut self, run_fn: impl Fn(App) + 'static) -> &mut Self {
         self.app.runner = Box::new(run_fn);
         self
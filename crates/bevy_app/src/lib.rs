@@ -9,6 +9,7 @@ mod event;
 mod plugin;
 mod plugin_group;
 mod schedule_runner;
+mod system_error;
 
 pub use app::*;
 pub use app_builder::*;
@@ -17,6 +18,7 @@ pub use event::*;
 pub use plugin::*;
 pub use plugin_group::*;
 pub use schedule_runner::*;
+pub use system_error::*;
 
 pub mod prelude {
     pub use crate::{
@@ -4,6 +4,10 @@ pub const STARTUP: &str = "startup";
 /// Name of app stage that runs before all other app stages
 pub const FIRST: &str = "first";
 
+/// Name of app stage that applies queued `State` transitions, so that every later stage of a frame
+/// sees the same state. Runs after FIRST.
+pub const STATE_TRANSITIONS: &str = "state_transitions";
+
 /// Name of app stage that runs before EVENT
 pub const PRE_EVENT: &str = "pre_event";
 
@@ -0,0 +1,66 @@
+use crate::Events;
+use bevy_ecs::{Resources, SystemError, SystemErrorHandler};
+
+/// What an App does with the errors returned by systems returning `anyhow::Result<()>`. See
+/// `AppBuilder::set_system_error_policy`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SystemErrorPolicy {
+    /// Log every error. This is the default.
+    Log,
+    /// Panic on the first error
+    Panic,
+    /// Send every error as an `Events<SystemError>` event
+    SendEvent,
+}
+
+impl Default for SystemErrorPolicy {
+    fn default() -> Self {
+        SystemErrorPolicy::Log
+    }
+}
+
+impl SystemErrorPolicy {
+    pub(crate) fn handler(self) -> SystemErrorHandler {
+        match self {
+            SystemErrorPolicy::Log => SystemErrorHandler::log(),
+            SystemErrorPolicy::Panic => SystemErrorHandler::panic(),
+            SystemErrorPolicy::SendEvent => SystemErrorHandler(send_system_error),
+        }
+    }
+}
+
+fn send_system_error(error: SystemError, resources: &mut Resources) {
+    resources
+        .get_mut::<Events<SystemError>>()
+        .expect("the `SendEvent` system error policy requires `Events<SystemError>`")
+        .send(error);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SystemErrorPolicy;
+    use crate::{App, Events};
+    use bevy_ecs::{IntoSystem, SystemError};
+
+    fn fails() -> anyhow::Result<()> {
+        anyhow::bail!("failed on purpose")
+    }
+
+    #[test]
+    fn send_system_errors_as_events() {
+        let mut app = App::build();
+        app.set_system_error_policy(SystemErrorPolicy::SendEvent)
+            .add_system(fails.system());
+        let mut app = app.app;
+        app.update();
+
+        let events = app.resources.get::<Events<SystemError>>().unwrap();
+        let errors = events.iter_current_update_events().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].system_name,
+            "bevy_app::system_error::tests::fails"
+        );
+        assert_eq!(errors[0].error.to_string(), "failed on purpose");
+    }
+}
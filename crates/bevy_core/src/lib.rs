@@ -1,21 +1,24 @@
 mod bytes;
 mod float_ord;
 mod label;
+mod rng;
 mod task_pool_options;
 mod time;
 
 use std::ops::Range;
 
-use bevy_ecs::IntoSystem;
+use bevy_ecs::{DeterministicExecution, IntoSystem};
 use bevy_reflect::RegisterTypeBuilder;
+use bevy_utils::Duration;
 pub use bytes::*;
 pub use float_ord::*;
 pub use label::*;
+pub use rng::*;
 pub use task_pool_options::DefaultTaskPoolOptions;
 pub use time::*;
 
 pub mod prelude {
-    pub use crate::{DefaultTaskPoolOptions, EntityLabels, Labels, Time, Timer};
+    pub use crate::{DefaultTaskPoolOptions, EntityLabels, GlobalRng, Labels, Time, Timer};
 }
 
 use bevy_app::prelude::*;
@@ -32,9 +35,15 @@ impl Plugin for CorePlugin {
             .unwrap_or_else(DefaultTaskPoolOptions::default)
             .create_default_pools(app.resources_mut());
 
+        // a seeded generator may have been added by DeterministicPlugin
+        if !app.resources().contains::<GlobalRng>() {
+            app.init_resource::<GlobalRng>();
+        }
+
         app.init_resource::<Time>()
             .init_resource::<EntityLabels>()
             .init_resource::<FixedTimesteps>()
+            .init_resource::<FixedTime>()
             .register_type::<Option<String>>()
             .register_type::<Range<f32>>()
             .register_type::<Timer>()
@@ -42,3 +51,30 @@ impl Plugin for CorePlugin {
             .add_system_to_stage(stage::PRE_UPDATE, entity_labels_system.system());
     }
 }
+
+/// Makes Apps reproducible, so that the same inputs lead to the same world on every run: the
+/// systems of each stage run one at a time in a fixed order (see [`DeterministicExecution`]),
+/// [`Time`] advances by `step` every update (see [`ManualClock`]) and [`GlobalRng`] is seeded with
+/// `seed`.
+#[derive(Debug, Clone, Copy)]
+pub struct DeterministicPlugin {
+    pub seed: u64,
+    pub step: Duration,
+}
+
+impl Default for DeterministicPlugin {
+    fn default() -> Self {
+        DeterministicPlugin {
+            seed: 0,
+            step: Duration::from_secs_f64(1.0 / 60.0),
+        }
+    }
+}
+
+impl Plugin for DeterministicPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(DeterministicExecution)
+            .add_resource(ManualClock { delta: self.step })
+            .add_resource(GlobalRng::with_seed(self.seed));
+    }
+}
@@ -0,0 +1,66 @@
+use rand::{rngs::StdRng, Error, RngCore, SeedableRng};
+
+/// A random number generator shared by the systems of an App
+///
+/// It is seeded by the operating system by default. Once seeded with [`GlobalRng::with_seed`],
+/// it produces the same numbers on every run, as long as systems draw from it in the same order.
+/// Systems that need their own generator can [`GlobalRng::fork`] one from it.
+#[derive(Debug, Clone)]
+pub struct GlobalRng(StdRng);
+
+impl Default for GlobalRng {
+    fn default() -> Self {
+        GlobalRng(StdRng::from_entropy())
+    }
+}
+
+impl GlobalRng {
+    pub fn with_seed(seed: u64) -> Self {
+        GlobalRng(StdRng::seed_from_u64(seed))
+    }
+
+    /// Creates a new generator seeded from this one
+    pub fn fork(&mut self) -> StdRng {
+        StdRng::from_rng(&mut self.0).expect("StdRng can always be seeded from another StdRng")
+    }
+}
+
+impl RngCore for GlobalRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GlobalRng;
+    use rand::Rng;
+
+    #[test]
+    fn seeded_rng_is_reproducible() {
+        let mut a = GlobalRng::with_seed(42);
+        let mut b = GlobalRng::with_seed(42);
+        let numbers = (0..8).map(|_| a.gen::<u32>()).collect::<Vec<_>>();
+        assert_eq!(numbers, (0..8).map(|_| b.gen::<u32>()).collect::<Vec<_>>());
+
+        let (mut fork_a, mut fork_b) = (a.fork(), b.fork());
+        assert_eq!(fork_a.gen::<u64>(), fork_b.gen::<u64>());
+        assert_eq!(a.gen::<u64>(), b.gen::<u64>());
+        assert_ne!(
+            GlobalRng::with_seed(1).gen::<u64>(),
+            GlobalRng::with_seed(2).gen::<u64>()
+        );
+    }
+}
@@ -1,11 +1,65 @@
 use crate::Time;
 use bevy_ecs::{ArchetypeComponent, ShouldRun, System, SystemId, ThreadLocalExecution, TypeAccess};
-use bevy_utils::HashMap;
+use bevy_utils::{Duration, HashMap};
 use std::{any::TypeId, borrow::Cow};
 
+/// Tracks time in fixed steps. It is advanced by one step every time a [`FixedTimestep`] lets
+/// its systems run, so systems driven by a fixed timestep can read it instead of [`Time`].
+///
+/// When several fixed timesteps are used, it reflects the one currently running: each timestep
+/// counts its own steps and elapsed time.
+#[derive(Debug, Default, Clone)]
+pub struct FixedTime {
+    delta: Duration,
+    delta_seconds_f64: f64,
+    seconds_since_startup: f64,
+    steps: u64,
+}
+
+impl FixedTime {
+    fn advance(&mut self, step: f64) {
+        self.delta = Duration::from_secs_f64(step);
+        self.delta_seconds_f64 = step;
+        self.seconds_since_startup += step;
+        self.steps += 1;
+    }
+
+    /// The length of the current step as a [`Duration`]
+    #[inline]
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// The length of the current step as [`f32`] seconds
+    #[inline]
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta_seconds_f64 as f32
+    }
+
+    /// The length of the current step as [`f64`] seconds
+    #[inline]
+    pub fn delta_seconds_f64(&self) -> f64 {
+        self.delta_seconds_f64
+    }
+
+    /// The sum of all steps taken so far by the current timestep, in seconds
+    #[inline]
+    pub fn seconds_since_startup(&self) -> f64 {
+        self.seconds_since_startup
+    }
+
+    /// The number of steps taken so far by the current timestep
+    #[inline]
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
 pub struct FixedTimestepState {
     pub step: f64,
     pub accumulator: f64,
+    pub max_steps: Option<u32>,
+    pub steps_this_frame: u32,
 }
 
 impl FixedTimestepState {
@@ -28,6 +82,22 @@ impl FixedTimestepState {
     pub fn overstep_percentage(&self) -> f64 {
         self.accumulator / self.step
     }
+
+    /// The interpolation factor between the previous and the current fixed step, in `[0, 1]`.
+    /// Rendering can use it to blend between the last two simulated states.
+    pub fn alpha(&self) -> f64 {
+        self.overstep_percentage().min(1.0)
+    }
+
+    /// The maximum number of steps run in a single frame, if any
+    pub fn max_steps(&self) -> Option<u32> {
+        self.max_steps
+    }
+
+    /// The number of steps run during the last frame
+    pub fn steps_this_frame(&self) -> u32 {
+        self.steps_this_frame
+    }
 }
 
 #[derive(Default)]
@@ -39,12 +109,21 @@ impl FixedTimesteps {
     pub fn get(&self, name: &str) -> Option<&FixedTimestepState> {
         self.fixed_timesteps.get(name)
     }
+
+    /// The interpolation factor of the fixed timestep with the given label. See
+    /// [`FixedTimestepState::alpha`].
+    pub fn alpha(&self, name: &str) -> Option<f64> {
+        self.get(name).map(FixedTimestepState::alpha)
+    }
 }
 
 pub struct FixedTimestep {
     step: f64,
     accumulator: f64,
+    max_steps: Option<u32>,
+    steps_this_frame: u32,
     looping: bool,
+    time: FixedTime,
     system_id: SystemId,
     label: Option<String>, // TODO: consider making this a TypedLabel
     resource_access: TypeAccess<TypeId>,
@@ -57,7 +136,10 @@ impl Default for FixedTimestep {
             system_id: SystemId::new(),
             step: 1.0 / 60.0,
             accumulator: 0.0,
+            max_steps: None,
+            steps_this_frame: 0,
             looping: false,
+            time: FixedTime::default(),
             label: None,
             resource_access: Default::default(),
             archetype_access: Default::default(),
@@ -85,13 +167,32 @@ impl FixedTimestep {
         self
     }
 
+    /// Limits the number of steps run in a single frame. When a frame takes long enough to need
+    /// more steps, the extra time is dropped instead of being caught up on in later frames, which
+    /// would otherwise make every following frame slower.
+    ///
+    /// # Panics
+    /// Panics if `max_steps` is zero.
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        assert!(max_steps > 0, "max_steps must be greater than zero");
+        self.max_steps = Some(max_steps);
+        self
+    }
+
     pub fn update(&mut self, time: &Time) -> ShouldRun {
         if !self.looping {
             self.accumulator += time.delta_seconds_f64();
+            self.steps_this_frame = 0;
+        }
+
+        if matches!(self.max_steps, Some(max_steps) if self.steps_this_frame >= max_steps) {
+            // keep the overstep so the interpolation alpha stays meaningful
+            self.accumulator %= self.step;
         }
 
         if self.accumulator >= self.step {
             self.accumulator -= self.step;
+            self.steps_this_frame += 1;
             self.looping = true;
             ShouldRun::YesAndLoop
         } else {
@@ -135,11 +236,17 @@ impl System for FixedTimestep {
     ) -> Option<Self::Out> {
         let time = resources.get::<Time>().unwrap();
         let result = self.update(&time);
+        if let ShouldRun::YesAndLoop = result {
+            self.time.advance(self.step);
+            *resources.get_mut::<FixedTime>().unwrap() = self.time.clone();
+        }
         if let Some(ref label) = self.label {
             let mut fixed_timesteps = resources.get_mut::<FixedTimesteps>().unwrap();
             let state = fixed_timesteps.fixed_timesteps.get_mut(label).unwrap();
             state.step = self.step;
             state.accumulator = self.accumulator;
+            state.max_steps = self.max_steps;
+            state.steps_this_frame = self.steps_this_frame;
         }
 
         Some(result)
@@ -154,6 +261,8 @@ impl System for FixedTimestep {
 
     fn initialize(&mut self, _world: &mut bevy_ecs::World, resources: &mut bevy_ecs::Resources) {
         self.resource_access.add_read(TypeId::of::<Time>());
+        self.resource_access.add_write(TypeId::of::<FixedTime>());
+        resources.get_or_insert_with(FixedTime::default);
         if let Some(ref label) = self.label {
             let mut fixed_timesteps = resources.get_mut::<FixedTimesteps>().unwrap();
             fixed_timesteps.fixed_timesteps.insert(
@@ -161,8 +270,127 @@ impl System for FixedTimestep {
                 FixedTimestepState {
                     accumulator: 0.0,
                     step: self.step,
+                    max_steps: self.max_steps,
+                    steps_this_frame: 0,
                 },
             );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::{Resources, World};
+    use bevy_utils::Instant;
+
+    const LABEL: &str = "fixed";
+
+    /// Runs the timestep for one frame of `delta` seconds and returns how many steps it allowed
+    fn run_frame(
+        timestep: &mut FixedTimestep,
+        world: &mut World,
+        resources: &mut Resources,
+        delta: f64,
+    ) -> u32 {
+        {
+            let mut time = resources.get_mut::<Time>().unwrap();
+            let last_update = time.last_update().unwrap();
+            time.update_with_instant(last_update + Duration::from_secs_f64(delta));
+        }
+        let mut steps = 0;
+        while let Some(ShouldRun::YesAndLoop) = timestep.run((), world, resources) {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn fixed_timestep() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let mut time = Time::default();
+        time.update_with_instant(Instant::now());
+        resources.insert(time);
+        resources.insert(FixedTimesteps::default());
+
+        let mut timestep = FixedTimestep::step(0.5).with_max_steps(3).with_label(LABEL);
+        timestep.initialize(&mut world, &mut resources);
+
+        assert_eq!(
+            run_frame(&mut timestep, &mut world, &mut resources, 0.25),
+            0
+        );
+        assert_eq!(
+            resources.get::<FixedTimesteps>().unwrap().alpha(LABEL),
+            Some(0.5)
+        );
+
+        assert_eq!(run_frame(&mut timestep, &mut world, &mut resources, 1.0), 2);
+        assert_eq!(
+            resources.get::<FixedTimesteps>().unwrap().alpha(LABEL),
+            Some(0.5)
+        );
+        {
+            let fixed_time = resources.get::<FixedTime>().unwrap();
+            assert_eq!(fixed_time.delta(), Duration::from_millis(500));
+            assert_eq!(fixed_time.seconds_since_startup(), 1.0);
+            assert_eq!(fixed_time.steps(), 2);
+        }
+
+        // a long frame only runs up to the maximum and drops the time it can't catch up on
+        assert_eq!(
+            run_frame(&mut timestep, &mut world, &mut resources, 10.0),
+            3
+        );
+        let fixed_timesteps = resources.get::<FixedTimesteps>().unwrap();
+        let state = fixed_timesteps.get(LABEL).unwrap();
+        assert_eq!(state.steps_this_frame(), 3);
+        assert_eq!(state.alpha(), 0.5);
+        assert_eq!(resources.get::<FixedTime>().unwrap().steps(), 5);
+
+        drop(fixed_timesteps);
+        assert_eq!(run_frame(&mut timestep, &mut world, &mut resources, 0.0), 0);
+    }
+
+    #[test]
+    fn fixed_time_per_timestep() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let mut time = Time::default();
+        time.update_with_instant(Instant::now());
+        resources.insert(time);
+
+        let mut slow = FixedTimestep::step(0.5);
+        let mut fast = FixedTimestep::step(0.25);
+        slow.initialize(&mut world, &mut resources);
+        fast.initialize(&mut world, &mut resources);
+
+        assert_eq!(run_frame(&mut slow, &mut world, &mut resources, 1.0), 2);
+        {
+            let fixed_time = resources.get::<FixedTime>().unwrap();
+            assert_eq!(fixed_time.steps(), 2);
+            assert_eq!(fixed_time.seconds_since_startup(), 1.0);
+        }
+
+        assert_eq!(run_frame(&mut fast, &mut world, &mut resources, 1.0), 4);
+        {
+            let fixed_time = resources.get::<FixedTime>().unwrap();
+            assert_eq!(fixed_time.delta(), Duration::from_millis(250));
+            assert_eq!(fixed_time.steps(), 4);
+            assert_eq!(fixed_time.seconds_since_startup(), 1.0);
+        }
+
+        assert_eq!(run_frame(&mut slow, &mut world, &mut resources, 0.5), 1);
+        let fixed_time = resources.get::<FixedTime>().unwrap();
+        assert_eq!(fixed_time.delta(), Duration::from_millis(500));
+        assert_eq!(fixed_time.steps(), 3);
+        assert_eq!(fixed_time.seconds_since_startup(), 1.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_max_steps() {
+        FixedTimestep::step(1.0).with_max_steps(0);
+    }
+}
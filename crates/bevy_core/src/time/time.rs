@@ -1,4 +1,4 @@
-use bevy_ecs::ResMut;
+use bevy_ecs::{Res, ResMut};
 use bevy_utils::{Duration, Instant};
 
 /// Tracks elapsed time since the last update and since the App has started
@@ -31,6 +31,13 @@ impl Time {
         self.update_with_instant(now);
     }
 
+    /// Updates the time as if `delta` had passed since the last update, without reading the wall
+    /// clock. The first call measures `delta` from [`Time::startup`].
+    pub fn advance(&mut self, delta: Duration) {
+        let last_update = *self.last_update.get_or_insert(self.startup);
+        self.update_with_instant(last_update + delta);
+    }
+
     pub(crate) fn update_with_instant(&mut self, instant: Instant) {
         if let Some(last_update) = self.last_update {
             self.delta = instant - last_update;
@@ -84,8 +91,19 @@ impl Time {
     }
 }
 
-pub(crate) fn time_system(mut time: ResMut<Time>) {
-    time.update();
+/// While this resource exists, [`Time`] is advanced by `delta` every update instead of following
+/// the wall clock, so that every run of an app sees the same times. `delta` can be changed between
+/// updates, for example to replay the frame times of a recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ManualClock {
+    pub delta: Duration,
+}
+
+pub(crate) fn time_system(mut time: ResMut<Time>, clock: Option<Res<ManualClock>>) {
+    match clock {
+        Some(clock) => time.advance(clock.delta),
+        None => time.update(),
+    }
 }
 
 #[cfg(test)]
@@ -143,4 +161,20 @@ mod tests {
         );
         assert_eq!(time.delta_seconds(), time.delta().as_secs_f32());
     }
+
+    #[test]
+    fn advance_test() {
+        let mut time = Time::default();
+        time.advance(Duration::from_millis(250));
+        assert_eq!(time.delta(), Duration::from_millis(250));
+        assert_eq!(time.seconds_since_startup(), 0.25);
+
+        time.advance(Duration::from_millis(500));
+        assert_eq!(time.delta_seconds(), 0.5);
+        assert_eq!(time.seconds_since_startup(), 0.75);
+        assert_eq!(
+            time.last_update(),
+            Some(time.startup() + Duration::from_millis(750))
+        );
+    }
 }
@@ -62,6 +62,29 @@ fn spawn_batch(b: &mut Bencher) {
     });
 }
 
+fn spawn_then_insert(b: &mut Bencher) {
+    let mut world = World::new();
+    b.iter(|| {
+        let entity = world.spawn((Position(0.0),));
+        world.insert_one(entity, Velocity(0.0)).unwrap();
+    });
+}
+
+fn insert_remove(b: &mut Bencher) {
+    let mut world = World::new();
+    let entities = (0..1_000)
+        .map(|_| world.spawn((Position(0.0),)))
+        .collect::<Vec<_>>();
+    b.iter(|| {
+        for &entity in &entities {
+            world.insert_one(entity, Velocity(0.0)).unwrap();
+        }
+        for &entity in &entities {
+            world.remove_one::<Velocity>(entity).unwrap();
+        }
+    });
+}
+
 fn iterate_100k(b: &mut Bencher) {
     let mut world = World::new();
     for i in 0..100_000 {
@@ -88,6 +111,8 @@ benchmark_group!(
     spawn_tuple,
     spawn_static,
     spawn_batch,
+    spawn_then_insert,
+    insert_remove,
     iterate_100k,
     build
 );
@@ -20,11 +20,11 @@ use std::borrow::Cow;
 
 use find_crate::Manifest;
 use proc_macro::TokenStream;
-use proc_macro2::{Span, TokenStream as TokenStream2};
-use quote::quote;
+use proc_macro2::{Group, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{quote, ToTokens};
 use syn::{
     parse::ParseStream, parse_macro_input, Data, DataStruct, DeriveInput, Error, Field, Fields,
-    Ident, Index, Lifetime, Path, Result,
+    GenericParam, Ident, Index, Lifetime, Path, Result, Type,
 };
 
 /// Implement `Bundle` for a monomorphic struct
@@ -54,17 +54,7 @@ fn derive_bundle_(input: DeriveInput) -> Result<TokenStream2> {
         }
     };
     let (tys, field_members) = struct_fields(&data.fields);
-    let manifest = Manifest::new().unwrap();
-    let path_str = if let Some(package) = manifest.find(|name| name == "bevy") {
-        format!("{}::ecs", package.name)
-    } else if let Some(package) = manifest.find(|name| name == "bevy_internal") {
-        format!("{}::ecs", package.name)
-    } else if let Some(package) = manifest.find(|name| name == "bevy_ecs") {
-        package.name
-    } else {
-        "bevy_ecs".to_string()
-    };
-    let crate_path: Path = syn::parse(path_str.parse::<TokenStream>().unwrap()).unwrap();
+    let crate_path = bevy_ecs_path();
     let field_idents = member_as_idents(&field_members);
     let generics = add_additional_bounds_to_generic_params(&crate_path, input.generics);
 
@@ -87,6 +77,20 @@ fn derive_bundle_(input: DeriveInput) -> Result<TokenStream2> {
     Ok(ts)
 }
 
+fn bevy_ecs_path() -> Path {
+    let manifest = Manifest::new().unwrap();
+    let path_str = if let Some(package) = manifest.find(|name| name == "bevy") {
+        format!("{}::ecs", package.name)
+    } else if let Some(package) = manifest.find(|name| name == "bevy_internal") {
+        format!("{}::ecs", package.name)
+    } else if let Some(package) = manifest.find(|name| name == "bevy_ecs") {
+        package.name
+    } else {
+        "bevy_ecs".to_string()
+    };
+    syn::parse(path_str.parse::<TokenStream>().unwrap()).unwrap()
+}
+
 fn gen_dynamic_bundle_impl(
     crate_path: &syn::Path,
     ident: &syn::Ident,
@@ -314,12 +318,14 @@ pub fn impl_query_set(_input: TokenStream) -> TokenStream {
         let query_fn_mut = &query_fn_muts[0..query_count];
         tokens.extend(TokenStream::from(quote! {
             impl<#(#lifetime,)* #(#query: WorldQuery,)* #(#filter: QueryFilter,)*> QueryTuple for (#(Query<#lifetime, #query, #filter>,)*) {
-                unsafe fn new(world: &World, component_access: &TypeAccess<ArchetypeComponent>) -> Self {
+                unsafe fn new(world: &World, component_access: &TypeAccess<ArchetypeComponent>, last_change_tick: u32, change_tick: u32) -> Self {
                     (
                         #(
                             Query::<#query, #filter>::new(
                                 std::mem::transmute(world),
                                 std::mem::transmute(component_access),
+                                last_change_tick,
+                                change_tick,
                             ),
                         )*
                     )
@@ -421,7 +427,7 @@ pub fn derive_system_param(input: TokenStream) -> TokenStream {
 
         impl #impl_generics #path::FetchSystemParam<'a> for #fetch_struct_name {
             type Item = #struct_name#ty_generics;
-            fn init(system_state: &mut #path::SystemState, world: &#path::World, resources: &mut #path::Resources) {
+            fn init(system_state: &mut #path::SystemState, world: &mut #path::World, resources: &mut #path::Resources) {
                 #(<<#field_types as SystemParam>::Fetch as #path::FetchSystemParam>::init(system_state, world, resources);)*
             }
 
@@ -438,3 +444,176 @@ pub fn derive_system_param(input: TokenStream) -> TokenStream {
         }
     })
 }
+
+/// Implement `WorldQuery` for a struct whose fields are queries
+///
+/// Each field must be a query whose item is the field's own type, like `Entity`, `&'a T`,
+/// `Mut<'a, T>`, `Option<&'a T>` or another derived query. Querying for the struct then returns
+/// it with every field fetched, and its access is the union of the fields' accesses. The struct
+/// may have at most one lifetime parameter, used for the borrowed fields.
+#[proc_macro_derive(WorldQuery)]
+pub fn derive_world_query(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match derive_world_query_(input) {
+        Ok(ts) => ts,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+fn derive_world_query_(input: DeriveInput) -> Result<TokenStream2> {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "derive(WorldQuery) only supports structs with named fields",
+            ))
+        }
+    };
+
+    let mut lifetime = None;
+    for param in input.generics.params.iter() {
+        match param {
+            GenericParam::Lifetime(def) if lifetime.is_none() => {
+                lifetime = Some(def.lifetime.clone())
+            }
+            _ => return Err(Error::new_spanned(
+                param,
+                "derive(WorldQuery) supports at most one lifetime parameter and no type parameters",
+            )),
+        }
+    }
+
+    let path = bevy_ecs_path();
+    let mut field_idents = Vec::new();
+    let mut fetch_tys = Vec::new();
+    let mut read_only_bounds = Vec::new();
+    for field in fields.iter() {
+        if let Type::Reference(reference) = &field.ty {
+            if reference.mutability.is_some() {
+                return Err(Error::new_spanned(
+                    &field.ty,
+                    "mutable fields must use `Mut<'a, T>` instead of `&'a mut T`",
+                ));
+            }
+        }
+        let ty = field.ty.to_token_stream();
+        let (static_ty, bound_ty) = match &lifetime {
+            Some(lifetime) => (
+                replace_lifetime(ty.clone(), &lifetime.ident, "static"),
+                replace_lifetime(ty, &lifetime.ident, "__w"),
+            ),
+            None => (ty.clone(), ty),
+        };
+        field_idents.push(field.ident.as_ref().unwrap());
+        fetch_tys.push(quote! { <#static_ty as #path::WorldQuery>::Fetch });
+        // bounds naming the impl's lifetime are checked where the impl is used rather than where
+        // it is declared, so queries with mutable fields simply aren't read only
+        read_only_bounds
+            .push(quote! { <#bound_ty as #path::WorldQuery>::Fetch: #path::ReadOnlyFetch });
+    }
+
+    let vis = &input.vis;
+    let fetch_ident = Ident::new(&format!("Fetch{}", ident), Span::call_site());
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let item_ty = match &lifetime {
+        Some(_) => quote! { #ident<'__w> },
+        None => quote! { #ident },
+    };
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #vis struct #fetch_ident {
+            #(#field_idents: #fetch_tys,)*
+        }
+
+        impl #impl_generics #path::WorldQuery for #ident #ty_generics #where_clause {
+            type Fetch = #fetch_ident;
+        }
+
+        impl<'__w> #path::Fetch<'__w> for #fetch_ident {
+            type Item = #item_ty;
+
+            const DANGLING: Self = #fetch_ident {
+                #(#field_idents: <#fetch_tys as #path::Fetch<'__w>>::DANGLING,)*
+            };
+
+            fn access() -> #path::QueryAccess {
+                #path::QueryAccess::union(vec![
+                    #(<#fetch_tys as #path::Fetch<'__w>>::access(),)*
+                ])
+            }
+
+            #[inline]
+            fn includes_disabled() -> bool {
+                false #(|| <#fetch_tys as #path::Fetch<'__w>>::includes_disabled())*
+            }
+
+            unsafe fn get(
+                archetype: &'__w #path::Archetype,
+                sparse_sets: &'__w #path::SparseSets,
+                offset: usize,
+                last_change_tick: u32,
+                change_tick: u32,
+            ) -> Option<Self> {
+                Some(#fetch_ident {
+                    #(#field_idents: <#fetch_tys as #path::Fetch<'__w>>::get(
+                        archetype,
+                        sparse_sets,
+                        offset,
+                        last_change_tick,
+                        change_tick,
+                    )?,)*
+                })
+            }
+
+            #[inline]
+            unsafe fn matches_entity(&self, n: usize) -> bool {
+                true #(&& <#fetch_tys as #path::Fetch<'__w>>::matches_entity(&self.#field_idents, n))*
+            }
+
+            #[inline]
+            unsafe fn fetch(&self, n: usize) -> Self::Item {
+                #ident {
+                    #(#field_idents: <#fetch_tys as #path::Fetch<'__w>>::fetch(&self.#field_idents, n),)*
+                }
+            }
+        }
+
+        unsafe impl<'__w> #path::ReadOnlyFetch for #fetch_ident
+        where
+            #(#read_only_bounds,)*
+        {}
+    })
+}
+
+/// Replaces every use of the lifetime `'from` in `tokens` with `'to`
+fn replace_lifetime(tokens: TokenStream2, from: &Ident, to: &str) -> TokenStream2 {
+    let mut after_apostrophe = false;
+    tokens
+        .into_iter()
+        .map(|token| {
+            let token = match token {
+                TokenTree::Group(group) => {
+                    let mut replaced = Group::new(
+                        group.delimiter(),
+                        replace_lifetime(group.stream(), from, to),
+                    );
+                    replaced.set_span(group.span());
+                    TokenTree::Group(replaced)
+                }
+                TokenTree::Ident(ident) if after_apostrophe && ident == *from => {
+                    TokenTree::Ident(Ident::new(to, ident.span()))
+                }
+                token => token,
+            };
+            after_apostrophe = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '\'');
+            token
+        })
+        .collect()
+}
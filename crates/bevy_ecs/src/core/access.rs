@@ -1,7 +1,7 @@
 use bevy_utils::HashSet;
 use std::{any::TypeId, boxed::Box, hash::Hash, vec::Vec};
 
-use super::{Archetype, World};
+use super::{Archetype, SparseSets, World};
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum Access {
@@ -77,7 +77,7 @@ impl QueryAccess {
         let archetypes = world.archetypes();
         for (i, archetype) in archetypes.enumerate() {
             let type_access = type_access.as_deref_mut();
-            let _ = self.get_access(archetype, i as u32, type_access);
+            let _ = self.get_access(archetype, i as u32, world.sparse_sets(), type_access);
         }
     }
 
@@ -115,16 +115,21 @@ impl QueryAccess {
 
     /// Returns how this [QueryAccess] accesses the given `archetype`.
     /// If `type_access` is set, it will populate type access with the types this query reads/writes
+    ///
+    /// Components stored in `sparse_sets` may belong to any entity, so they count as part of every
+    /// archetype.
     pub fn get_access(
         &self,
         archetype: &Archetype,
         archetype_index: u32,
+        sparse_sets: &SparseSets,
         type_access: Option<&mut TypeAccess<ArchetypeComponent>>,
     ) -> Option<Access> {
+        let has_type = |ty: TypeId| archetype.has_type(ty) || sparse_sets.contains(ty);
         match self {
             QueryAccess::None => Some(Access::None),
             QueryAccess::Read(ty, _) => {
-                if archetype.has_type(*ty) {
+                if has_type(*ty) {
                     if let Some(type_access) = type_access {
                         type_access.add_read(ArchetypeComponent::new_ty(archetype_index, *ty));
                     }
@@ -134,7 +139,7 @@ impl QueryAccess {
                 }
             }
             QueryAccess::Write(ty, _) => {
-                if archetype.has_type(*ty) {
+                if has_type(*ty) {
                     if let Some(type_access) = type_access {
                         type_access.add_write(ArchetypeComponent::new_ty(archetype_index, *ty));
                     }
@@ -144,10 +149,17 @@ impl QueryAccess {
                 }
             }
             QueryAccess::Optional(query_access) => {
-                if let Some(access) = query_access.get_access(archetype, archetype_index, None) {
+                if let Some(access) =
+                    query_access.get_access(archetype, archetype_index, sparse_sets, None)
+                {
                     // only re-run get_archetype_access if we need to set type_access
                     if type_access.is_some() {
-                        query_access.get_access(archetype, archetype_index, type_access)
+                        query_access.get_access(
+                            archetype,
+                            archetype_index,
+                            sparse_sets,
+                            type_access,
+                        )
                     } else {
                         Some(access)
                     }
@@ -156,15 +168,15 @@ impl QueryAccess {
                 }
             }
             QueryAccess::With(ty, query_access) => {
-                if archetype.has_type(*ty) {
-                    query_access.get_access(archetype, archetype_index, type_access)
+                if has_type(*ty) {
+                    query_access.get_access(archetype, archetype_index, sparse_sets, type_access)
                 } else {
                     None
                 }
             }
             QueryAccess::Without(ty, query_access) => {
                 if !archetype.has_type(*ty) {
-                    query_access.get_access(archetype, archetype_index, type_access)
+                    query_access.get_access(archetype, archetype_index, sparse_sets, type_access)
                 } else {
                     None
                 }
@@ -172,7 +184,8 @@ impl QueryAccess {
             QueryAccess::Union(query_accesses) => {
                 let mut result = None;
                 for query_access in query_accesses {
-                    if let Some(access) = query_access.get_access(archetype, archetype_index, None)
+                    if let Some(access) =
+                        query_access.get_access(archetype, archetype_index, sparse_sets, None)
                     {
                         result = Some(result.unwrap_or(Access::Read).max(access));
                     } else {
@@ -184,7 +197,12 @@ impl QueryAccess {
                 if let Some(type_access) = type_access {
                     if result.is_some() {
                         for query_access in query_accesses {
-                            query_access.get_access(archetype, archetype_index, Some(type_access));
+                            query_access.get_access(
+                                archetype,
+                                archetype_index,
+                                sparse_sets,
+                                Some(type_access),
+                            );
                         }
                     }
                 }
@@ -282,7 +300,7 @@ impl<T: Hash + Eq + PartialEq + Copy> TypeAccess<T> {
 #[cfg(test)]
 mod tests {
     use super::{ArchetypeComponent, TypeAccess};
-    use crate::{core::World, Entity, Fetch, QueryAccess, WorldQuery};
+    use crate::{core::World, Entity, Fetch, QueryAccess, StorageType, WorldQuery};
     use std::vec;
 
     struct A;
@@ -362,4 +380,39 @@ mod tests {
             TypeAccess::new(vec![e2_a, e3_a], vec![e3_c])
         );
     }
+
+    #[test]
+    fn sparse_set_type_access() {
+        let mut world = World::default();
+        world.register_component::<B>(StorageType::SparseSet);
+        let e1 = world.spawn((A,));
+        let e2 = world.spawn((A, B));
+        let e3 = world.spawn((C,));
+
+        // entities with and without B share an archetype, and B is not part of it
+        let a_archetype = world.get_entity_location(e1).unwrap().archetype;
+        assert_eq!(
+            world.get_entity_location(e2).unwrap().archetype,
+            a_archetype
+        );
+        let c_archetype = world.get_entity_location(e3).unwrap().archetype;
+
+        let a_b = ArchetypeComponent::new::<B>(a_archetype);
+        let c_b = ArchetypeComponent::new::<B>(c_archetype);
+
+        // components stored in sparse sets may belong to an entity of any archetype
+        let mut bmut_type_access = TypeAccess::default();
+        <(Entity, &mut B) as WorldQuery>::Fetch::access()
+            .get_world_archetype_access(&world, Some(&mut bmut_type_access));
+        assert!(bmut_type_access.is_write(&a_b));
+        assert!(bmut_type_access.is_write(&c_b));
+
+        let mut a_with_b_type_access = TypeAccess::default();
+        QueryAccess::with::<B>(<&A as WorldQuery>::Fetch::access())
+            .get_world_archetype_access(&world, Some(&mut a_with_b_type_access));
+        assert_eq!(
+            a_with_b_type_access,
+            TypeAccess::new(vec![ArchetypeComponent::new::<A>(a_archetype)], vec![])
+        );
+    }
 }
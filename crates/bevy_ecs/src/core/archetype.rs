@@ -16,7 +16,6 @@
 
 use crate::{AtomicBorrow, Component, Entity};
 use bevy_utils::AHasher;
-use bitflags::bitflags;
 use std::{
     alloc::{alloc, dealloc, Layout},
     any::{type_name, TypeId},
@@ -42,6 +41,7 @@ pub struct Archetype {
     data: UnsafeCell<NonNull<u8>>,
     data_size: usize,
     grow_size: usize,
+    edges: ArchetypeEdges,
 }
 
 impl Archetype {
@@ -83,6 +83,7 @@ impl Archetype {
             data: UnsafeCell::new(NonNull::dangling()),
             data_size: 0,
             grow_size,
+            edges: ArchetypeEdges::default(),
         }
     }
 
@@ -252,6 +253,29 @@ impl Archetype {
         self.len - 1
     }
 
+    /// Drops the component of type `ty` at `index`. Returns false if this archetype doesn't store
+    /// components of that type.
+    ///
+    /// # Safety
+    /// `index` must be in bounds, and the component must be overwritten before it is read again.
+    pub(crate) unsafe fn drop_dynamic(&mut self, ty: TypeId, index: usize) -> bool {
+        let info = match self.types.iter().find(|info| info.id == ty) {
+            Some(info) => info,
+            None => return false,
+        };
+        let ptr = self.get_dynamic(ty, info.layout.size(), index).unwrap();
+        (info.drop)(ptr.as_ptr());
+        true
+    }
+
+    pub(crate) fn edges(&self) -> &ArchetypeEdges {
+        &self.edges
+    }
+
+    pub(crate) fn edges_mut(&mut self) -> &mut ArchetypeEdges {
+        &mut self.edges
+    }
+
     pub(crate) fn reserve(&mut self, additional: usize) {
         if additional > (self.capacity() - self.len()) {
             self.grow(additional - (self.capacity() - self.len()));
@@ -262,10 +286,11 @@ impl Archetype {
         self.entities.len()
     }
 
-    #[allow(missing_docs)]
-    pub fn clear_trackers(&mut self) {
+    /// Clamps the change ticks of every component in this archetype so they never become older
+    /// than [MAX_CHANGE_AGE] relative to `change_tick`
+    pub fn check_change_ticks(&mut self, change_tick: u32) {
         for type_state in self.state.values_mut() {
-            type_state.clear_trackers();
+            type_state.check_change_ticks(change_tick);
         }
     }
 
@@ -283,8 +308,8 @@ impl Archetype {
 
             for type_state in self.state.values_mut() {
                 type_state
-                    .component_flags
-                    .resize_with(new_capacity, ComponentFlags::empty);
+                    .component_ticks
+                    .resize_with(new_capacity, ComponentTicks::default);
             }
 
             let old_data_size = mem::replace(&mut self.data_size, 0);
@@ -341,7 +366,6 @@ impl Archetype {
                 .as_ptr();
             (ty.drop)(removed);
             if index != last {
-                // TODO: copy component tracker state here
                 ptr::copy_nonoverlapping(
                     self.get_dynamic(ty.id, ty.layout.size(), last)
                         .unwrap()
@@ -351,7 +375,7 @@ impl Archetype {
                 );
 
                 let type_state = self.state.get_mut(&ty.id).unwrap();
-                type_state.component_flags[index] = type_state.component_flags[last];
+                type_state.component_ticks[index] = type_state.component_ticks[last];
             }
         }
         self.len = last;
@@ -367,7 +391,7 @@ impl Archetype {
     pub(crate) unsafe fn move_to(
         &mut self,
         index: usize,
-        mut f: impl FnMut(*mut u8, TypeId, usize, ComponentFlags),
+        mut f: impl FnMut(*mut u8, TypeId, usize, ComponentTicks),
     ) -> Option<Entity> {
         let last = self.len - 1;
         for ty in &self.types {
@@ -376,8 +400,8 @@ impl Archetype {
                 .unwrap()
                 .as_ptr();
             let type_state = self.state.get(&ty.id).unwrap();
-            let flags = type_state.component_flags[index];
-            f(moved, ty.id(), ty.layout().size(), flags);
+            let ticks = type_state.component_ticks[index];
+            f(moved, ty.id(), ty.layout().size(), ticks);
             if index != last {
                 ptr::copy_nonoverlapping(
                     self.get_dynamic(ty.id, ty.layout.size(), last)
@@ -387,7 +411,7 @@ impl Archetype {
                     ty.layout.size(),
                 );
                 let type_state = self.state.get_mut(&ty.id).unwrap();
-                type_state.component_flags[index] = type_state.component_flags[last];
+                type_state.component_ticks[index] = type_state.component_ticks[last];
             }
         }
         self.len -= 1;
@@ -399,6 +423,53 @@ impl Archetype {
         }
     }
 
+    /// Moves all the entities of `source` to the end of this archetype, under the new IDs
+    /// `entities`, and returns the index of the first one. `source` is left empty. Components this
+    /// archetype doesn't store are passed to `f` with their type and row in `source` instead.
+    ///
+    /// # Safety
+    /// `entities` must have one ID per entity of `source`, and this archetype's types must be a
+    /// subset of those of `source`. Components passed to `f` must be moved out.
+    pub(crate) unsafe fn append(
+        &mut self,
+        source: &mut Archetype,
+        entities: &[Entity],
+        ticks: ComponentTicks,
+        mut f: impl FnMut(*mut u8, TypeId, usize),
+    ) -> usize {
+        let count = source.len;
+        debug_assert_eq!(entities.len(), count);
+        self.reserve(count);
+        let start = self.len;
+        self.entities[start..start + count].copy_from_slice(entities);
+        for ty in &source.types {
+            let size = ty.layout.size();
+            let column = (*source.data.get())
+                .as_ptr()
+                .add(source.state.get(&ty.id).unwrap().offset);
+            match self.state.get_mut(&ty.id) {
+                Some(state) => {
+                    ptr::copy_nonoverlapping(
+                        column,
+                        (*self.data.get()).as_ptr().add(state.offset + size * start),
+                        size * count,
+                    );
+                    for component_ticks in &mut state.component_ticks[start..start + count] {
+                        *component_ticks = ticks;
+                    }
+                }
+                None => {
+                    for row in 0..count {
+                        f(column.add(size * row), ty.id, row);
+                    }
+                }
+            }
+        }
+        self.len += count;
+        source.len = 0;
+        start
+    }
+
     /// # Safety
     ///
     ///  - `component` must point to valid memory
@@ -412,10 +483,10 @@ impl Archetype {
         ty: TypeId,
         size: usize,
         index: usize,
-        flags: ComponentFlags,
+        ticks: ComponentTicks,
     ) {
         let state = self.state.get_mut(&ty).unwrap();
-        state.component_flags[index] = flags;
+        state.component_ticks[index] = ticks;
         let ptr = (*self.data.get())
             .as_ptr()
             .add(state.offset + size * index)
@@ -441,19 +512,45 @@ impl Drop for Archetype {
     }
 }
 
+/// The archetypes reached from an archetype by inserting or removing a set of components
+///
+/// Edges are keyed by the ids of the inserted or removed components, in the order given by the
+/// bundle, so moving entities between two archetypes with the same bundle is a single lookup.
+#[derive(Debug, Default)]
+pub(crate) struct ArchetypeEdges {
+    insert: HashMap<Vec<TypeId>, u32, BuildHasherDefault<AHasher>>,
+    remove: HashMap<Vec<TypeId>, u32, BuildHasherDefault<AHasher>>,
+}
+
+impl ArchetypeEdges {
+    pub fn get_insert(&self, ids: &[TypeId]) -> Option<u32> {
+        self.insert.get(ids).copied()
+    }
+
+    pub fn set_insert(&mut self, ids: &[TypeId], archetype: u32) {
+        self.insert.insert(ids.to_vec(), archetype);
+    }
+
+    pub fn get_remove(&self, ids: &[TypeId]) -> Option<u32> {
+        self.remove.get(ids).copied()
+    }
+
+    pub fn set_remove(&mut self, ids: &[TypeId], archetype: u32) {
+        self.remove.insert(ids.to_vec(), archetype);
+    }
+
+    pub fn clear(&mut self) {
+        self.insert.clear();
+        self.remove.clear();
+    }
+}
+
 /// Metadata about a type stored in an archetype
 #[derive(Debug)]
 pub struct TypeState {
     offset: usize,
     borrow: AtomicBorrow,
-    component_flags: Vec<ComponentFlags>,
-}
-
-bitflags! {
-    pub struct ComponentFlags: u8 {
-        const ADDED = 1;
-        const MUTATED = 2;
-    }
+    component_ticks: Vec<ComponentTicks>,
 }
 
 impl TypeState {
@@ -461,20 +558,103 @@ impl TypeState {
         Self {
             offset: 0,
             borrow: AtomicBorrow::new(),
-            component_flags: Vec::new(),
+            component_ticks: Vec::new(),
         }
     }
 
-    fn clear_trackers(&mut self) {
-        for flags in self.component_flags.iter_mut() {
-            *flags = ComponentFlags::empty();
+    fn check_change_ticks(&mut self, change_tick: u32) {
+        for ticks in self.component_ticks.iter_mut() {
+            ticks.check_ticks(change_tick);
         }
     }
 
+    pub(crate) fn borrow_state(&self) -> &AtomicBorrow {
+        &self.borrow
+    }
+
     #[allow(missing_docs)]
     #[inline]
-    pub fn component_flags(&self) -> NonNull<ComponentFlags> {
-        unsafe { NonNull::new_unchecked(self.component_flags.as_ptr() as *mut ComponentFlags) }
+    pub fn component_ticks(&self) -> NonNull<ComponentTicks> {
+        unsafe { NonNull::new_unchecked(self.component_ticks.as_ptr() as *mut ComponentTicks) }
+    }
+}
+
+/// The number of change ticks after which `World::clear_trackers` clamps old component ticks
+pub const CHECK_TICK_THRESHOLD: u32 = 518_400_000;
+
+/// The maximum age, in change ticks, that a component change can have before it is clamped.
+/// Changes older than this are never reported by change detection.
+pub const MAX_CHANGE_AGE: u32 = u32::MAX - (2 * CHECK_TICK_THRESHOLD - 1);
+
+/// The change ticks at which a component was added and last mutated
+///
+/// Each system records the `World`'s change tick when it runs. A component counts as added or
+/// mutated for a system if its tick is newer than the tick at which that system last ran.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ComponentTicks {
+    added: u32,
+    mutated: Option<u32>,
+}
+
+impl ComponentTicks {
+    /// Creates the ticks of a component added at `change_tick`
+    pub fn new(change_tick: u32) -> Self {
+        Self {
+            added: change_tick,
+            mutated: None,
+        }
+    }
+
+    /// Returns true if the component was added after `last_change_tick`
+    #[inline]
+    pub fn is_added(&self, last_change_tick: u32, change_tick: u32) -> bool {
+        is_tick_newer(self.added, last_change_tick, change_tick)
+    }
+
+    /// Returns true if the component was mutated after `last_change_tick`
+    #[inline]
+    pub fn is_mutated(&self, last_change_tick: u32, change_tick: u32) -> bool {
+        match self.mutated {
+            Some(mutated) => is_tick_newer(mutated, last_change_tick, change_tick),
+            None => false,
+        }
+    }
+
+    /// Returns true if the component was either added or mutated after `last_change_tick`
+    #[inline]
+    pub fn is_changed(&self, last_change_tick: u32, change_tick: u32) -> bool {
+        self.is_added(last_change_tick, change_tick)
+            || self.is_mutated(last_change_tick, change_tick)
+    }
+
+    /// Marks the component as mutated at `change_tick`
+    #[inline]
+    pub fn set_mutated(&mut self, change_tick: u32) {
+        self.mutated = Some(change_tick);
+    }
+
+    pub(crate) fn check_ticks(&mut self, change_tick: u32) {
+        check_tick(&mut self.added, change_tick);
+        if let Some(mutated) = &mut self.mutated {
+            check_tick(mutated, change_tick);
+        }
+    }
+}
+
+/// Returns true if `tick` happened after `last_change_tick`, as seen from `change_tick`. Ticks
+/// wrap around, so both are compared by their distance to `change_tick`.
+#[inline]
+fn is_tick_newer(tick: u32, last_change_tick: u32, change_tick: u32) -> bool {
+    let tick_age = change_tick.wrapping_sub(tick).min(MAX_CHANGE_AGE);
+    let last_change_age = change_tick
+        .wrapping_sub(last_change_tick)
+        .min(MAX_CHANGE_AGE);
+    tick_age < last_change_age
+}
+
+fn check_tick(tick: &mut u32, change_tick: u32) {
+    if change_tick.wrapping_sub(*tick) > MAX_CHANGE_AGE {
+        *tick = change_tick.wrapping_sub(MAX_CHANGE_AGE);
     }
 }
 
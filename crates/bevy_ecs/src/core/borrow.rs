@@ -14,8 +14,11 @@
 
 // modified by Bevy contributors
 
-use crate::{Archetype, Component, ComponentFlags, MissingComponent};
+use crate::{
+    Archetype, Component, ComponentSparseSet, ComponentTicks, Entity, MissingComponent, SparseSets,
+};
 use core::{
+    any::{type_name, TypeId},
     fmt::Debug,
     ops::{Deref, DerefMut},
     sync::atomic::{AtomicUsize, Ordering},
@@ -70,9 +73,8 @@ impl AtomicBorrow {
 const UNIQUE_BIT: usize = !(usize::max_value() >> 1);
 
 /// Shared borrow of an entity's component
-#[derive(Clone)]
 pub struct Ref<'a, T: Component> {
-    archetype: &'a Archetype,
+    borrow: &'a AtomicBorrow,
     target: &'a T,
 }
 
@@ -83,23 +85,50 @@ impl<'a, T: Component> Ref<'a, T> {
     ///
     /// - the index of the component must be valid
     pub unsafe fn new(archetype: &'a Archetype, index: usize) -> Result<Self, MissingComponent> {
-        let target = archetype
-            .get::<T>()
+        let (target, type_state) = archetype
+            .get_with_type_state::<T>()
             .ok_or_else(MissingComponent::new::<T>)?;
         archetype.borrow::<T>();
         Ok(Self {
-            archetype,
+            borrow: type_state.borrow_state(),
             target: &*target.as_ptr().add(index as usize),
         })
     }
+
+    /// Creates a new borrow of the component of `entity` stored in `sparse_set`
+    pub(crate) fn from_sparse_set(
+        sparse_set: &'a ComponentSparseSet,
+        entity: Entity,
+    ) -> Result<Self, MissingComponent> {
+        let target = sparse_set
+            .get(entity)
+            .ok_or_else(MissingComponent::new::<T>)?;
+        if !sparse_set.borrow_state().borrow() {
+            panic!("{} already borrowed uniquely.", type_name::<T>());
+        }
+        Ok(Self {
+            borrow: sparse_set.borrow_state(),
+            target: unsafe { &*target.as_ptr().cast::<T>() },
+        })
+    }
 }
 
 unsafe impl<T: Component> Send for Ref<'_, T> {}
 unsafe impl<T: Component> Sync for Ref<'_, T> {}
 
+impl<'a, T: Component> Clone for Ref<'a, T> {
+    fn clone(&self) -> Self {
+        self.borrow.borrow();
+        Self {
+            borrow: self.borrow,
+            target: self.target,
+        }
+    }
+}
+
 impl<'a, T: Component> Drop for Ref<'a, T> {
     fn drop(&mut self) {
-        self.archetype.release::<T>();
+        self.borrow.release();
     }
 }
 
@@ -122,26 +151,54 @@ where
 
 /// Unique borrow of an entity's component
 pub struct RefMut<'a, T: Component> {
-    archetype: &'a Archetype,
+    borrow: &'a AtomicBorrow,
     target: &'a mut T,
-    flags: &'a mut ComponentFlags,
+    ticks: &'a mut ComponentTicks,
+    change_tick: u32,
 }
 
 impl<'a, T: Component> RefMut<'a, T> {
-    /// Creates a new entity component mutable borrow
+    /// Creates a new entity component mutable borrow. Mutations made through it are recorded at
+    /// `change_tick`.
     ///
     /// # Safety
     ///
     /// - the index of the component must be valid
-    pub unsafe fn new(archetype: &'a Archetype, index: usize) -> Result<Self, MissingComponent> {
+    pub unsafe fn new(
+        archetype: &'a Archetype,
+        index: usize,
+        change_tick: u32,
+    ) -> Result<Self, MissingComponent> {
         let (target, type_state) = archetype
             .get_with_type_state::<T>()
             .ok_or_else(MissingComponent::new::<T>)?;
         archetype.borrow_mut::<T>();
         Ok(Self {
-            archetype,
+            borrow: type_state.borrow_state(),
             target: &mut *target.as_ptr().add(index),
-            flags: &mut *type_state.component_flags().as_ptr().add(index),
+            ticks: &mut *type_state.component_ticks().as_ptr().add(index),
+            change_tick,
+        })
+    }
+
+    /// Creates a new mutable borrow of the component of `entity` stored in `sparse_set`.
+    /// Mutations made through it are recorded at `change_tick`.
+    pub(crate) fn from_sparse_set(
+        sparse_set: &'a ComponentSparseSet,
+        entity: Entity,
+        change_tick: u32,
+    ) -> Result<Self, MissingComponent> {
+        let (target, ticks) = sparse_set
+            .get_with_ticks(entity)
+            .ok_or_else(MissingComponent::new::<T>)?;
+        if !sparse_set.borrow_state().borrow_mut() {
+            panic!("{} already borrowed.", type_name::<T>());
+        }
+        Ok(Self {
+            borrow: sparse_set.borrow_state(),
+            target: unsafe { &mut *target.as_ptr().cast::<T>() },
+            ticks: unsafe { &mut *ticks.as_ptr() },
+            change_tick,
         })
     }
 }
@@ -151,7 +208,7 @@ unsafe impl<T: Component> Sync for RefMut<'_, T> {}
 
 impl<'a, T: Component> Drop for RefMut<'a, T> {
     fn drop(&mut self) {
-        self.archetype.release_mut::<T>();
+        self.borrow.release_mut();
     }
 }
 
@@ -165,7 +222,7 @@ impl<'a, T: Component> Deref for RefMut<'a, T> {
 
 impl<'a, T: Component> DerefMut for RefMut<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        self.flags.insert(ComponentFlags::MUTATED);
+        self.ticks.set_mutated(self.change_tick);
         self.target
     }
 }
@@ -182,39 +239,52 @@ where
 /// Handle to an entity with any component types
 #[derive(Copy, Clone)]
 pub struct EntityRef<'a> {
-    archetype: Option<&'a Archetype>,
+    entity: Entity,
+    archetype: &'a Archetype,
+    sparse_sets: &'a SparseSets,
     index: usize,
+    change_tick: u32,
 }
 
 impl<'a> EntityRef<'a> {
-    /// Construct a `Ref` for an entity with no components
-    pub(crate) fn empty() -> Self {
-        Self {
-            archetype: None,
-            index: 0,
-        }
-    }
-
-    pub(crate) unsafe fn new(archetype: &'a Archetype, index: usize) -> Self {
+    pub(crate) unsafe fn new(
+        entity: Entity,
+        archetype: &'a Archetype,
+        sparse_sets: &'a SparseSets,
+        index: usize,
+        change_tick: u32,
+    ) -> Self {
         Self {
-            archetype: Some(archetype),
+            entity,
+            archetype,
+            sparse_sets,
             index,
+            change_tick,
         }
     }
 
     /// Borrow the component of type `T`, if it exists
     ///
     /// Panics if the component is already uniquely borrowed from another entity with the same
-    /// components.
+    /// components, or from any entity if `T` is stored in a sparse set.
     pub fn get<T: Component>(&self) -> Option<Ref<'a, T>> {
-        Some(unsafe { Ref::new(self.archetype?, self.index).ok()? })
+        match self.sparse_sets.get(TypeId::of::<T>()) {
+            Some(sparse_set) => Ref::from_sparse_set(sparse_set, self.entity).ok(),
+            None => unsafe { Ref::new(self.archetype, self.index).ok() },
+        }
     }
 
     /// Uniquely borrow the component of type `T`, if it exists
     ///
-    /// Panics if the component is already borrowed from another entity with the same components.
+    /// Panics if the component is already borrowed from another entity with the same components,
+    /// or from any entity if `T` is stored in a sparse set.
     pub fn get_mut<T: Component>(&self) -> Option<RefMut<'a, T>> {
-        Some(unsafe { RefMut::new(self.archetype?, self.index).ok()? })
+        match self.sparse_sets.get(TypeId::of::<T>()) {
+            Some(sparse_set) => {
+                RefMut::from_sparse_set(sparse_set, self.entity, self.change_tick).ok()
+            }
+            None => unsafe { RefMut::new(self.archetype, self.index, self.change_tick).ok() },
+        }
     }
 }
 
@@ -0,0 +1,218 @@
+use crate::{Commands, Entity, World};
+use std::{any::type_name, fmt, sync::Arc};
+
+/// A function run by the `World` when a component is added to or removed from an entity
+///
+/// Hooks only get shared access to the `World`. Changes are queued in the given `Commands`, which
+/// are applied right after the command buffer or exclusive system that triggered the hook.
+pub type ComponentHook = Arc<dyn Fn(&World, Entity, &mut Commands) + Send + Sync>;
+
+/// The hooks registered for a component type
+///
+/// See `World::component_hooks_mut`.
+#[derive(Default, Clone)]
+pub struct ComponentHooks {
+    pub(crate) on_add: Option<ComponentHook>,
+    pub(crate) on_insert: Option<ComponentHook>,
+    pub(crate) on_remove: Option<ComponentHook>,
+    component_name: &'static str,
+}
+
+impl ComponentHooks {
+    pub(crate) fn of<T>() -> Self {
+        Self {
+            component_name: type_name::<T>(),
+            ..Default::default()
+        }
+    }
+
+    /// Registers a hook run when the component is added to an entity that didn't have it. It
+    /// runs after the component was added, before any `on_insert` hook.
+    ///
+    /// # Panics
+    /// Panics if the component already has an `on_add` hook.
+    pub fn on_add(
+        &mut self,
+        hook: impl Fn(&World, Entity, &mut Commands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        Self::set(&mut self.on_add, hook, "on_add", self.component_name);
+        self
+    }
+
+    /// Registers a hook run every time the component is inserted into an entity, including when
+    /// it replaces a previous value. It runs after the component was inserted.
+    ///
+    /// # Panics
+    /// Panics if the component already has an `on_insert` hook.
+    pub fn on_insert(
+        &mut self,
+        hook: impl Fn(&World, Entity, &mut Commands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        Self::set(&mut self.on_insert, hook, "on_insert", self.component_name);
+        self
+    }
+
+    /// Registers a hook run when the component is removed from an entity, either directly or by
+    /// despawning the entity. It runs before the component is removed, so it can still be read.
+    ///
+    /// # Panics
+    /// Panics if the component already has an `on_remove` hook.
+    pub fn on_remove(
+        &mut self,
+        hook: impl Fn(&World, Entity, &mut Commands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        Self::set(&mut self.on_remove, hook, "on_remove", self.component_name);
+        self
+    }
+
+    fn set(
+        slot: &mut Option<ComponentHook>,
+        hook: impl Fn(&World, Entity, &mut Commands) + Send + Sync + 'static,
+        hook_name: &str,
+        component_name: &str,
+    ) {
+        if slot.is_some() {
+            panic!("{} already has an {} hook", component_name, hook_name);
+        }
+        *slot = Some(Arc::new(hook));
+    }
+}
+
+impl fmt::Debug for ComponentHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentHooks")
+            .field("component_name", &self.component_name)
+            .field("on_add", &self.on_add.is_some())
+            .field("on_insert", &self.on_insert.is_some())
+            .field("on_remove", &self.on_remove.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        resource::Resources, Commands, Entity, IntoSystem, Schedule, StorageType, SystemStage,
+        World,
+    };
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct A(usize);
+    struct B;
+    struct Sparse;
+
+    type Log = Arc<Mutex<Vec<(&'static str, Entity)>>>;
+
+    fn log_hooks<T: Send + Sync + 'static>(world: &mut World, log: &Log) {
+        let (add, insert, remove) = (log.clone(), log.clone(), log.clone());
+        world
+            .component_hooks_mut::<T>()
+            .on_add(move |_, entity, _| add.lock().push(("add", entity)))
+            .on_insert(move |_, entity, _| insert.lock().push(("insert", entity)))
+            .on_remove(move |world, entity, _| {
+                assert!(
+                    world.get::<T>(entity).is_ok(),
+                    "removed component is readable"
+                );
+                remove.lock().push(("remove", entity));
+            });
+    }
+
+    #[test]
+    fn hooks_run_on_structural_changes() {
+        let mut world = World::default();
+        world.register_component::<Sparse>(StorageType::SparseSet);
+        let log = Log::default();
+        log_hooks::<A>(&mut world, &log);
+        log_hooks::<Sparse>(&mut world, &log);
+        let take = || log.lock().drain(..).collect::<Vec<_>>();
+
+        let e1 = world.spawn((A(1), B));
+        assert_eq!(take(), vec![("add", e1), ("insert", e1)]);
+        world.insert_one(e1, A(2)).unwrap();
+        assert_eq!(take(), vec![("insert", e1)]);
+        world.insert(e1, (Sparse,)).unwrap();
+        assert_eq!(take(), vec![("add", e1), ("insert", e1)]);
+        assert_eq!(world.remove_one::<A>(e1), Ok(A(2)));
+        assert_eq!(take(), vec![("remove", e1)]);
+        assert!(world.remove::<(A, B)>(e1).is_err());
+        assert_eq!(take(), vec![]);
+
+        let e2 = world.spawn((B,));
+        world.insert_one(e2, A(3)).unwrap();
+        assert_eq!(take(), vec![("add", e2), ("insert", e2)]);
+        world.remove_one_by_one::<(A, Sparse)>(e2).unwrap();
+        assert_eq!(take(), vec![("remove", e2)]);
+
+        world.despawn(e1).unwrap();
+        assert_eq!(take(), vec![("remove", e1)]);
+
+        let batch = world
+            .spawn_batch((0..2).map(|i| (A(i),)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            take(),
+            vec![
+                ("add", batch[0]),
+                ("insert", batch[0]),
+                ("add", batch[1]),
+                ("insert", batch[1])
+            ]
+        );
+        world.clear();
+        assert_eq!(take(), vec![("remove", batch[0]), ("remove", batch[1])]);
+    }
+
+    #[test]
+    fn hook_commands_are_applied_after_commands() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        world
+            .component_hooks_mut::<A>()
+            .on_add(|_, entity, commands| {
+                commands.insert_one(entity, B);
+            })
+            .on_remove(|_, _, commands| {
+                commands.spawn((B,));
+            });
+        let e1 = world.spawn((0,));
+        let e2 = world.spawn((A(0),));
+
+        fn insert_a(commands: &mut Commands, query: crate::Query<Entity>) {
+            for entity in query.iter() {
+                commands.insert_one(entity, A(1));
+            }
+        }
+        fn remove_a(world: &mut World, _resources: &mut Resources) {
+            let entities = world
+                .query::<(Entity, &A)>()
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>();
+            for entity in entities {
+                world.remove_one::<A>(entity).unwrap();
+            }
+        }
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("insert", SystemStage::single(insert_a.system()));
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert!(world.get::<B>(e1).is_ok());
+        assert_eq!(world.query::<&B>().count(), 2);
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("remove", SystemStage::single(remove_a.system()));
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert!(world.get::<A>(e2).is_err());
+        assert_eq!(world.query::<&B>().count(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn hooks_cannot_be_replaced() {
+        let mut world = World::default();
+        world.component_hooks_mut::<A>().on_add(|_, _, _| {});
+        world.component_hooks_mut::<A>().on_add(|_, _, _| {});
+    }
+}
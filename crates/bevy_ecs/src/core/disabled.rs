@@ -0,0 +1,113 @@
+/// Marks an entity as disabled, taking it out of the simulation without despawning it
+///
+/// Queries skip disabled entities unless they ask for them, by fetching or filtering on
+/// `Disabled` or with the `IncludeDisabled` filter. Accessing the components of a disabled entity
+/// directly, e.g. with `World::get`, still works.
+///
+/// `Disabled` is always stored in archetype tables, since queries skip whole archetypes.
+///
+/// # Example
+/// ```
+/// # use bevy_ecs::*;
+/// let mut world = World::new();
+/// let bullet = world.spawn((1.0f32, Disabled));
+/// assert_eq!(world.query::<&f32>().count(), 0);
+/// assert_eq!(world.query_filtered::<&f32, IncludeDisabled>().count(), 1);
+/// world.remove_one::<Disabled>(bullet).unwrap();
+/// assert_eq!(world.query::<&f32>().count(), 1);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Disabled;
+
+#[cfg(test)]
+mod tests {
+    use super::Disabled;
+    use crate::{
+        resource::Resources, Entity, IncludeDisabled, IntoSystem, Or, Query, Schedule, StorageType,
+        SystemStage, With, World,
+    };
+
+    #[test]
+    fn world_queries_skip_disabled() {
+        let mut world = World::new();
+        let a = world.spawn((1,));
+        let b = world.spawn((2, Disabled));
+        let sorted = |mut entities: Vec<Entity>| {
+            entities.sort();
+            entities
+        };
+
+        assert_eq!(world.query::<Entity>().collect::<Vec<_>>(), vec![a]);
+        assert_eq!(world.query::<&i32>().len(), 1);
+        assert!(world.query_one::<&i32>(b).is_err());
+        assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+
+        assert_eq!(
+            sorted(world.query_filtered::<Entity, IncludeDisabled>().collect()),
+            sorted(vec![a, b])
+        );
+        assert_eq!(
+            world
+                .query::<(Entity, &Disabled)>()
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>(),
+            vec![b]
+        );
+        assert_eq!(world.query::<Option<&Disabled>>().count(), 2);
+        assert_eq!(
+            world
+                .query_filtered::<Entity, With<Disabled>>()
+                .collect::<Vec<_>>(),
+            vec![b]
+        );
+        assert_eq!(
+            world
+                .query_filtered::<Entity, Or<(With<i32>, With<Disabled>)>>()
+                .count(),
+            2
+        );
+        assert!(world.query_one_filtered::<&i32, IncludeDisabled>(b).is_ok());
+    }
+
+    #[test]
+    fn system_queries_skip_disabled() {
+        fn count(
+            mut counts: crate::ResMut<Vec<(usize, usize, bool)>>,
+            enabled: Query<&i32>,
+            all: Query<&i32, IncludeDisabled>,
+            disabled: Query<Entity, With<Disabled>>,
+        ) {
+            let hidden = disabled.iter().all(|entity| enabled.get(entity).is_err());
+            counts.push((enabled.iter().count(), all.iter().count(), hidden));
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(Vec::<(usize, usize, bool)>::new());
+        world.spawn((1,));
+        let e = world.spawn((2,));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage(
+            "update",
+            SystemStage::parallel().with_system(count.system()),
+        );
+        schedule.initialize_and_run(&mut world, &mut resources);
+        world.insert_one(e, Disabled).unwrap();
+        schedule.initialize_and_run(&mut world, &mut resources);
+        world.remove_one::<Disabled>(e).unwrap();
+        schedule.initialize_and_run(&mut world, &mut resources);
+
+        assert_eq!(
+            *resources.get::<Vec<(usize, usize, bool)>>().unwrap(),
+            vec![(2, 2, true), (1, 2, true), (2, 2, true)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn disabled_cannot_be_sparse() {
+        let mut world = World::new();
+        world.register_component::<Disabled>(StorageType::SparseSet);
+    }
+}
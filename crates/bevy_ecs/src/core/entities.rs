@@ -311,6 +311,40 @@ impl Entities {
         self.reserved = new_reserved.into();
     }
 
+    /// Returns the generation of every entity slot, and the free IDs in the order they are reused
+    ///
+    /// Must be called after `flush`.
+    pub(crate) fn allocator_state(&self) -> (Vec<u32>, Vec<u32>) {
+        let free_cursor = self.free_cursor.load(Ordering::Relaxed);
+        let generations = self.meta.iter().map(|meta| meta.generation).collect();
+        (generations, self.free[..free_cursor as usize].to_vec())
+    }
+
+    /// Restores a state returned by `allocator_state`. Locations are left untouched, so those of
+    /// the entities becoming alive must be written immediately.
+    ///
+    /// Must be called after `flush`, while no entity outside of `generations` is alive.
+    pub(crate) fn set_allocator_state(&mut self, generations: &[u32], free: &[u32]) {
+        self.meta.resize(
+            generations.len(),
+            EntityMeta {
+                generation: 0,
+                location: Location {
+                    archetype: 0,
+                    index: usize::max_value(), // dummy value, to be filled in
+                },
+            },
+        );
+        for (meta, &generation) in self.meta.iter_mut().zip(generations) {
+            meta.generation = generation;
+        }
+        self.free.clear();
+        self.free.extend_from_slice(free);
+        self.free.resize(generations.len(), 0);
+        self.free_cursor.store(free.len() as u32, Ordering::Relaxed); // Not racey due to &mut self
+        self.reserved = (0..generations.len()).map(|_| AtomicU32::new(0)).collect();
+    }
+
     pub fn get_reserver(&self) -> EntityReserver {
         // SAFE: reservers use atomics for anything write-related
         let entities: &'static Entities = unsafe { mem::transmute(self) };
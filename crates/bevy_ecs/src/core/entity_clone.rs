@@ -0,0 +1,110 @@
+use crate::{resource::Resources, Entity, World};
+use bevy_utils::HashMap;
+use std::{any::TypeId, fmt, sync::Arc};
+
+/// A function copying one component from a `source` entity to a `destination` entity of the same
+/// `World`
+///
+/// See `World::register_component_cloner`.
+pub type ComponentCloner = Arc<dyn Fn(&mut World, &Resources, Entity, Entity) + Send + Sync>;
+
+/// The cloner of each component type, or `None` for types left out of clones
+#[derive(Default)]
+pub(crate) struct ComponentCloners(HashMap<TypeId, Option<ComponentCloner>>);
+
+impl ComponentCloners {
+    pub(crate) fn insert(&mut self, ty: TypeId, cloner: Option<ComponentCloner>) {
+        self.0.insert(ty, cloner);
+    }
+
+    pub(crate) fn contains(&self, ty: TypeId) -> bool {
+        self.0.contains_key(&ty)
+    }
+
+    pub(crate) fn get(&self, ty: TypeId) -> Option<ComponentCloner> {
+        self.0.get(&ty).cloned().flatten()
+    }
+}
+
+impl fmt::Debug for ComponentCloners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(ty, cloner)| (ty, cloner.is_some())))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        resource::Resources, Commands, Entity, IntoSystem, Query, Schedule, StorageType,
+        SystemStage, World,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Name(String);
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Health(u32);
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Selected;
+    struct Unique;
+
+    #[test]
+    fn clone_registered_components() {
+        let mut world = World::new();
+        let resources = Resources::default();
+        world.register_component::<Selected>(StorageType::SparseSet);
+        world.register_clone::<Name>();
+        world.register_clone::<Selected>();
+        world.register_component_cloner::<Health>(|world, _, source, destination| {
+            let health = *world.get::<Health>(source).unwrap();
+            world.insert_one(destination, Health(health.0 / 2)).unwrap();
+        });
+
+        let e = world.spawn((Name("orc".to_owned()), Health(10), Unique));
+        world.insert_one(e, Selected).unwrap();
+        let clone = world.clone_entity(&resources, e).unwrap();
+        assert_ne!(clone, e);
+        assert_eq!(*world.get::<Name>(clone).unwrap(), Name("orc".to_owned()));
+        assert_eq!(*world.get::<Health>(clone).unwrap(), Health(5));
+        assert!(world.get::<Selected>(clone).is_ok());
+        assert!(world.get::<Unique>(clone).is_err());
+        assert_eq!(*world.get::<Health>(e).unwrap(), Health(10));
+
+        world.ignore_on_clone::<Name>();
+        let clone = world.clone_entity(&resources, e).unwrap();
+        assert!(world.get::<Name>(clone).is_err());
+        assert!(world.get::<Health>(clone).is_ok());
+
+        world.despawn(e).unwrap();
+        assert!(world.clone_entity(&resources, e).is_err());
+    }
+
+    #[test]
+    fn clone_with_commands() {
+        fn duplicate(commands: &mut Commands, query: Query<Entity>) {
+            for entity in query.iter() {
+                commands.clone_entity(entity).with(Selected);
+            }
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        world.register_clone::<Health>();
+        world.spawn((Health(1),));
+        world.spawn((Health(2), Unique));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("duplicate", SystemStage::single(duplicate.system()));
+        schedule.initialize_and_run(&mut world, &mut resources);
+
+        let mut clones = world
+            .query::<(&Health, &Selected)>()
+            .map(|(health, _)| health.0)
+            .collect::<Vec<_>>();
+        clones.sort_unstable();
+        assert_eq!(clones, vec![1, 2]);
+        assert_eq!(world.query::<&Health>().count(), 4);
+        assert_eq!(world.query::<&Unique>().count(), 1);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Entity;
+use crate::{Component, Entity, World};
 use bevy_utils::HashMap;
 use std::collections::hash_map::Entry;
 use thiserror::Error;
@@ -13,6 +13,23 @@ pub trait MapEntities {
     fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError>;
 }
 
+/// Maps the entities stored in one component type of the given entities
+pub(crate) type EntityMapper =
+    fn(&mut World, &[Entity], &EntityMap) -> Result<(), MapEntitiesError>;
+
+pub(crate) fn map_component_entities<T: Component + MapEntities>(
+    world: &mut World,
+    entities: &[Entity],
+    entity_map: &EntityMap,
+) -> Result<(), MapEntitiesError> {
+    for &entity in entities {
+        if let Ok(mut component) = world.get_mut::<T>(entity) {
+            component.map_entities(entity_map)?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Default, Debug)]
 pub struct EntityMap {
     map: HashMap<Entity, Entity>,
@@ -46,3 +63,92 @@ impl EntityMap {
         self.map.values().cloned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{EntityMap, MapEntities, MapEntitiesError};
+    use crate::{Entity, Relation, StorageType, World};
+    use std::any::TypeId;
+
+    #[derive(Debug, PartialEq)]
+    struct Tile(u32);
+    struct Selected;
+    struct Hovered;
+    struct Owns;
+
+    #[derive(Debug, PartialEq)]
+    struct Neighbor(Entity);
+
+    impl MapEntities for Neighbor {
+        fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+            self.0 = entity_map.get(self.0)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn move_entities_between_worlds() {
+        let mut world = World::new();
+        world.register_component::<Selected>(StorageType::SparseSet);
+        world.register_map_entities::<Neighbor>();
+        world.register_relation::<Owns>();
+        let player = world.spawn((Tile(0),));
+        let existing = world.spawn((Tile(1), "existing"));
+
+        let mut chunk = World::new();
+        chunk.register_component::<Hovered>(StorageType::SparseSet);
+        // stands for `player` in the chunk
+        let placeholder = Entity::new(1000);
+        let a = chunk.spawn((Tile(2), "a"));
+        let b = chunk.spawn((Tile(3), Neighbor(a)));
+        let c = chunk.spawn((Tile(4), Neighbor(b), Relation::<Owns>::new(placeholder)));
+        chunk.insert_one(a, Selected).unwrap();
+        chunk.insert_one(b, Hovered).unwrap();
+
+        let mut entity_map = EntityMap::default();
+        entity_map.insert(placeholder, player);
+        world
+            .move_entities_from(&mut chunk, &mut entity_map)
+            .unwrap();
+
+        let (new_a, new_b, new_c) = (
+            entity_map.get(a).unwrap(),
+            entity_map.get(b).unwrap(),
+            entity_map.get(c).unwrap(),
+        );
+        assert_eq!(*world.get::<Tile>(existing).unwrap(), Tile(1));
+        assert_eq!(*world.get::<&str>(new_a).unwrap(), "a");
+        assert!(world.get::<Selected>(new_a).is_ok());
+        assert!(world.get::<Hovered>(new_b).is_ok());
+        assert_eq!(
+            world.storage_type(TypeId::of::<Hovered>()),
+            StorageType::SparseSet
+        );
+        assert_eq!(*world.get::<Neighbor>(new_b).unwrap(), Neighbor(new_a));
+        assert_eq!(*world.get::<Neighbor>(new_c).unwrap(), Neighbor(new_b));
+        assert_eq!(world.relation_sources::<Owns>(player), vec![new_c]);
+        assert_eq!(world.query::<&Tile>().count(), 5);
+
+        assert_eq!(chunk.query::<&Tile>().count(), 0);
+        assert!(chunk
+            .sparse_sets()
+            .get(TypeId::of::<Hovered>())
+            .unwrap()
+            .is_empty());
+        assert!(!chunk.contains(a));
+    }
+
+    #[test]
+    fn move_entities_with_dangling_references() {
+        let mut world = World::new();
+        world.register_map_entities::<Neighbor>();
+        let mut chunk = World::new();
+        let dangling = chunk.spawn(());
+        chunk.despawn(dangling).unwrap();
+        let e = chunk.spawn((Tile(0), Neighbor(dangling)));
+
+        assert!(world.append(&mut chunk).is_err());
+        assert_eq!(world.query::<&Tile>().count(), 1);
+        assert!(!chunk.contains(e));
+    }
+}
@@ -1,10 +1,30 @@
-use crate::{core::ComponentFlags, Archetype, Bundle, Component, QueryAccess};
+use crate::{
+    core::{sparse_set::SparseSetFetch, ComponentTicks},
+    Archetype, Bundle, Component, Disabled, QueryAccess, SparseSets,
+};
 use std::{any::TypeId, marker::PhantomData, ptr::NonNull};
 
 pub trait QueryFilter: Sized {
     type EntityFilter: EntityFilter;
     fn access() -> QueryAccess;
-    fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter>;
+
+    /// Whether this filter asks for `Disabled` entities, which queries skip otherwise
+    #[inline]
+    fn includes_disabled() -> bool {
+        false
+    }
+
+    /// Creates the filter of the entities of `archetype` starting at `offset`
+    ///
+    /// # Safety
+    /// `offset` must be in bounds of `archetype`
+    unsafe fn get_entity_filter(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self::EntityFilter>;
 }
 
 pub trait EntityFilter: Sized {
@@ -27,17 +47,102 @@ impl EntityFilter for AnyEntityFilter {
     }
 }
 
+/// Entity filter of `With`, `Without` and `WithType` that checks components stored in sparse sets
+/// per entity. Components stored in archetype tables are checked per archetype instead.
+pub struct SparseSetEntityFilter {
+    sparse_sets: Vec<SparseSetFetch>,
+    present: bool,
+}
+
+impl SparseSetEntityFilter {
+    fn new(present: bool) -> Self {
+        Self {
+            sparse_sets: Vec::new(),
+            present,
+        }
+    }
+}
+
+impl EntityFilter for SparseSetEntityFilter {
+    const DANGLING: Self = SparseSetEntityFilter {
+        sparse_sets: Vec::new(),
+        present: true,
+    };
+
+    #[inline]
+    unsafe fn matches_entity(&self, offset: usize) -> bool {
+        self.sparse_sets
+            .iter()
+            .all(|sparse_set| sparse_set.contains(offset) == self.present)
+    }
+}
+
 pub struct Or<T>(pub T);
 
-/// Query transformer that retrieves components of type `T` that have been mutated since the start of the frame.
+/// Query transformer that retrieves components of type `T` that have been mutated since the system last ran.
 /// Added components do not count as mutated.
-pub struct Mutated<T>(NonNull<ComponentFlags>, PhantomData<T>);
+pub struct Mutated<T>(ComponentTicksState, PhantomData<T>);
 
-/// Query transformer that retrieves components of type `T` that have been added since the start of the frame.
-pub struct Added<T>(NonNull<ComponentFlags>, PhantomData<T>);
+/// Query transformer that retrieves components of type `T` that have been added since the system last ran.
+pub struct Added<T>(ComponentTicksState, PhantomData<T>);
 
-/// Query transformer that retrieves components of type `T` that have either been mutated or added since the start of the frame.
-pub struct Changed<T>(NonNull<ComponentFlags>, PhantomData<T>);
+/// Query transformer that retrieves components of type `T` that have either been mutated or added since the system last ran.
+pub struct Changed<T>(ComponentTicksState, PhantomData<T>);
+
+enum TicksStorage {
+    Table(NonNull<ComponentTicks>),
+    SparseSet(SparseSetFetch),
+}
+
+struct ComponentTicksState {
+    ticks: TicksStorage,
+    last_change_tick: u32,
+    change_tick: u32,
+}
+
+impl ComponentTicksState {
+    const DANGLING: Self = ComponentTicksState {
+        ticks: TicksStorage::Table(NonNull::dangling()),
+        last_change_tick: 0,
+        change_tick: 0,
+    };
+
+    /// # Safety
+    /// `offset` must be in bounds of `archetype`
+    unsafe fn new<T: Component>(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self> {
+        let ticks = match archetype.get_type_state(TypeId::of::<T>()) {
+            Some(state) => TicksStorage::Table(NonNull::new_unchecked(
+                state.component_ticks().as_ptr().add(offset),
+            )),
+            None => {
+                let set = sparse_sets.get(TypeId::of::<T>())?;
+                TicksStorage::SparseSet(SparseSetFetch::new(archetype, offset, set))
+            }
+        };
+        Some(ComponentTicksState {
+            ticks,
+            last_change_tick,
+            change_tick,
+        })
+    }
+
+    /// Returns the ticks of the `offset`th entity's component, if it has one
+    #[inline]
+    unsafe fn get(&self, offset: usize) -> Option<&ComponentTicks> {
+        match &self.ticks {
+            TicksStorage::Table(ticks) => Some(&*ticks.as_ptr().add(offset)),
+            TicksStorage::SparseSet(sparse_set) => sparse_set
+                .get_with_ticks(offset)
+                .map(|(_, ticks)| &*ticks.as_ptr()),
+        }
+    }
+}
 
 impl QueryFilter for () {
     type EntityFilter = AnyEntityFilter;
@@ -47,7 +152,13 @@ impl QueryFilter for () {
     }
 
     #[inline]
-    fn get_entity_filter(_archetype: &Archetype) -> Option<Self::EntityFilter> {
+    unsafe fn get_entity_filter(
+        _archetype: &Archetype,
+        _sparse_sets: &SparseSets,
+        _offset: usize,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
         Some(AnyEntityFilter)
     }
 }
@@ -60,19 +171,38 @@ impl<T: Component> QueryFilter for Added<T> {
     }
 
     #[inline]
-    fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
-        archetype
-            .get_type_state(TypeId::of::<T>())
-            .map(|state| Added(state.component_flags(), Default::default()))
+    fn includes_disabled() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Disabled>()
+    }
+
+    #[inline]
+    unsafe fn get_entity_filter(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
+        ComponentTicksState::new::<T>(
+            archetype,
+            sparse_sets,
+            offset,
+            last_change_tick,
+            change_tick,
+        )
+        .map(|trackers| Added(trackers, Default::default()))
     }
 }
 
 impl<T: Component> EntityFilter for Added<T> {
-    const DANGLING: Self = Added(NonNull::dangling(), PhantomData::<T>);
+    const DANGLING: Self = Added(ComponentTicksState::DANGLING, PhantomData::<T>);
 
     #[inline]
     unsafe fn matches_entity(&self, offset: usize) -> bool {
-        (*self.0.as_ptr().add(offset)).contains(ComponentFlags::ADDED)
+        match self.0.get(offset) {
+            Some(ticks) => ticks.is_added(self.0.last_change_tick, self.0.change_tick),
+            None => false,
+        }
     }
 }
 
@@ -84,18 +214,37 @@ impl<T: Component> QueryFilter for Mutated<T> {
     }
 
     #[inline]
-    fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
-        archetype
-            .get_type_state(TypeId::of::<T>())
-            .map(|state| Mutated(state.component_flags(), Default::default()))
+    fn includes_disabled() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Disabled>()
+    }
+
+    #[inline]
+    unsafe fn get_entity_filter(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
+        ComponentTicksState::new::<T>(
+            archetype,
+            sparse_sets,
+            offset,
+            last_change_tick,
+            change_tick,
+        )
+        .map(|trackers| Mutated(trackers, Default::default()))
     }
 }
 
 impl<T: Component> EntityFilter for Mutated<T> {
-    const DANGLING: Self = Mutated(NonNull::dangling(), PhantomData::<T>);
+    const DANGLING: Self = Mutated(ComponentTicksState::DANGLING, PhantomData::<T>);
 
     unsafe fn matches_entity(&self, offset: usize) -> bool {
-        (*self.0.as_ptr().add(offset)).contains(ComponentFlags::MUTATED)
+        match self.0.get(offset) {
+            Some(ticks) => ticks.is_mutated(self.0.last_change_tick, self.0.change_tick),
+            None => false,
+        }
     }
 }
 
@@ -107,65 +256,140 @@ impl<T: Component> QueryFilter for Changed<T> {
     }
 
     #[inline]
-    fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
-        archetype
-            .get_type_state(TypeId::of::<T>())
-            .map(|state| Changed(state.component_flags(), Default::default()))
+    fn includes_disabled() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Disabled>()
+    }
+
+    #[inline]
+    unsafe fn get_entity_filter(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
+        ComponentTicksState::new::<T>(
+            archetype,
+            sparse_sets,
+            offset,
+            last_change_tick,
+            change_tick,
+        )
+        .map(|trackers| Changed(trackers, Default::default()))
     }
 }
 
 impl<T: Component> EntityFilter for Changed<T> {
-    const DANGLING: Self = Changed(NonNull::dangling(), PhantomData::<T>);
+    const DANGLING: Self = Changed(ComponentTicksState::DANGLING, PhantomData::<T>);
 
     #[inline]
     unsafe fn matches_entity(&self, offset: usize) -> bool {
-        let flags = *self.0.as_ptr().add(offset);
-        flags.contains(ComponentFlags::ADDED) || flags.contains(ComponentFlags::MUTATED)
+        match self.0.get(offset) {
+            Some(ticks) => ticks.is_changed(self.0.last_change_tick, self.0.change_tick),
+            None => false,
+        }
+    }
+}
+
+/// Query filter that includes `Disabled` entities, which queries skip otherwise
+pub struct IncludeDisabled;
+
+impl QueryFilter for IncludeDisabled {
+    type EntityFilter = AnyEntityFilter;
+
+    fn access() -> QueryAccess {
+        QueryAccess::None
+    }
+
+    #[inline]
+    fn includes_disabled() -> bool {
+        true
+    }
+
+    #[inline]
+    unsafe fn get_entity_filter(
+        _archetype: &Archetype,
+        _sparse_sets: &SparseSets,
+        _offset: usize,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
+        Some(AnyEntityFilter)
     }
 }
 
 pub struct Without<T>(PhantomData<T>);
 
 impl<T: Component> QueryFilter for Without<T> {
-    type EntityFilter = AnyEntityFilter;
+    type EntityFilter = SparseSetEntityFilter;
 
     fn access() -> QueryAccess {
         QueryAccess::without::<T>(QueryAccess::None)
     }
 
     #[inline]
-    fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
+    fn includes_disabled() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Disabled>()
+    }
+
+    #[inline]
+    unsafe fn get_entity_filter(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
         if archetype.has_type(TypeId::of::<T>()) {
-            None
-        } else {
-            Some(AnyEntityFilter)
+            return None;
+        }
+        let mut filter = SparseSetEntityFilter::new(false);
+        if let Some(set) = sparse_sets.get(TypeId::of::<T>()) {
+            filter
+                .sparse_sets
+                .push(SparseSetFetch::new(archetype, offset, set));
         }
+        Some(filter)
     }
 }
 
 pub struct With<T>(PhantomData<T>);
 
 impl<T: Component> QueryFilter for With<T> {
-    type EntityFilter = AnyEntityFilter;
+    type EntityFilter = SparseSetEntityFilter;
 
     fn access() -> QueryAccess {
         QueryAccess::with::<T>(QueryAccess::None)
     }
 
     #[inline]
-    fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
-        if archetype.has_type(TypeId::of::<T>()) {
-            Some(AnyEntityFilter)
-        } else {
-            None
+    fn includes_disabled() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Disabled>()
+    }
+
+    #[inline]
+    unsafe fn get_entity_filter(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
+        let mut filter = SparseSetEntityFilter::new(true);
+        if !archetype.has_type(TypeId::of::<T>()) {
+            let set = sparse_sets.get(TypeId::of::<T>())?;
+            filter
+                .sparse_sets
+                .push(SparseSetFetch::new(archetype, offset, set));
         }
+        Some(filter)
     }
 }
 
 pub struct WithType<T: Bundle>(PhantomData<T>);
 
 impl<T: Bundle> QueryFilter for WithType<T> {
-    type EntityFilter = AnyEntityFilter;
+    type EntityFilter = SparseSetEntityFilter;
 
     fn access() -> QueryAccess {
         QueryAccess::union(
@@ -176,16 +400,30 @@ impl<T: Bundle> QueryFilter for WithType<T> {
         )
     }
 
-    #[inline]
-    fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
-        if T::static_type_info()
+    fn includes_disabled() -> bool {
+        T::static_type_info()
             .iter()
-            .all(|info| archetype.has_type(info.id()))
-        {
-            Some(AnyEntityFilter)
-        } else {
-            None
+            .any(|info| info.id() == TypeId::of::<Disabled>())
+    }
+
+    #[inline]
+    unsafe fn get_entity_filter(
+        archetype: &Archetype,
+        sparse_sets: &SparseSets,
+        offset: usize,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Option<Self::EntityFilter> {
+        let mut filter = SparseSetEntityFilter::new(true);
+        for info in T::static_type_info() {
+            if !archetype.has_type(info.id()) {
+                let set = sparse_sets.get(info.id())?;
+                filter
+                    .sparse_sets
+                    .push(SparseSetFetch::new(archetype, offset, set));
+            }
         }
+        Some(filter)
     }
 }
 
@@ -202,8 +440,12 @@ macro_rules! impl_query_filter_tuple {
                 ])
             }
 
-            fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
-                Some(($($filter::get_entity_filter(archetype)?,)*))
+            fn includes_disabled() -> bool {
+                false $(|| $filter::includes_disabled())*
+            }
+
+            unsafe fn get_entity_filter(archetype: &Archetype, sparse_sets: &SparseSets, offset: usize, last_change_tick: u32, change_tick: u32) -> Option<Self::EntityFilter> {
+                Some(($($filter::get_entity_filter(archetype, sparse_sets, offset, last_change_tick, change_tick)?,)*))
             }
 
         }
@@ -228,10 +470,14 @@ macro_rules! impl_query_filter_tuple {
                 ])
             }
 
-            fn get_entity_filter(archetype: &Archetype) -> Option<Self::EntityFilter> {
+            fn includes_disabled() -> bool {
+                false $(|| $filter::includes_disabled())*
+            }
+
+            unsafe fn get_entity_filter(archetype: &Archetype, sparse_sets: &SparseSets, offset: usize, last_change_tick: u32, change_tick: u32) -> Option<Self::EntityFilter> {
                 let mut matches_something = false;
                 $(
-                    let $filter = $filter::get_entity_filter(archetype);
+                    let $filter = $filter::get_entity_filter(archetype, sparse_sets, offset, last_change_tick, change_tick);
                     matches_something = matches_something || $filter.is_some();
                 )*
                 if matches_something {
@@ -34,27 +34,46 @@ mod access;
 mod archetype;
 mod borrow;
 mod bundle;
+mod component_hooks;
+mod disabled;
 mod entities;
 mod entity_builder;
+mod entity_clone;
 mod entity_map;
 mod filter;
 mod query;
+mod relation;
+mod removed_components;
 mod serde;
+mod snapshot;
+mod sparse_set;
 mod world;
 mod world_builder;
 
 pub use access::{ArchetypeComponent, QueryAccess, TypeAccess};
-pub use archetype::{Archetype, ComponentFlags, TypeState};
+pub use archetype::{Archetype, ComponentTicks, TypeState};
 pub use borrow::{AtomicBorrow, Ref, RefMut};
 pub use bundle::{Bundle, DynamicBundle, MissingComponent};
+pub use component_hooks::{ComponentHook, ComponentHooks};
+pub use disabled::Disabled;
 pub use entities::{Entity, EntityReserver, Location, NoSuchEntity};
 pub use entity_builder::{BuiltEntity, EntityBuilder};
+pub use entity_clone::ComponentCloner;
 pub use entity_map::*;
-pub use filter::{Added, Changed, EntityFilter, Mutated, Or, QueryFilter, With, Without};
+pub use filter::{
+    Added, Changed, EntityFilter, IncludeDisabled, Mutated, Or, QueryFilter, With, Without,
+};
 pub use query::{Batch, BatchedIter, Mut, QueryIter, ReadOnlyFetch, WorldQuery};
+pub use relation::Relation;
+pub use snapshot::{Snapshot, SnapshotError, SnapshotFilter};
+pub use sparse_set::{ComponentSparseSet, SparseSets, StorageType};
 pub use world::{ArchetypesGeneration, Component, ComponentError, SpawnBatchIter, World};
 pub use world_builder::*;
 
+pub(crate) use entity_clone::ComponentCloners;
+pub(crate) use query::{visits_archetype, MatchedArchetypes};
+pub(crate) use relation::RelationIndex;
+
 // Unstable implementation details needed by the macros
 #[doc(hidden)]
 pub use archetype::TypeInfo;
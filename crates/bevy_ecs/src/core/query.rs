@@ -14,9 +14,13 @@
 
 // modified by Bevy contributors
 
-use super::{Archetype, Component, Entity, MissingComponent, QueryAccess, QueryFilter};
-use crate::{ComponentFlags, EntityFilter};
+use super::{
+    sparse_set::SparseSetFetch, Archetype, Component, Entity, MissingComponent, QueryAccess,
+    QueryFilter, SparseSets,
+};
+use crate::{ArchetypesGeneration, ComponentTicks, Disabled, EntityFilter, World};
 use std::{
+    any::TypeId,
     marker::PhantomData,
     ops::{Deref, DerefMut},
     ptr::NonNull,
@@ -44,16 +48,41 @@ pub trait Fetch<'a>: Sized {
     /// How this query will access `archetype`, if at all
     fn access() -> QueryAccess;
 
-    /// Construct a `Fetch` for `archetype` if it should be traversed
+    /// Whether this fetch asks for `Disabled` entities, which queries skip otherwise
+    #[inline]
+    fn includes_disabled() -> bool {
+        false
+    }
+
+    /// Construct a `Fetch` for `archetype` if it should be traversed. Components stored in sparse
+    /// sets are looked up in `sparse_sets`. Mutations made through the fetch are recorded at
+    /// `change_tick`; `last_change_tick` is the tick at which the fetching system last ran.
     ///
     /// # Safety
     /// `offset` must be in bounds of `archetype`
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self>;
+    unsafe fn get(
+        archetype: &'a Archetype,
+        sparse_sets: &'a SparseSets,
+        offset: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self>;
+
+    /// Whether the `n`th entity in this archetype has the fetched components. Components stored in
+    /// archetype tables are always present, while components stored in sparse sets are looked up
+    /// per entity.
+    ///
+    /// # Safety
+    /// Bounds-checking must be performed externally
+    #[inline]
+    unsafe fn matches_entity(&self, _n: usize) -> bool {
+        true
+    }
 
     /// Access the `n`th item in this archetype without bounds checking
     ///
     /// # Safety
-    /// - Must only be called after `borrow`
+    /// - Must only be called if `matches_entity(n)` returns true
     /// - `release` must not be called while `'a` is still live
     /// - Bounds-checking must be performed externally
     /// - Any resulting borrows must be legal (e.g. no &mut to something another iterator might access)
@@ -74,7 +103,13 @@ impl<'a> Fetch<'a> for EntityFetch {
     const DANGLING: Self = Self(NonNull::dangling());
 
     #[inline]
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
+    unsafe fn get(
+        archetype: &'a Archetype,
+        _sparse_sets: &'a SparseSets,
+        offset: usize,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Option<Self> {
         Some(EntityFetch(NonNull::new_unchecked(
             archetype.entities().as_ptr().add(offset),
         )))
@@ -95,25 +130,95 @@ impl<'a, T: Component> WorldQuery for &'a T {
     type Fetch = FetchRead<T>;
 }
 
+/// Where a fetch finds the `T` components of a run of entities
+enum FetchStorage<T> {
+    Table {
+        components: NonNull<T>,
+        ticks: NonNull<ComponentTicks>,
+    },
+    SparseSet(SparseSetFetch),
+}
+
+impl<T: Component> FetchStorage<T> {
+    const DANGLING: Self = FetchStorage::Table {
+        components: NonNull::dangling(),
+        ticks: NonNull::dangling(),
+    };
+
+    /// # Safety
+    /// `offset` must be in bounds of `archetype`
+    unsafe fn new(archetype: &Archetype, sparse_sets: &SparseSets, offset: usize) -> Option<Self> {
+        if let Some((components, type_state)) = archetype.get_with_type_state::<T>() {
+            Some(FetchStorage::Table {
+                components: NonNull::new_unchecked(components.as_ptr().add(offset)),
+                ticks: NonNull::new_unchecked(type_state.component_ticks().as_ptr().add(offset)),
+            })
+        } else {
+            sparse_sets
+                .get(TypeId::of::<T>())
+                .map(|set| FetchStorage::SparseSet(SparseSetFetch::new(archetype, offset, set)))
+        }
+    }
+
+    #[inline]
+    unsafe fn contains(&self, n: usize) -> bool {
+        match self {
+            FetchStorage::Table { .. } => true,
+            FetchStorage::SparseSet(sparse_set) => sparse_set.contains(n),
+        }
+    }
+
+    /// # Safety
+    /// Must only be called if `contains(n)` returns true
+    #[inline]
+    unsafe fn get(&self, n: usize) -> (*mut T, *mut ComponentTicks) {
+        match self {
+            FetchStorage::Table { components, ticks } => {
+                (components.as_ptr().add(n), ticks.as_ptr().add(n))
+            }
+            FetchStorage::SparseSet(sparse_set) => {
+                let (component, ticks) = sparse_set
+                    .get_with_ticks(n)
+                    .expect("fetched entity should have the sparse set component");
+                (component.as_ptr().cast::<T>(), ticks.as_ptr())
+            }
+        }
+    }
+}
+
 #[doc(hidden)]
-pub struct FetchRead<T>(NonNull<T>);
+pub struct FetchRead<T>(FetchStorage<T>);
 
 unsafe impl<T> ReadOnlyFetch for FetchRead<T> {}
 
 impl<'a, T: Component> Fetch<'a> for FetchRead<T> {
     type Item = &'a T;
 
-    const DANGLING: Self = Self(NonNull::dangling());
+    const DANGLING: Self = Self(FetchStorage::DANGLING);
+
+    #[inline]
+    fn includes_disabled() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Disabled>()
+    }
+
+    unsafe fn get(
+        archetype: &'a Archetype,
+        sparse_sets: &'a SparseSets,
+        offset: usize,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Option<Self> {
+        FetchStorage::new(archetype, sparse_sets, offset).map(Self)
+    }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        archetype
-            .get::<T>()
-            .map(|x| Self(NonNull::new_unchecked(x.as_ptr().add(offset))))
+    #[inline]
+    unsafe fn matches_entity(&self, n: usize) -> bool {
+        self.0.contains(n)
     }
 
     #[inline]
     unsafe fn fetch(&self, n: usize) -> &'a T {
-        &*self.0.as_ptr().add(n)
+        &*self.0.get(n).0
     }
 
     #[inline]
@@ -133,21 +238,28 @@ impl<T: WorldQuery> WorldQuery for Option<T> {
 /// Unique borrow of an entity's component
 pub struct Mut<'a, T: Component> {
     pub(crate) value: &'a mut T,
-    pub(crate) flags: &'a mut ComponentFlags,
+    pub(crate) ticks: &'a mut ComponentTicks,
+    pub(crate) change_tick: u32,
 }
 
 impl<'a, T: Component> Mut<'a, T> {
-    /// Creates a new mutable reference to a component. This is unsafe because the index bounds are not checked.
+    /// Creates a new mutable reference to a component. Mutations made through it are recorded at
+    /// `change_tick`. This is unsafe because the index bounds are not checked.
     ///
     /// # Safety
     /// This doesn't check the bounds of index in archetype
-    pub unsafe fn new(archetype: &'a Archetype, index: usize) -> Result<Self, MissingComponent> {
+    pub unsafe fn new(
+        archetype: &'a Archetype,
+        index: usize,
+        change_tick: u32,
+    ) -> Result<Self, MissingComponent> {
         let (target, type_state) = archetype
             .get_with_type_state::<T>()
             .ok_or_else(MissingComponent::new::<T>)?;
         Ok(Self {
             value: &mut *target.as_ptr().add(index),
-            flags: &mut *type_state.component_flags().as_ptr().add(index),
+            ticks: &mut *type_state.component_ticks().as_ptr().add(index),
+            change_tick,
         })
     }
 }
@@ -167,7 +279,7 @@ impl<'a, T: Component> Deref for Mut<'a, T> {
 impl<'a, T: Component> DerefMut for Mut<'a, T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
-        self.flags.insert(ComponentFlags::MUTATED);
+        self.ticks.set_mutated(self.change_tick);
         self.value
     }
 }
@@ -182,29 +294,35 @@ impl<'a, T: Component> WorldQuery for Mut<'a, T> {
     type Fetch = FetchMut<T>;
 }
 #[doc(hidden)]
-pub struct FetchMut<T>(NonNull<T>, NonNull<ComponentFlags>);
+pub struct FetchMut<T>(FetchStorage<T>, u32);
 
 impl<'a, T: Component> Fetch<'a> for FetchMut<T> {
     type Item = Mut<'a, T>;
 
-    const DANGLING: Self = Self(NonNull::dangling(), NonNull::dangling());
+    const DANGLING: Self = Self(FetchStorage::DANGLING, 0);
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        archetype
-            .get_with_type_state::<T>()
-            .map(|(components, type_state)| {
-                Self(
-                    NonNull::new_unchecked(components.as_ptr().add(offset)),
-                    NonNull::new_unchecked(type_state.component_flags().as_ptr().add(offset)),
-                )
-            })
+    unsafe fn get(
+        archetype: &'a Archetype,
+        sparse_sets: &'a SparseSets,
+        offset: usize,
+        _last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self> {
+        FetchStorage::new(archetype, sparse_sets, offset).map(|storage| Self(storage, change_tick))
+    }
+
+    #[inline]
+    unsafe fn matches_entity(&self, n: usize) -> bool {
+        self.0.contains(n)
     }
 
     #[inline]
     unsafe fn fetch(&self, n: usize) -> Mut<'a, T> {
+        let (value, ticks) = self.0.get(n);
         Mut {
-            value: &mut *self.0.as_ptr().add(n),
-            flags: &mut *self.1.as_ptr().add(n),
+            value: &mut *value,
+            ticks: &mut *ticks,
+            change_tick: self.1,
         }
     }
 
@@ -212,6 +330,11 @@ impl<'a, T: Component> Fetch<'a> for FetchMut<T> {
     fn access() -> QueryAccess {
         QueryAccess::write::<T>()
     }
+
+    #[inline]
+    fn includes_disabled() -> bool {
+        TypeId::of::<T>() == TypeId::of::<Disabled>()
+    }
 }
 
 #[doc(hidden)]
@@ -228,12 +351,112 @@ impl<'a, T: Fetch<'a>> Fetch<'a> for TryFetch<T> {
         QueryAccess::optional(T::access())
     }
 
-    unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-        Some(Self(T::get(archetype, offset)))
+    #[inline]
+    fn includes_disabled() -> bool {
+        T::includes_disabled()
+    }
+
+    unsafe fn get(
+        archetype: &'a Archetype,
+        sparse_sets: &'a SparseSets,
+        offset: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Self> {
+        Some(Self(T::get(
+            archetype,
+            sparse_sets,
+            offset,
+            last_change_tick,
+            change_tick,
+        )))
     }
 
     unsafe fn fetch(&self, n: usize) -> Option<T::Item> {
-        Some(self.0.as_ref()?.fetch(n))
+        let fetch = self.0.as_ref()?;
+        if fetch.matches_entity(n) {
+            Some(fetch.fetch(n))
+        } else {
+            None
+        }
+    }
+}
+
+/// The indices of the archetypes matched by a query, kept up to date incrementally as new
+/// archetypes are added to a `World`
+pub(crate) struct MatchedArchetypes {
+    matches: fn(&Archetype, &SparseSets) -> bool,
+    indices: Vec<u32>,
+    generation: Option<ArchetypesGeneration>,
+}
+
+impl MatchedArchetypes {
+    pub(crate) fn new<Q: WorldQuery, F: QueryFilter>() -> Self {
+        Self {
+            // SAFE: the fetch and filter are only used to check whether the archetype matches.
+            // they never access component data
+            matches: |archetype, sparse_sets| unsafe {
+                visits_archetype::<Q, F>(archetype)
+                    && Q::Fetch::get(archetype, sparse_sets, 0, 0, 0).is_some()
+                    && F::get_entity_filter(archetype, sparse_sets, 0, 0, 0).is_some()
+            },
+            indices: Vec::new(),
+            generation: None,
+        }
+    }
+
+    /// Forgets all matched archetypes
+    pub(crate) fn clear(&mut self) {
+        self.indices.clear();
+        self.generation = None;
+    }
+
+    /// Checks whether the archetype at `index` matches the query and records it if it does
+    pub(crate) fn add_archetype(
+        &mut self,
+        archetype: &Archetype,
+        index: u32,
+        sparse_sets: &SparseSets,
+    ) {
+        if (self.matches)(archetype, sparse_sets) {
+            self.indices.push(index);
+        }
+    }
+
+    /// Marks the matched archetypes as up to date with the given generation
+    pub(crate) fn set_generation(&mut self, generation: ArchetypesGeneration) {
+        self.generation = Some(generation);
+    }
+
+    /// Returns the matched archetype indices if they are up to date with `world`
+    pub(crate) fn get(&self, world: &World) -> Option<&[u32]> {
+        if self.generation == Some(world.archetypes_generation()) {
+            Some(&self.indices)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a query visits `archetype`. Archetypes of `Disabled` entities are only visited by
+/// queries that ask for them.
+#[inline]
+pub(crate) fn visits_archetype<Q: WorldQuery, F: QueryFilter>(archetype: &Archetype) -> bool {
+    <Q::Fetch as Fetch>::includes_disabled()
+        || F::includes_disabled()
+        || !archetype.has::<Disabled>()
+}
+
+/// Returns the `n`th archetype to visit, either from the matched archetype indices or from all archetypes
+#[inline]
+fn nth_archetype<'w>(
+    archetypes: &'w [Archetype],
+    archetype_indices: Option<&[u32]>,
+    n: usize,
+) -> Option<&'w Archetype> {
+    match archetype_indices {
+        Some(indices) => indices.get(n).map(|&index| &archetypes[index as usize]),
+        None => archetypes.get(n),
     }
 }
 
@@ -246,9 +469,13 @@ struct ChunkInfo<Q: WorldQuery, F: QueryFilter> {
 /// Iterator over the set of entities with the components in `Q`
 pub struct QueryIter<'w, Q: WorldQuery, F: QueryFilter> {
     archetypes: &'w [Archetype],
+    archetype_indices: Option<&'w [u32]>,
+    sparse_sets: &'w SparseSets,
     archetype_index: usize,
     chunk_info: ChunkInfo<Q, F>,
     chunk_position: usize,
+    last_change_tick: u32,
+    change_tick: u32,
 }
 
 impl<'w, Q: WorldQuery, F: QueryFilter> QueryIter<'w, Q, F> {
@@ -258,16 +485,33 @@ impl<'w, Q: WorldQuery, F: QueryFilter> QueryIter<'w, Q, F> {
         filter: F::EntityFilter::DANGLING,
     };
 
-    /// Creates a new QueryIter
+    /// Creates a new QueryIter that reports changes made after `last_change_tick`
     #[inline]
-    pub(crate) fn new(archetypes: &'w [Archetype]) -> Self {
+    pub(crate) fn new(
+        archetypes: &'w [Archetype],
+        sparse_sets: &'w SparseSets,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Self {
         Self {
             archetypes,
+            archetype_indices: None,
+            sparse_sets,
             archetype_index: 0,
             chunk_info: Self::EMPTY,
             chunk_position: 0,
+            last_change_tick,
+            change_tick,
         }
     }
+
+    /// Only visits the archetypes at the given indices, which must include every archetype
+    /// matched by the query
+    #[inline]
+    pub(crate) fn with_archetype_indices(mut self, archetype_indices: Option<&'w [u32]>) -> Self {
+        self.archetype_indices = archetype_indices;
+        self
+    }
 }
 
 impl<'w, Q: WorldQuery, F: QueryFilter> Iterator for QueryIter<'w, Q, F> {
@@ -278,18 +522,38 @@ impl<'w, Q: WorldQuery, F: QueryFilter> Iterator for QueryIter<'w, Q, F> {
         unsafe {
             loop {
                 if self.chunk_position == self.chunk_info.len {
-                    let archetype = self.archetypes.get(self.archetype_index)?;
+                    let archetype = nth_archetype(
+                        self.archetypes,
+                        self.archetype_indices,
+                        self.archetype_index,
+                    )?;
                     self.archetype_index += 1;
                     self.chunk_position = 0;
-                    self.chunk_info = Q::Fetch::get(archetype, 0)
-                        .and_then(|fetch| {
-                            Some(ChunkInfo {
-                                fetch,
-                                len: archetype.len(),
-                                filter: F::get_entity_filter(archetype)?,
-                            })
+                    if !visits_archetype::<Q, F>(archetype) {
+                        self.chunk_info = Self::EMPTY;
+                        continue;
+                    }
+                    self.chunk_info = Q::Fetch::get(
+                        archetype,
+                        self.sparse_sets,
+                        0,
+                        self.last_change_tick,
+                        self.change_tick,
+                    )
+                    .and_then(|fetch| {
+                        Some(ChunkInfo {
+                            fetch,
+                            len: archetype.len(),
+                            filter: F::get_entity_filter(
+                                archetype,
+                                self.sparse_sets,
+                                0,
+                                self.last_change_tick,
+                                self.change_tick,
+                            )?,
                         })
-                        .unwrap_or(Self::EMPTY);
+                    })
+                    .unwrap_or(Self::EMPTY);
                     continue;
                 }
 
@@ -297,6 +561,10 @@ impl<'w, Q: WorldQuery, F: QueryFilter> Iterator for QueryIter<'w, Q, F> {
                     .chunk_info
                     .filter
                     .matches_entity(self.chunk_position as usize)
+                    || !self
+                        .chunk_info
+                        .fetch
+                        .matches_entity(self.chunk_position as usize)
                 {
                     self.chunk_position += 1;
                     continue;
@@ -311,13 +579,28 @@ impl<'w, Q: WorldQuery, F: QueryFilter> Iterator for QueryIter<'w, Q, F> {
 }
 
 // if the Fetch is an UnfilteredFetch, then we can cheaply compute the length of the query by getting
-// the length of each matching archetype
+// the length of each matching archetype. Components stored in sparse sets are checked per entity.
 impl<'w, Q: WorldQuery> ExactSizeIterator for QueryIter<'w, Q, ()> {
     fn len(&self) -> usize {
-        self.archetypes
-            .iter()
-            .filter(|&archetype| unsafe { Q::Fetch::get(archetype, 0).is_some() })
-            .map(|x| x.len())
+        (0..)
+            .map(|n| nth_archetype(self.archetypes, self.archetype_indices, n))
+            .take_while(Option::is_some)
+            .flatten()
+            .filter(|archetype| visits_archetype::<Q, ()>(archetype))
+            .filter_map(|archetype| unsafe {
+                let fetch = Q::Fetch::get(
+                    archetype,
+                    self.sparse_sets,
+                    0,
+                    self.last_change_tick,
+                    self.change_tick,
+                )?;
+                Some(
+                    (0..archetype.len())
+                        .filter(|&n| fetch.matches_entity(n))
+                        .count(),
+                )
+            })
             .sum()
     }
 }
@@ -336,7 +619,9 @@ impl<Q: WorldQuery, F: QueryFilter> ChunkIter<Q, F> {
                 return None;
             }
 
-            if !self.filter.matches_entity(self.position as usize) {
+            if !self.filter.matches_entity(self.position)
+                || !self.fetch.matches_entity(self.position)
+            {
                 self.position += 1;
                 continue;
             }
@@ -351,22 +636,45 @@ impl<Q: WorldQuery, F: QueryFilter> ChunkIter<Q, F> {
 /// Batched version of `QueryIter`
 pub struct BatchedIter<'w, Q: WorldQuery, F: QueryFilter> {
     archetypes: &'w [Archetype],
+    archetype_indices: Option<&'w [u32]>,
+    sparse_sets: &'w SparseSets,
     archetype_index: usize,
     batch_size: usize,
     batch: usize,
+    last_change_tick: u32,
+    change_tick: u32,
     _marker: PhantomData<(Q, F)>,
 }
 
 impl<'w, Q: WorldQuery, F: QueryFilter> BatchedIter<'w, Q, F> {
-    pub(crate) fn new(archetypes: &'w [Archetype], batch_size: usize) -> Self {
+    pub(crate) fn new(
+        archetypes: &'w [Archetype],
+        sparse_sets: &'w SparseSets,
+        batch_size: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Self {
+        assert!(batch_size > 0, "batch_size must be greater than zero");
         Self {
             archetypes,
+            archetype_indices: None,
+            sparse_sets,
             archetype_index: 0,
             batch_size,
             batch: 0,
+            last_change_tick,
+            change_tick,
             _marker: Default::default(),
         }
     }
+
+    /// Only visits the archetypes at the given indices, which must include every archetype
+    /// matched by the query
+    #[inline]
+    pub(crate) fn with_archetype_indices(mut self, archetype_indices: Option<&'w [u32]>) -> Self {
+        self.archetype_indices = archetype_indices;
+        self
+    }
 }
 
 unsafe impl<'w, Q: WorldQuery, F: QueryFilter> Send for BatchedIter<'w, Q, F> {}
@@ -377,16 +685,36 @@ impl<'w, Q: WorldQuery, F: QueryFilter> Iterator for BatchedIter<'w, Q, F> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let archetype = self.archetypes.get(self.archetype_index)?;
+            let archetype = nth_archetype(
+                self.archetypes,
+                self.archetype_indices,
+                self.archetype_index,
+            )?;
             let offset = self.batch_size * self.batch;
-            if offset >= archetype.len() {
+            if offset >= archetype.len() || !visits_archetype::<Q, F>(archetype) {
                 self.archetype_index += 1;
                 self.batch = 0;
                 continue;
             }
             if let (Some(fetch), Some(filter)) = (
-                unsafe { Q::Fetch::get(archetype, offset) },
-                F::get_entity_filter(archetype),
+                unsafe {
+                    Q::Fetch::get(
+                        archetype,
+                        self.sparse_sets,
+                        offset,
+                        self.last_change_tick,
+                        self.change_tick,
+                    )
+                },
+                unsafe {
+                    F::get_entity_filter(
+                        archetype,
+                        self.sparse_sets,
+                        offset,
+                        self.last_change_tick,
+                        self.change_tick,
+                    )
+                },
             ) {
                 self.batch += 1;
                 return Some(Batch {
@@ -441,9 +769,22 @@ macro_rules! tuple_impl {
                 ])
             }
 
+            #[inline]
+            fn includes_disabled() -> bool {
+                false $(|| $name::includes_disabled())*
+            }
+
+            #[allow(unused_variables)]
+            unsafe fn get(archetype: &'a Archetype, sparse_sets: &'a SparseSets, offset: usize, last_change_tick: u32, change_tick: u32) -> Option<Self> {
+                Some(($($name::get(archetype, sparse_sets, offset, last_change_tick, change_tick)?,)*))
+            }
+
             #[allow(unused_variables)]
-            unsafe fn get(archetype: &'a Archetype, offset: usize) -> Option<Self> {
-                Some(($($name::get(archetype, offset)?,)*))
+            #[inline]
+            unsafe fn matches_entity(&self, n: usize) -> bool {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+                true $(&& $name.matches_entity(n))*
             }
 
             #[allow(unused_variables)]
@@ -466,7 +807,9 @@ smaller_tuples_too!(tuple_impl, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
 
 #[cfg(test)]
 mod tests {
-    use crate::core::{Added, Changed, Component, Entity, Mutated, Or, QueryFilter, World};
+    use crate::core::{
+        Added, Changed, Component, Entity, Mutated, Or, QueryFilter, StorageType, World,
+    };
     use std::{vec, vec::Vec};
 
     use super::Mut;
@@ -640,6 +983,34 @@ mod tests {
         assert_eq!(get_changed(&world), vec![e1]);
     }
 
+    #[test]
+    fn batched_changed_query() {
+        let mut world = World::default();
+        world.register_component::<B>(StorageType::SparseSet);
+        let entities = (0..10)
+            .map(|i| world.spawn((A(i), B(i))))
+            .collect::<Vec<_>>();
+        world.clear_trackers();
+        for &entity in &entities[5..] {
+            world.get_mut::<A>(entity).unwrap().0 += 1;
+            world.get_mut::<B>(entity).unwrap().0 += 1;
+        }
+
+        // every batch but the first starts in the middle of the archetype
+        let changed = world
+            .query_batched_filtered::<&A, Changed<A>>(3)
+            .flatten()
+            .map(|a| a.0)
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![6, 7, 8, 9, 10]);
+        let mutated = world
+            .query_batched_filtered::<&B, Mutated<B>>(3)
+            .flatten()
+            .map(|b| b.0)
+            .collect::<Vec<_>>();
+        assert_eq!(mutated, vec![6, 7, 8, 9, 10]);
+    }
+
     #[test]
     fn exact_size_query() {
         let mut world = World::default();
@@ -651,4 +1022,62 @@ mod tests {
         // the following example shouldn't compile because Changed<A> is not an UnfilteredFetch
         // assert_eq!(world.query::<(Changed<A>, &B)>().len(), 2);
     }
+
+    #[test]
+    fn derived_world_query() {
+        use crate as bevy_ecs;
+        use crate::{ArchetypeComponent, Fetch, TypeAccess, WorldQuery};
+
+        #[derive(WorldQuery)]
+        struct Movement<'a> {
+            entity: Entity,
+            position: Mut<'a, A>,
+            velocity: &'a B,
+            frozen: Option<&'a C>,
+        }
+
+        #[derive(WorldQuery)]
+        struct Positions<'a> {
+            position: &'a A,
+            nested: Option<Velocities<'a>>,
+        }
+
+        #[derive(WorldQuery)]
+        struct Velocities<'a> {
+            velocity: &'a B,
+        }
+
+        let mut world = World::default();
+        let e1 = world.spawn((A(0), B(1)));
+        let e2 = world.spawn((A(0), B(2), C));
+        let e3 = world.spawn((A(5),));
+
+        for mut movement in world.query_mut::<Movement>() {
+            if movement.frozen.is_none() {
+                movement.position.0 += movement.velocity.0;
+            }
+            assert!(movement.entity == e1 || movement.entity == e2);
+        }
+
+        let positions = world
+            .query::<Positions>()
+            .map(|positions| {
+                (
+                    positions.position.0,
+                    positions.nested.map(|nested| nested.velocity.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1, Some(1)), (0, Some(2)), (5, None)]);
+
+        let mut access = TypeAccess::default();
+        <<Movement as WorldQuery>::Fetch as Fetch>::access()
+            .get_world_archetype_access(&world, Some(&mut access));
+        let archetype = world.get_entity_location(e1).unwrap().archetype;
+        assert!(access.is_write(&ArchetypeComponent::new::<A>(archetype)));
+        assert!(!access.is_write(&ArchetypeComponent::new::<B>(archetype)));
+        assert!(access.is_read_or_write(&ArchetypeComponent::new::<B>(archetype)));
+        let archetype = world.get_entity_location(e3).unwrap().archetype;
+        assert!(!access.is_read_or_write(&ArchetypeComponent::new::<A>(archetype)));
+    }
 }
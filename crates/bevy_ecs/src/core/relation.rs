@@ -0,0 +1,339 @@
+use crate::{Entity, EntityMap, IncludeDisabled, MapEntities, MapEntitiesError, World};
+use bevy_utils::HashMap;
+use parking_lot::RwLock;
+use std::{fmt, marker::PhantomData, sync::Arc};
+
+/// A typed edge from the entity holding this component to a `target` entity
+///
+/// `K` is a marker type naming the kind of relation, like `Owns` or `Targets`. An entity has at
+/// most one relation of each kind. Once the kind is registered with `World::register_relation`,
+/// the world keeps a reverse index of the relation, which is queried with
+/// `World::relation_sources`, or with `Query::iter_targeting` and `Query::par_iter_targeting`
+/// to combine it with a query. Despawning the target removes the relations pointing at it.
+///
+/// The target can't be changed in place, which would bypass the reverse index. Insert a new
+/// relation instead.
+pub struct Relation<K> {
+    target: Entity,
+    marker: PhantomData<fn() -> K>,
+}
+
+impl<K> Relation<K> {
+    /// Creates a relation pointing at `target`
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the entity this relation points at
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+}
+
+impl<K> Clone for Relation<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for Relation<K> {}
+
+impl<K> PartialEq for Relation<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.target == other.target
+    }
+}
+
+impl<K> Eq for Relation<K> {}
+
+impl<K> fmt::Debug for Relation<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Relation").field(&self.target).finish()
+    }
+}
+
+impl<K> MapEntities for Relation<K> {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+        self.target = entity_map.get(self.target)?;
+        Ok(())
+    }
+}
+
+/// The edges of one relation kind, in both directions
+#[derive(Debug, Default)]
+pub(crate) struct RelationEdges {
+    targets: HashMap<Entity, Entity>,
+    sources: HashMap<Entity, Vec<Entity>>,
+}
+
+impl RelationEdges {
+    fn insert(&mut self, source: Entity, target: Entity) {
+        if let Some(old_target) = self.targets.insert(source, target) {
+            self.remove_source(old_target, source);
+        }
+        self.sources.entry(target).or_default().push(source);
+    }
+
+    fn remove(&mut self, source: Entity) {
+        if let Some(target) = self.targets.remove(&source) {
+            self.remove_source(target, source);
+        }
+    }
+
+    fn remove_source(&mut self, target: Entity, source: Entity) {
+        if let Some(sources) = self.sources.get_mut(&target) {
+            sources.retain(|&entity| entity != source);
+            if sources.is_empty() {
+                self.sources.remove(&target);
+            }
+        }
+    }
+
+    pub(crate) fn sources(&self, target: Entity) -> &[Entity] {
+        self.sources.get(&target).map_or(&[], |sources| sources)
+    }
+}
+
+/// The reverse index of a registered relation kind
+#[derive(Clone)]
+pub(crate) struct RelationIndex {
+    // hooks only get shared access to the world, so they update the edges through a lock
+    pub(crate) edges: Arc<RwLock<RelationEdges>>,
+    remove_relation: fn(&mut World, Entity),
+}
+
+impl RelationIndex {
+    pub(crate) fn new<K: 'static>() -> Self {
+        Self {
+            edges: Default::default(),
+            remove_relation: |world, source| {
+                let _ = world.remove_one::<Relation<K>>(source);
+            },
+        }
+    }
+
+    /// Installs the hooks keeping the index in sync with the `Relation<K>` components of `world`
+    pub(crate) fn add_hooks<K: 'static>(&self, world: &mut World) {
+        let (inserted, removed) = (self.edges.clone(), self.edges.clone());
+        world
+            .component_hooks_mut::<Relation<K>>()
+            .on_insert(move |world, source, _| {
+                let relation = world.get::<Relation<K>>(source).unwrap();
+                inserted.write().insert(source, relation.target);
+            })
+            .on_remove(move |_, source, _| removed.write().remove(source));
+    }
+
+    /// Adds the `Relation<K>` components already in `world` to the index
+    pub(crate) fn add_existing<K: 'static>(&self, world: &World) {
+        let mut edges = self.edges.write();
+        for (source, relation) in world.query_filtered::<(Entity, &Relation<K>), IncludeDisabled>()
+        {
+            edges.insert(source, relation.target);
+        }
+    }
+
+    /// Removes the relations pointing at `target`
+    pub(crate) fn remove_targeting(&self, world: &mut World, target: Entity) {
+        let sources = self.edges.read().sources(target).to_vec();
+        for source in sources {
+            (self.remove_relation)(world, source);
+        }
+    }
+}
+
+impl fmt::Debug for RelationIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RelationIndex")
+            .field("edges", &self.edges)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Relation;
+    use crate::{
+        resource::{Res, ResMut, Resources},
+        Changed, Commands, Entity, IntoSystem, Query, Schedule, SystemStage, With, World,
+    };
+    use bevy_tasks::{ComputeTaskPool, ParallelIterator, TaskPool};
+
+    struct Owns;
+    struct Targets;
+    struct Name(&'static str);
+    struct Item(u32);
+
+    #[test]
+    fn reverse_index_follows_changes() {
+        let mut world = World::new();
+        world.register_relation::<Owns>();
+        let player = world.spawn(());
+        let chest = world.spawn(());
+        let sword = world.spawn((Relation::<Owns>::new(player),));
+        let shield = world.spawn((Relation::<Owns>::new(player),));
+        assert_eq!(world.relation_sources::<Owns>(player), vec![sword, shield]);
+
+        world
+            .insert_one(shield, Relation::<Owns>::new(chest))
+            .unwrap();
+        assert_eq!(world.relation_sources::<Owns>(player), vec![sword]);
+        assert_eq!(world.relation_sources::<Owns>(chest), vec![shield]);
+
+        world.remove_one::<Relation<Owns>>(sword).unwrap();
+        assert!(world.relation_sources::<Owns>(player).is_empty());
+        world.despawn(shield).unwrap();
+        assert!(world.relation_sources::<Owns>(chest).is_empty());
+    }
+
+    #[test]
+    fn despawning_target_removes_relations() {
+        let mut world = World::new();
+        world.register_relation::<Owns>();
+        world.register_relation::<Targets>();
+        let player = world.spawn(());
+        let item = world.spawn((
+            Relation::<Owns>::new(player),
+            Relation::<Targets>::new(player),
+        ));
+        let other = world.spawn((Relation::<Targets>::new(item),));
+
+        world.despawn(player).unwrap();
+        assert!(world.get::<Relation<Owns>>(item).is_err());
+        assert!(world.get::<Relation<Targets>>(item).is_err());
+        assert_eq!(
+            *world.get::<Relation<Targets>>(other).unwrap(),
+            Relation::new(item)
+        );
+    }
+
+    #[test]
+    fn register_indexes_existing_relations() {
+        let mut world = World::new();
+        let player = world.spawn(());
+        let sword = world.spawn((Relation::<Owns>::new(player),));
+        world.register_relation::<Owns>();
+        world.register_relation::<Owns>();
+        assert_eq!(world.relation_sources::<Owns>(player), vec![sword]);
+    }
+
+    #[test]
+    fn query_entities_targeting() {
+        fn equip(commands: &mut Commands, players: Query<(Entity, &Name)>) {
+            for (player, name) in players.iter() {
+                if name.0 == "alice" {
+                    commands.spawn((Item(0), Relation::<Owns>::new(player)));
+                }
+            }
+        }
+
+        fn upgrade(players: Query<Entity, With<Name>>, mut items: Query<&mut Item>) {
+            for player in players.iter() {
+                for mut item in items.iter_targeting_mut::<Owns>(player) {
+                    item.0 += 1;
+                }
+            }
+        }
+
+        fn check(players: Query<(Entity, &Name)>, items: Query<&Item>) {
+            for (player, name) in players.iter() {
+                let owned = items
+                    .iter_targeting::<Owns>(player)
+                    .map(|item| item.0)
+                    .collect::<Vec<_>>();
+                if name.0 == "alice" {
+                    assert_eq!(owned, vec![1]);
+                } else {
+                    assert!(owned.is_empty());
+                }
+            }
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        world.register_relation::<Owns>();
+        world.spawn((Name("alice"),));
+        world.spawn((Name("bob"),));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("equip", SystemStage::single(equip.system()));
+        schedule.add_stage("upgrade", SystemStage::single(upgrade.system()));
+        schedule.add_stage("check", SystemStage::single(check.system()));
+        schedule.initialize_and_run(&mut world, &mut resources);
+    }
+
+    #[test]
+    fn query_targeting_applies_filters() {
+        struct Broken;
+
+        #[derive(Default)]
+        struct Found {
+            changed: Vec<u32>,
+            changed_par: Vec<u32>,
+        }
+
+        fn repair(
+            pool: Res<ComputeTaskPool>,
+            players: Query<Entity, With<Name>>,
+            mut items: Query<&mut Item, With<Broken>>,
+        ) {
+            for player in players.iter() {
+                items
+                    .par_iter_targeting_mut::<Owns>(2, player)
+                    .for_each(&pool, |mut item| item.0 += 100);
+            }
+        }
+
+        fn find(
+            pool: Res<ComputeTaskPool>,
+            mut found: ResMut<Found>,
+            players: Query<(Entity, &Name)>,
+            items: Query<&Item, Changed<Item>>,
+        ) {
+            for (player, name) in players.iter() {
+                if name.0 == "alice" {
+                    found.changed = items
+                        .iter_targeting::<Owns>(player)
+                        .map(|item| item.0)
+                        .collect();
+                    found.changed.sort_unstable();
+                    found.changed_par = items
+                        .par_iter_targeting::<Owns>(2, player)
+                        .map(|item| item.0)
+                        .collect(&pool);
+                    found.changed_par.sort_unstable();
+                }
+            }
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(Found::default());
+        world.register_relation::<Owns>();
+        let alice = world.spawn((Name("alice"),));
+        let bob = world.spawn((Name("bob"),));
+        for i in 0..10 {
+            let item = world.spawn((Item(i), Relation::<Owns>::new(alice)));
+            if i % 2 == 0 {
+                world.insert_one(item, Broken).unwrap();
+            }
+        }
+        world.spawn((Item(10), Broken, Relation::<Owns>::new(bob)));
+        world.spawn((Item(11), Broken));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("repair", SystemStage::single(repair.system()));
+        schedule.add_stage("find", SystemStage::single(find.system()));
+        schedule.initialize_and_run(&mut world, &mut resources);
+        schedule.initialize_and_run(&mut world, &mut resources);
+
+        // only the broken items of alice were repaired since `find` last ran
+        let found = resources.get::<Found>().unwrap();
+        assert_eq!(found.changed, vec![200, 202, 204, 206, 208]);
+        assert_eq!(found.changed_par, found.changed);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::Entity;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// The number of calls to `RemovedComponentLog::update` a removal is kept for at most
+pub(crate) const RETAINED_FRAMES: usize = 64;
+
+/// The entities that had a component of a single type removed
+///
+/// Each reader owns a cursor that counts how many removals it has read. Removals are kept until
+/// every reader has read them, so readers observe each removal exactly once no matter how often
+/// they run, but for no more than [RETAINED_FRAMES] frames: a reader that doesn't run for longer
+/// than that misses the oldest removals. Readers are dropped once nothing else holds their cursor.
+#[derive(Debug, Default)]
+pub(crate) struct RemovedComponentLog {
+    entities: Vec<Entity>,
+    /// The number of removals that were trimmed from the front of `entities`
+    trimmed: usize,
+    /// The number of removals recorded before the last call to `update`
+    frame_start: usize,
+    readers: Vec<Arc<AtomicUsize>>,
+    /// The value of `frame_start` after each of the last [RETAINED_FRAMES] calls to `update`
+    frame_starts: VecDeque<usize>,
+}
+
+impl RemovedComponentLog {
+    pub fn push(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+
+    pub fn extend(&mut self, entities: impl IntoIterator<Item = Entity>) {
+        self.entities.extend(entities);
+    }
+
+    /// Returns the removals recorded since the last call to `update`
+    pub fn current_frame(&self) -> &[Entity] {
+        &self.entities[self.frame_start - self.trimmed..]
+    }
+
+    /// Registers a new reader. It will observe every removal recorded since the last call to
+    /// `update`.
+    pub fn add_reader(&mut self) -> Arc<AtomicUsize> {
+        let reader = Arc::new(AtomicUsize::new(self.frame_start));
+        self.readers.push(reader.clone());
+        reader
+    }
+
+    /// Returns the removals that `reader` has not read yet and marks them as read
+    pub fn read(&self, reader: &AtomicUsize) -> &[Entity] {
+        let total = self.trimmed + self.entities.len();
+        let start = reader
+            .swap(total, Ordering::AcqRel)
+            .max(self.trimmed)
+            .min(total);
+        &self.entities[start - self.trimmed..]
+    }
+
+    /// Starts a new frame and drops the removals that every reader has read, or that were
+    /// recorded more than [RETAINED_FRAMES] frames ago
+    pub fn update(&mut self) {
+        self.frame_start = self.trimmed + self.entities.len();
+        self.frame_starts.push_back(self.frame_start);
+        if self.frame_starts.len() > RETAINED_FRAMES {
+            self.frame_starts.pop_front();
+        }
+        let expired = if self.frame_starts.len() == RETAINED_FRAMES {
+            self.frame_starts[0]
+        } else {
+            0
+        };
+        self.readers.retain(|reader| Arc::strong_count(reader) > 1);
+        let read_by_all = self
+            .readers
+            .iter()
+            .map(|reader| reader.load(Ordering::Acquire))
+            .min()
+            .unwrap_or(self.frame_start)
+            .min(self.frame_start)
+            .max(expired);
+        if read_by_all > self.trimmed {
+            self.entities.drain(..read_by_all - self.trimmed);
+            self.trimmed = read_by_all;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RemovedComponentLog, RETAINED_FRAMES};
+    use crate::Entity;
+
+    #[test]
+    fn removals_are_kept_until_read_by_all_readers() {
+        let mut log = RemovedComponentLog::default();
+        let fast = log.add_reader();
+        let slow = log.add_reader();
+        let e1 = Entity::new(1);
+        let e2 = Entity::new(2);
+
+        log.push(e1);
+        assert_eq!(log.read(&fast), &[e1]);
+        assert_eq!(log.read(&fast), &[]);
+        log.update();
+        assert_eq!(log.current_frame(), &[]);
+
+        log.push(e2);
+        log.update();
+        assert_eq!(log.read(&fast), &[e2]);
+        assert_eq!(log.read(&slow), &[e1, e2]);
+
+        let late = log.add_reader();
+        log.update();
+        assert_eq!(log.read(&late), &[]);
+
+        drop(slow);
+        drop(late);
+        log.update();
+        assert!(
+            log.entities.is_empty(),
+            "dropped readers should not hold on to removals"
+        );
+    }
+
+    #[test]
+    fn removals_expire_for_readers_that_never_run() {
+        let mut log = RemovedComponentLog::default();
+        let running = log.add_reader();
+        let never_runs = log.add_reader();
+        for frame in 0..RETAINED_FRAMES * 3 {
+            let entity = Entity::new(frame as u32);
+            log.push(entity);
+            assert_eq!(log.read(&running), &[entity]);
+            log.update();
+            assert!(log.entities.len() <= RETAINED_FRAMES);
+        }
+
+        // only the removals of the last frames are left
+        let left = log.read(&never_runs);
+        assert_eq!(left.len(), RETAINED_FRAMES - 1);
+        assert_eq!(
+            left.last(),
+            Some(&Entity::new(RETAINED_FRAMES as u32 * 3 - 1))
+        );
+    }
+}
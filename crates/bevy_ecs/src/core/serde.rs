@@ -1,6 +1,6 @@
 // modified by Bevy contributors
 
-use crate::Entity;
+use crate::{Disabled, Entity};
 use serde::{de::Visitor, Deserialize, Serialize, Serializer};
 
 impl Serialize for Entity {
@@ -37,3 +37,38 @@ impl<'de> Visitor<'de> for EntityVisitor {
         Ok(Entity::new(v))
     }
 }
+
+impl Serialize for Disabled {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_unit_struct("Disabled")
+    }
+}
+
+impl<'de> Deserialize<'de> for Disabled {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_unit_struct("Disabled", DisabledVisitor)
+    }
+}
+
+struct DisabledVisitor;
+
+impl<'de> Visitor<'de> for DisabledVisitor {
+    type Value = Disabled;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("expected Disabled")
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Disabled)
+    }
+}
@@ -0,0 +1,455 @@
+use crate::{
+    resource::{Resource, Resources},
+    Component, Entity, EntityMap, IncludeDisabled, World,
+};
+use bevy_utils::HashSet;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    any::{type_name, Any, TypeId},
+    fmt,
+    sync::Arc,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("failed to encode or decode the snapshot: {0}")]
+    Encoding(#[from] bincode::Error),
+    #[error("the snapshot was taken with a different filter")]
+    FilterMismatch,
+    #[error("the snapshot contains invalid entities")]
+    InvalidEntities,
+    #[error("the resource {0} does not exist")]
+    MissingResource(&'static str),
+}
+
+/// The values of one type, read back from a snapshot
+type SnapshotValues = Box<dyn Any>;
+
+/// How one type of a `SnapshotFilter` is written, read back and restored
+#[derive(Clone)]
+struct SnapshotType {
+    name: &'static str,
+    write: fn(&World, &Resources, &mut Vec<u8>) -> Result<(), SnapshotError>,
+    /// Reads the values of the type, checking they only belong to `alive` entities
+    read: fn(&mut &[u8], alive: &HashSet<Entity>) -> Result<SnapshotValues, SnapshotError>,
+    restore: fn(&mut World, &mut Resources, &EntityMap, SnapshotValues),
+}
+
+/// The component types and resources saved by `World::snapshot`
+///
+/// Types are saved with `serde`, in the order they were added to the filter.
+///
+/// # Example
+/// ```
+/// # use bevy_ecs::*;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// struct Position(f32, f32);
+/// #[derive(Serialize, Deserialize)]
+/// struct Score(u32);
+///
+/// let filter = SnapshotFilter::new().component::<Position>().resource::<Score>();
+/// ```
+#[derive(Clone, Default)]
+pub struct SnapshotFilter {
+    types: Arc<Vec<SnapshotType>>,
+}
+
+impl SnapshotFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Saves the components of type `T` of every entity
+    pub fn component<T: Component + Serialize + DeserializeOwned>(self) -> Self {
+        self.with(SnapshotType {
+            name: type_name::<T>(),
+            write: |world, _resources, bytes| {
+                let mut components = world
+                    .query_filtered::<(Entity, &T), IncludeDisabled>()
+                    .collect::<Vec<_>>();
+                components.sort_unstable_by_key(|(entity, _)| entity.id());
+                // `Entity` only serializes its ID, the generation is needed to restore it
+                let components = components
+                    .into_iter()
+                    .map(|(entity, component)| (entity.to_bits(), component))
+                    .collect::<Vec<_>>();
+                Ok(bincode::serialize_into(bytes, &components)?)
+            },
+            read: |bytes, alive| {
+                let components: Vec<(u64, T)> = bincode::deserialize_from(bytes)?;
+                let components = components
+                    .into_iter()
+                    .map(|(bits, component)| (Entity::from_bits(bits), component))
+                    .collect::<Vec<_>>();
+                if components.iter().any(|(entity, _)| !alive.contains(entity)) {
+                    return Err(SnapshotError::InvalidEntities);
+                }
+                Ok(Box::new(components))
+            },
+            restore: |world, _resources, entity_map, components| {
+                let components = *components.downcast::<Vec<(Entity, T)>>().unwrap();
+                let entities = components
+                    .iter()
+                    .map(|(entity, _)| *entity)
+                    .collect::<Vec<_>>();
+                let restored = entities.iter().copied().collect::<HashSet<_>>();
+                let stale = world
+                    .query_filtered::<(Entity, &T), IncludeDisabled>()
+                    .map(|(entity, _)| entity)
+                    .filter(|entity| !restored.contains(entity))
+                    .collect::<Vec<_>>();
+                for entity in stale {
+                    world.remove_one::<T>(entity).unwrap();
+                }
+                for (entity, component) in components {
+                    world.insert_one(entity, component).unwrap();
+                }
+                if let Some(map_entities) = world.entity_mapper(TypeId::of::<T>()) {
+                    // the entity map covers every ID the snapshot allocated, so this only fails
+                    // for IDs that never existed, which are left as they were decoded
+                    let _ = map_entities(world, &entities, entity_map);
+                }
+            },
+        })
+    }
+
+    /// Saves the resource `T`, which must exist when the snapshot is taken
+    pub fn resource<T: Resource + Serialize + DeserializeOwned>(self) -> Self {
+        self.with(SnapshotType {
+            name: type_name::<T>(),
+            write: |_world, resources, bytes| {
+                let resource = resources
+                    .get::<T>()
+                    .ok_or_else(|| SnapshotError::MissingResource(type_name::<T>()))?;
+                Ok(bincode::serialize_into(bytes, &*resource)?)
+            },
+            read: |bytes, _alive| {
+                let resource: T = bincode::deserialize_from(bytes)?;
+                Ok(Box::new(resource))
+            },
+            restore: |_world, resources, _entity_map, resource| {
+                resources.insert(*resource.downcast::<T>().unwrap());
+            },
+        })
+    }
+
+    fn with(mut self, ty: SnapshotType) -> Self {
+        Arc::make_mut(&mut self.types).push(ty);
+        self
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        self.types.iter().map(|ty| ty.name).collect()
+    }
+}
+
+impl fmt::Debug for SnapshotFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// A binary copy of the entities of a `World` and of the types selected by a `SnapshotFilter`,
+/// created with `World::snapshot`
+///
+/// Restoring a snapshot with `World::restore` brings back the entities with the same IDs and
+/// generations, and the entity allocator in the same state, so entities spawned afterwards get
+/// the same IDs as well.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    filter: SnapshotFilter,
+    bytes: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Creates a snapshot from bytes returned by `as_bytes`. `filter` must have the same types,
+    /// in the same order, as the filter the snapshot was taken with.
+    pub fn from_bytes(filter: SnapshotFilter, bytes: Vec<u8>) -> Self {
+        Self { filter, bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn filter(&self) -> &SnapshotFilter {
+        &self.filter
+    }
+}
+
+/// The filter types, the state of the entity allocator and the entities alive, at the start of
+/// a snapshot
+type SnapshotHeader = (Vec<String>, Vec<u32>, Vec<u32>, Vec<u64>);
+
+impl World {
+    /// Saves the entities of the world, and the components and resources selected by `filter`
+    ///
+    /// Taking a snapshot of the same state always gives the same bytes.
+    pub fn snapshot(
+        &mut self,
+        resources: &Resources,
+        filter: &SnapshotFilter,
+    ) -> Result<Snapshot, SnapshotError> {
+        let (generations, free, alive) = self.entities_state();
+        let names = filter.names().into_iter().map(String::from).collect();
+        let alive = alive.iter().map(|entity| entity.to_bits()).collect();
+        let header: SnapshotHeader = (names, generations, free, alive);
+        let mut bytes = bincode::serialize(&header)?;
+        for ty in filter.types.iter() {
+            (ty.write)(self, resources, &mut bytes)?;
+        }
+        Ok(Snapshot {
+            filter: filter.clone(),
+            bytes,
+        })
+    }
+
+    /// Brings the world back to the state saved in `snapshot`
+    ///
+    /// Entities spawned since are despawned, and despawned ones are spawned again with the same
+    /// IDs and generations. Components and resources selected by the filter of the snapshot are
+    /// replaced with their saved values, and removed from entities which didn't have them. Other
+    /// components are left untouched.
+    ///
+    /// Entities stored inside components are saved by their `Serialize` implementation, which
+    /// only keeps their ID. In component types registered with `World::register_map_entities`,
+    /// they are given the generation of the restored entity with that ID.
+    ///
+    /// Saved components are restored with `World::insert_one`, and stale ones are removed with
+    /// `World::remove_one`, so component hooks run and restored components are marked as added or
+    /// mutated at the current tick.
+    ///
+    /// The world is left untouched if the snapshot can't be decoded, or refers to entities it
+    /// doesn't contain.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::*;
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    /// let mut resources = Resources::default();
+    /// let filter = SnapshotFilter::new().component::<Health>();
+    /// let e = world.spawn((Health(10),));
+    /// let snapshot = world.snapshot(&resources, &filter).unwrap();
+    ///
+    /// world.despawn(e).unwrap();
+    /// world.spawn((Health(5),));
+    /// world.restore(&mut resources, &snapshot).unwrap();
+    /// assert_eq!(world.get::<Health>(e).unwrap().0, 10);
+    /// assert_eq!(world.query::<&Health>().count(), 1);
+    /// ```
+    pub fn restore(
+        &mut self,
+        resources: &mut Resources,
+        snapshot: &Snapshot,
+    ) -> Result<(), SnapshotError> {
+        let mut bytes = snapshot.as_bytes();
+        let (names, generations, free, alive): SnapshotHeader =
+            bincode::deserialize_from(&mut bytes)?;
+        if names != snapshot.filter.names() {
+            return Err(SnapshotError::FilterMismatch);
+        }
+        let alive = alive.into_iter().map(Entity::from_bits).collect::<Vec<_>>();
+        // each ID must either be free or alive with its current generation
+        let mut used = vec![false; generations.len()];
+        let ids = free
+            .iter()
+            .copied()
+            .chain(alive.iter().map(|entity| entity.id));
+        for id in ids {
+            match used.get_mut(id as usize) {
+                Some(used) if !*used => *used = true,
+                _ => return Err(SnapshotError::InvalidEntities),
+            }
+        }
+        if alive
+            .iter()
+            .any(|entity| generations[entity.id as usize] != entity.generation)
+        {
+            return Err(SnapshotError::InvalidEntities);
+        }
+        let alive_set = alive.iter().copied().collect::<HashSet<_>>();
+        let values = snapshot
+            .filter
+            .types
+            .iter()
+            .map(|ty| (ty.read)(&mut bytes, &alive_set))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // entities stored in components only keep their ID, this gives them their generation back
+        let mut entity_map = EntityMap::default();
+        for id in 0..generations.len() as u32 {
+            entity_map.insert(Entity::new(id), Entity::new(id));
+        }
+        for &entity in alive.iter() {
+            entity_map.insert(Entity::new(entity.id), entity);
+        }
+
+        self.set_entities_state(&generations, &free, &alive);
+        for (ty, value) in snapshot.filter.types.iter().zip(values) {
+            (ty.restore)(self, resources, &entity_map, value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Snapshot, SnapshotError, SnapshotFilter, SnapshotHeader};
+    use crate::{
+        resource::Resources, Disabled, Entity, EntityMap, IncludeDisabled, MapEntities,
+        MapEntitiesError, StorageType, World,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position(f32, f32);
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Name(String);
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Target(Entity);
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Selected;
+
+    impl MapEntities for Target {
+        fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+            self.0 = entity_map.get(self.0)?;
+            Ok(())
+        }
+    }
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Score(u32);
+    struct Local(u32);
+
+    fn filter() -> SnapshotFilter {
+        SnapshotFilter::new()
+            .component::<Position>()
+            .component::<Name>()
+            .component::<Target>()
+            .component::<Selected>()
+            .component::<Disabled>()
+            .resource::<Score>()
+    }
+
+    fn setup() -> (World, Resources, Vec<Entity>) {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        world.register_component::<Selected>(StorageType::SparseSet);
+        world.register_map_entities::<Target>();
+        resources.insert(Score(3));
+        // `a` reuses this ID with a new generation
+        let old = world.spawn(());
+        world.despawn(old).unwrap();
+        let a = world.spawn((Position(1.0, -0.0), Name("a".to_owned()), Local(0)));
+        let b = world.spawn((Position(f32::NAN, 2.5), Target(a), Selected));
+        let c = world.spawn((Name("c".to_owned()), Disabled));
+        let d = world.spawn((Target(c),));
+        world.despawn(d).unwrap();
+        (world, resources, vec![a, b, c])
+    }
+
+    #[test]
+    fn round_trip_is_bit_identical() {
+        let (mut world, mut resources, entities) = setup();
+        let (a, b, c) = (entities[0], entities[1], entities[2]);
+        let snapshot = world.snapshot(&resources, &filter()).unwrap();
+        let spawned = world.spawn(());
+
+        world.despawn(b).unwrap();
+        world.spawn((Position(0.0, 0.0), Target(spawned)));
+        world.get_mut::<Position>(a).unwrap().0 = 10.0;
+        world.remove_one::<Name>(a).unwrap();
+        world.insert(c, (Selected, Position(3.0, 3.0))).unwrap();
+        world.remove_one::<Disabled>(c).unwrap();
+        world.get_mut::<Local>(a).unwrap().0 = 1;
+        resources.insert(Score(4));
+
+        world.restore(&mut resources, &snapshot).unwrap();
+        let restored = world.snapshot(&resources, &filter()).unwrap();
+        assert_eq!(snapshot.as_bytes(), restored.as_bytes());
+        assert_eq!(*world.get::<Target>(b).unwrap(), Target(a));
+        assert_eq!(world.get::<Local>(a).unwrap().0, 1);
+        let alive = world
+            .query_filtered::<Entity, IncludeDisabled>()
+            .collect::<Vec<_>>();
+        assert_eq!(alive, entities);
+        assert_eq!(resources.get::<Score>().unwrap().0, 3);
+        // the allocator is restored too
+        assert_eq!(world.spawn(()), spawned);
+
+        let mut other_world = World::new();
+        let mut other_resources = Resources::default();
+        other_world.register_map_entities::<Target>();
+        let bytes = Snapshot::from_bytes(filter(), snapshot.as_bytes().to_vec());
+        other_world.restore(&mut other_resources, &bytes).unwrap();
+        let copied = other_world.snapshot(&other_resources, &filter()).unwrap();
+        assert_eq!(snapshot.as_bytes(), copied.as_bytes());
+        assert_eq!(*other_world.get::<Name>(c).unwrap(), Name("c".to_owned()));
+        assert!(other_world.get::<Selected>(b).is_ok());
+        assert_eq!(*other_world.get::<Target>(b).unwrap(), Target(a));
+    }
+
+    #[test]
+    fn restore_rejects_invalid_snapshots() {
+        let (mut world, mut resources, _) = setup();
+        let snapshot = world.snapshot(&resources, &filter()).unwrap();
+        let before = snapshot.as_bytes().to_vec();
+        let mut other_world = World::new();
+        let e = other_world.spawn((Position(0.0, 0.0),));
+
+        let reordered = SnapshotFilter::new()
+            .component::<Name>()
+            .component::<Position>();
+        let mismatched = Snapshot::from_bytes(reordered, before.clone());
+        assert!(matches!(
+            other_world.restore(&mut resources, &mismatched),
+            Err(SnapshotError::FilterMismatch)
+        ));
+        let truncated = Snapshot::from_bytes(filter(), before[..before.len() - 1].to_vec());
+        assert!(matches!(
+            other_world.restore(&mut resources, &truncated),
+            Err(SnapshotError::Encoding(_))
+        ));
+        assert!(other_world.get::<Position>(e).is_ok());
+
+        world
+            .snapshot(&Resources::default(), &filter())
+            .unwrap_err();
+    }
+
+    #[test]
+    fn restore_rejects_components_of_dead_entities() {
+        let filter = SnapshotFilter::new().component::<Position>();
+        let mut world = World::new();
+        let resources = Resources::default();
+        let a = world.spawn((Position(0.0, 0.0),));
+        let b = world.spawn((Position(1.0, 1.0),));
+        let both = world.snapshot(&resources, &filter).unwrap();
+        world.despawn(b).unwrap();
+        let only_a = world.snapshot(&resources, &filter).unwrap();
+
+        // the header of `only_a`, where `b` is dead, with the components of `both`
+        let header_len = |snapshot: &Snapshot| {
+            let mut rest = snapshot.as_bytes();
+            let _: SnapshotHeader = bincode::deserialize_from(&mut rest).unwrap();
+            snapshot.as_bytes().len() - rest.len()
+        };
+        let mut bytes = only_a.as_bytes()[..header_len(&only_a)].to_vec();
+        bytes.extend_from_slice(&both.as_bytes()[header_len(&both)..]);
+        let forged = Snapshot::from_bytes(filter, bytes);
+
+        let mut resources = Resources::default();
+        let c = world.spawn((Position(2.0, 2.0),));
+        assert!(matches!(
+            world.restore(&mut resources, &forged),
+            Err(SnapshotError::InvalidEntities)
+        ));
+        assert!(world.get::<Position>(a).is_ok());
+        assert!(world.get::<Position>(c).is_ok());
+    }
+}
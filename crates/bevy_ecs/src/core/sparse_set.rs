@@ -0,0 +1,585 @@
+use super::archetype::{TypeIdMap, TypeInfo};
+use crate::{Archetype, AtomicBorrow, ComponentTicks, Entity};
+use std::{
+    alloc::{alloc, dealloc, handle_alloc_error, realloc, Layout},
+    any::TypeId,
+    cell::UnsafeCell,
+    ptr::{self, NonNull},
+};
+
+/// How the components of a type are stored in a `World`
+///
+/// See `World::register_component`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum StorageType {
+    /// Components live in the archetype tables of their entities. They are the fastest to iterate,
+    /// but adding or removing one moves its entity to another archetype.
+    Table,
+    /// Components live in a sparse set indexed by entity. They are slower to iterate, but adding
+    /// or removing one leaves its entity in place, which suits frequently toggled marker
+    /// components.
+    SparseSet,
+}
+
+const EMPTY: u32 = u32::MAX;
+
+/// The components of a single type that are stored in a sparse set
+///
+/// Components are densely packed, and each entity id maps to the index of its component.
+#[derive(Debug)]
+pub struct ComponentSparseSet {
+    info: TypeInfo,
+    /// The dense index of each entity's component, indexed by entity id
+    sparse: Vec<u32>,
+    entities: Vec<Entity>,
+    component_ticks: Vec<ComponentTicks>,
+    // UnsafeCell allows unique references into `data` to be constructed while shared references
+    // containing the `ComponentSparseSet` exist
+    data: UnsafeCell<NonNull<u8>>,
+    capacity: usize,
+    borrow: AtomicBorrow,
+}
+
+impl ComponentSparseSet {
+    pub(crate) fn new(info: TypeInfo) -> Self {
+        Self {
+            info,
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            component_ticks: Vec::new(),
+            // zero sized components are never allocated, so the pointer only needs to be aligned
+            data: UnsafeCell::new(unsafe {
+                NonNull::new_unchecked(info.layout().align() as *mut u8)
+            }),
+            capacity: 0,
+            borrow: AtomicBorrow::new(),
+        }
+    }
+
+    #[allow(missing_docs)]
+    pub fn type_info(&self) -> &TypeInfo {
+        &self.info
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// The entities that have a component in this set, in storage order
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Whether `entity` has a component in this set
+    #[inline]
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dense_index(entity).is_some()
+    }
+
+    #[inline]
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        let dense = *self.sparse.get(entity.id as usize)? as usize;
+        if self.entities.get(dense) == Some(&entity) {
+            Some(dense)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    unsafe fn component_ptr(&self, dense: usize) -> NonNull<u8> {
+        NonNull::new_unchecked(
+            (*self.data.get())
+                .as_ptr()
+                .add(dense * self.info.layout().size()),
+        )
+    }
+
+    /// Returns a pointer to the component of `entity`, if it has one
+    #[inline]
+    pub(crate) fn get(&self, entity: Entity) -> Option<NonNull<u8>> {
+        let dense = self.dense_index(entity)?;
+        Some(unsafe { self.component_ptr(dense) })
+    }
+
+    /// Returns pointers to the component of `entity` and its change ticks, if it has one
+    #[inline]
+    pub(crate) fn get_with_ticks(
+        &self,
+        entity: Entity,
+    ) -> Option<(NonNull<u8>, NonNull<ComponentTicks>)> {
+        let dense = self.dense_index(entity)?;
+        unsafe {
+            Some((
+                self.component_ptr(dense),
+                NonNull::new_unchecked(self.component_ticks.as_ptr().add(dense) as *mut _),
+            ))
+        }
+    }
+
+    /// Moves `component` into the set. If `entity` already has a component, it is dropped and
+    /// replaced, and the new one is marked as mutated at `change_tick`. Otherwise the component is
+    /// marked as added at `change_tick`.
+    ///
+    /// # Safety
+    /// `component` must point to a valid value of this set's type. The value is moved into the
+    /// set and must not be used or dropped by the caller.
+    pub(crate) unsafe fn insert(&mut self, entity: Entity, component: *mut u8, change_tick: u32) {
+        let size = self.info.layout().size();
+        if let Some(dense) = self.dense_index(entity) {
+            let target = self.component_ptr(dense).as_ptr();
+            self.info.drop(target);
+            ptr::copy_nonoverlapping(component, target, size);
+            self.component_ticks[dense].set_mutated(change_tick);
+            return;
+        }
+
+        let dense = self.entities.len();
+        if dense == self.capacity {
+            self.grow();
+        }
+        ptr::copy_nonoverlapping(component, self.component_ptr(dense).as_ptr(), size);
+        self.entities.push(entity);
+        self.component_ticks.push(ComponentTicks::new(change_tick));
+        let id = entity.id as usize;
+        if id >= self.sparse.len() {
+            self.sparse.resize(id + 1, EMPTY);
+        }
+        self.sparse[id] = dense as u32;
+    }
+
+    /// Removes and drops the component of `entity`. Returns false if it had none.
+    pub(crate) fn remove(&mut self, entity: Entity) -> bool {
+        let info = self.info;
+        unsafe { self.remove_with(entity, |component| info.drop(component)) }
+    }
+
+    /// Removes the component of `entity` without dropping it. Returns false if it had none.
+    ///
+    /// # Safety
+    /// The component must have been moved out of the set beforehand.
+    pub(crate) unsafe fn forget(&mut self, entity: Entity) -> bool {
+        self.remove_with(entity, |_| {})
+    }
+
+    unsafe fn remove_with(&mut self, entity: Entity, f: impl FnOnce(*mut u8)) -> bool {
+        let dense = match self.dense_index(entity) {
+            Some(dense) => dense,
+            None => return false,
+        };
+        let last = self.entities.len() - 1;
+        let removed = self.component_ptr(dense).as_ptr();
+        f(removed);
+        if dense != last {
+            ptr::copy_nonoverlapping(
+                self.component_ptr(last).as_ptr(),
+                removed,
+                self.info.layout().size(),
+            );
+            self.sparse[self.entities[last].id as usize] = dense as u32;
+        }
+        self.entities.swap_remove(dense);
+        self.component_ticks.swap_remove(dense);
+        self.sparse[entity.id as usize] = EMPTY;
+        true
+    }
+
+    /// Drops every component in the set
+    pub(crate) fn clear(&mut self) {
+        for dense in 0..self.entities.len() {
+            unsafe {
+                self.info.drop(self.component_ptr(dense).as_ptr());
+            }
+        }
+        self.sparse.clear();
+        self.entities.clear();
+        self.component_ticks.clear();
+    }
+
+    /// Empties the set without dropping its components
+    ///
+    /// # Safety
+    /// The components must have been moved out of the set beforehand.
+    pub(crate) unsafe fn forget_all(&mut self) {
+        self.sparse.clear();
+        self.entities.clear();
+        self.component_ticks.clear();
+    }
+
+    /// Clamps the change ticks of every component in this set so they never become older than
+    /// `MAX_CHANGE_AGE` relative to `change_tick`
+    pub fn check_change_ticks(&mut self, change_tick: u32) {
+        for ticks in self.component_ticks.iter_mut() {
+            ticks.check_ticks(change_tick);
+        }
+    }
+
+    pub(crate) fn borrow_state(&self) -> &AtomicBorrow {
+        &self.borrow
+    }
+
+    fn grow(&mut self) {
+        let new_capacity = (self.capacity * 2).max(4);
+        let layout = self.info.layout();
+        if layout.size() != 0 {
+            unsafe {
+                let new_layout =
+                    Layout::from_size_align(layout.size() * new_capacity, layout.align()).unwrap();
+                let data = if self.capacity == 0 {
+                    alloc(new_layout)
+                } else {
+                    realloc(
+                        (*self.data.get()).as_ptr(),
+                        self.data_layout(),
+                        new_layout.size(),
+                    )
+                };
+                *self.data.get_mut() =
+                    NonNull::new(data).unwrap_or_else(|| handle_alloc_error(new_layout));
+            }
+        }
+        self.capacity = new_capacity;
+    }
+
+    fn data_layout(&self) -> Layout {
+        let layout = self.info.layout();
+        unsafe { Layout::from_size_align_unchecked(layout.size() * self.capacity, layout.align()) }
+    }
+}
+
+impl Drop for ComponentSparseSet {
+    fn drop(&mut self) {
+        self.clear();
+        if self.info.layout().size() != 0 && self.capacity != 0 {
+            unsafe {
+                dealloc((*self.data.get()).as_ptr(), self.data_layout());
+            }
+        }
+    }
+}
+
+/// The sparse sets of every component type registered with `StorageType::SparseSet`
+#[derive(Debug, Default)]
+pub struct SparseSets {
+    sets: TypeIdMap<ComponentSparseSet>,
+}
+
+impl SparseSets {
+    /// Returns the sparse set storing components of type `ty`, if that type is stored in one
+    #[inline]
+    pub fn get(&self, ty: TypeId) -> Option<&ComponentSparseSet> {
+        self.sets.get(&ty)
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self, ty: TypeId) -> Option<&mut ComponentSparseSet> {
+        self.sets.get_mut(&ty)
+    }
+
+    /// Whether components of type `ty` are stored in a sparse set
+    #[inline]
+    pub fn contains(&self, ty: TypeId) -> bool {
+        self.sets.contains_key(&ty)
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    #[allow(missing_docs)]
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, &ComponentSparseSet)> {
+        self.sets.iter().map(|(ty, set)| (*ty, set))
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (TypeId, &mut ComponentSparseSet)> {
+        self.sets.iter_mut().map(|(ty, set)| (*ty, set))
+    }
+
+    pub(crate) fn insert(&mut self, info: TypeInfo) {
+        self.sets.insert(info.id(), ComponentSparseSet::new(info));
+    }
+
+    pub(crate) fn remove(&mut self, ty: TypeId) {
+        self.sets.remove(&ty);
+    }
+}
+
+/// Looks up the sparse set components of a run of entities in an archetype
+#[derive(Copy, Clone)]
+pub(crate) struct SparseSetFetch {
+    entities: NonNull<Entity>,
+    set: NonNull<ComponentSparseSet>,
+}
+
+impl SparseSetFetch {
+    /// # Safety
+    /// `offset` must be in bounds of `archetype`
+    pub(crate) unsafe fn new(
+        archetype: &Archetype,
+        offset: usize,
+        set: &ComponentSparseSet,
+    ) -> Self {
+        Self {
+            entities: NonNull::new_unchecked(archetype.entities().as_ptr().add(offset)),
+            set: NonNull::from(set),
+        }
+    }
+
+    #[inline]
+    unsafe fn entity(&self, n: usize) -> Entity {
+        *self.entities.as_ptr().add(n)
+    }
+
+    /// Whether the `n`th entity has a component in the set
+    ///
+    /// # Safety
+    /// Bounds-checking must be performed externally
+    #[inline]
+    pub(crate) unsafe fn contains(&self, n: usize) -> bool {
+        self.set.as_ref().contains(self.entity(n))
+    }
+
+    /// # Safety
+    /// Bounds-checking must be performed externally
+    #[inline]
+    pub(crate) unsafe fn get_with_ticks(
+        &self,
+        n: usize,
+    ) -> Option<(NonNull<u8>, NonNull<ComponentTicks>)> {
+        self.set.as_ref().get_with_ticks(self.entity(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::filter::WithType, Added, Changed, Entity, Mut, Mutated, QueryFilter, StorageType,
+        With, Without, World,
+    };
+    use std::sync::Arc;
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct A(usize);
+    #[derive(Debug, Eq, PartialEq)]
+    struct Marker;
+    #[derive(Debug, Eq, PartialEq)]
+    struct Sparse(usize);
+
+    fn sparse_world() -> World {
+        let mut world = World::default();
+        world.register_component::<Marker>(StorageType::SparseSet);
+        world.register_component::<Sparse>(StorageType::SparseSet);
+        world
+    }
+
+    #[test]
+    fn insert_and_remove_keep_archetype() {
+        let mut world = sparse_world();
+        let e1 = world.spawn((A(1),));
+        let e2 = world.spawn((A(2), Marker));
+        let location = world.get_entity_location(e1).unwrap();
+        let archetypes = world.archetypes().len();
+        assert_eq!(
+            world.get_entity_location(e2).unwrap().archetype,
+            location.archetype
+        );
+
+        world.insert_one(e1, Marker).unwrap();
+        assert_eq!(
+            world.get_entity_location(e1).unwrap().archetype,
+            location.archetype
+        );
+        assert_eq!(*world.get::<Marker>(e1).unwrap(), Marker);
+
+        world.remove_one::<Marker>(e1).unwrap();
+        assert_eq!(
+            world.get_entity_location(e1).unwrap().archetype,
+            location.archetype
+        );
+        assert!(world.get::<Marker>(e1).is_err());
+        assert!(world.remove_one::<Marker>(e1).is_err());
+        assert_eq!(world.archetypes().len(), archetypes);
+        assert_eq!(world.removed::<Marker>(), &[e1]);
+
+        // mixed bundles move the table components and leave the sparse ones in place
+        world.insert(e1, (Sparse(3), true)).unwrap();
+        assert_eq!(world.remove::<(Sparse, bool)>(e1), Ok((Sparse(3), true)));
+        assert!(!world.has_component_type(e1, std::any::TypeId::of::<Sparse>()));
+        assert_eq!(*world.get::<A>(e1).unwrap(), A(1));
+    }
+
+    #[test]
+    fn query_sparse_set_components() {
+        let mut world = sparse_world();
+        let e1 = world.spawn((A(1), Marker));
+        let e2 = world.spawn((A(2),));
+        let e3 = world.spawn((Marker, Sparse(3)));
+        let e4 = world.spawn((true, Sparse(4)));
+
+        let marked = world
+            .query::<(Entity, &Marker)>()
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+        assert_eq!(marked.len(), 2);
+        assert!(marked.contains(&e1) && marked.contains(&e3));
+        assert_eq!(world.query::<&Marker>().len(), 2);
+        assert_eq!(world.query::<(&A, &Marker)>().len(), 1);
+
+        let with = world
+            .query_filtered::<Entity, With<Marker>>()
+            .collect::<Vec<_>>();
+        assert_eq!(with.len(), 2);
+        assert!(with.contains(&e1) && with.contains(&e3));
+        let without = world
+            .query_filtered::<&A, Without<Marker>>()
+            .collect::<Vec<_>>();
+        assert_eq!(without, vec![&A(2)]);
+
+        let optional = world
+            .query::<(&A, Option<&Marker>)>()
+            .map(|(a, marker)| (a.0, marker.is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(optional, vec![(1, true), (2, false)]);
+
+        for mut sparse in world.query_mut::<Mut<Sparse>>() {
+            sparse.0 *= 10;
+        }
+        assert_eq!(*world.get::<Sparse>(e3).unwrap(), Sparse(30));
+        assert_eq!(*world.get::<Sparse>(e4).unwrap(), Sparse(40));
+        assert!(world.query_one::<&Marker>(e2).is_err());
+        assert_eq!(*world.query_one::<&Sparse>(e4).unwrap(), Sparse(40));
+
+        let batched = world
+            .query_batched::<&Marker>(1)
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(batched.len(), 2);
+
+        let entity = world.entity(e3).unwrap();
+        assert_eq!(*entity.get::<Sparse>().unwrap(), Sparse(30));
+        assert!(entity.get::<A>().is_none());
+    }
+
+    #[test]
+    fn sparse_set_change_detection() {
+        let mut world = sparse_world();
+        let e1 = world.spawn((A(1), Sparse(1)));
+        let e2 = world.spawn((A(2),));
+        assert_eq!(
+            world
+                .query_filtered::<Entity, Added<Sparse>>()
+                .collect::<Vec<_>>(),
+            vec![e1]
+        );
+
+        world.clear_trackers();
+        world.insert_one(e2, Sparse(2)).unwrap();
+        world.insert_one(e1, Sparse(10)).unwrap();
+        assert_eq!(
+            world
+                .query_filtered::<Entity, Added<Sparse>>()
+                .collect::<Vec<_>>(),
+            vec![e2]
+        );
+        assert_eq!(
+            world
+                .query_filtered::<Entity, Mutated<Sparse>>()
+                .collect::<Vec<_>>(),
+            vec![e1]
+        );
+
+        world.clear_trackers();
+        assert_eq!(world.query_filtered::<Entity, Changed<Sparse>>().count(), 0);
+        world.get_mut::<Sparse>(e2).unwrap().0 += 1;
+        assert_eq!(
+            world
+                .query_filtered::<Entity, Changed<Sparse>>()
+                .collect::<Vec<_>>(),
+            vec![e2]
+        );
+    }
+
+    #[test]
+    fn batched_sparse_set_filters() {
+        let mut world = sparse_world();
+        let entities = (0..10).map(|i| world.spawn((A(i),))).collect::<Vec<_>>();
+        for &entity in &entities[5..] {
+            world.insert_one(entity, Marker).unwrap();
+        }
+
+        // batches smaller than the archetype start in the middle of it
+        fn batched<F: QueryFilter>(world: &World) -> Vec<usize> {
+            world
+                .query_batched_filtered::<&A, F>(3)
+                .flatten()
+                .map(|a| a.0)
+                .collect()
+        }
+        assert_eq!(batched::<With<Marker>>(&world), vec![5, 6, 7, 8, 9]);
+        assert_eq!(batched::<WithType<(Marker,)>>(&world), vec![5, 6, 7, 8, 9]);
+        assert_eq!(batched::<Without<Marker>>(&world), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn despawn_drops_sparse_set_components() {
+        struct Tracked(Arc<()>);
+
+        let mut world = World::default();
+        world.register_component::<Tracked>(StorageType::SparseSet);
+        let tracker = Arc::new(());
+        let e1 = world.spawn((A(1), Tracked(tracker.clone())));
+        let e2 = world.spawn((Tracked(tracker.clone()),));
+        let batch = world
+            .spawn_batch((0..3).map(|i| (A(i), Tracked(tracker.clone()))))
+            .collect::<Vec<_>>();
+        assert_eq!(Arc::strong_count(&tracker), 6);
+
+        world.insert_one(e2, Tracked(tracker.clone())).unwrap();
+        assert_eq!(Arc::strong_count(&tracker), 6);
+
+        world.despawn(e1).unwrap();
+        assert_eq!(Arc::strong_count(&tracker), 5);
+        assert_eq!(world.removed::<Tracked>(), &[e1]);
+        assert_eq!(world.query::<&Tracked>().count(), 4);
+
+        world.remove_one_by_one::<(Tracked,)>(batch[0]).unwrap();
+        assert_eq!(Arc::strong_count(&tracker), 4);
+
+        world.clear();
+        assert_eq!(Arc::strong_count(&tracker), 1);
+        assert_eq!(world.removed::<Tracked>().len(), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn register_used_component() {
+        let mut world = World::default();
+        world.spawn((Marker,));
+        world.register_component::<Marker>(StorageType::SparseSet);
+    }
+
+    #[test]
+    fn changing_storage_updates_archetype_transitions() {
+        let mut world = sparse_world();
+        let e = world.spawn((A(1),));
+        let archetype = world.get_entity_location(e).unwrap().archetype;
+        world.insert_one(e, Marker).unwrap();
+        world.remove_one::<Marker>(e).unwrap();
+
+        world.register_component::<Marker>(StorageType::Table);
+        world.insert_one(e, Marker).unwrap();
+        assert_ne!(world.get_entity_location(e).unwrap().archetype, archetype);
+        world.remove_one::<Marker>(e).unwrap();
+        assert_eq!(world.get_entity_location(e).unwrap().archetype, archetype);
+    }
+}
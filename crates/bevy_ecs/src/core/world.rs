@@ -15,12 +15,29 @@
 // modified by Bevy contributors
 
 use crate::{
-    core::entities::Entities, Archetype, BatchedIter, Bundle, ComponentFlags, DynamicBundle,
-    Entity, EntityFilter, EntityReserver, Fetch, Location, MissingComponent, Mut, NoSuchEntity,
-    QueryFilter, QueryIter, ReadOnlyFetch, Ref, RefMut, WorldQuery,
+    core::{
+        archetype::CHECK_TICK_THRESHOLD,
+        entities::Entities,
+        entity_map::{map_component_entities, EntityMapper},
+        removed_components::RemovedComponentLog,
+    },
+    resource::Resources,
+    visits_archetype, Archetype, BatchedIter, Bundle, Commands, ComponentCloners, ComponentHook,
+    ComponentHooks, ComponentTicks, Disabled, DynamicBundle, Entity, EntityFilter, EntityMap,
+    EntityReserver, Fetch, Location, MapEntities, MapEntitiesError, MissingComponent, Mut,
+    NoSuchEntity, QueryFilter, QueryIter, ReadOnlyFetch, Ref, RefMut, Relation, RelationIndex,
+    SparseSets, StorageType, TypeInfo, WorldQuery,
 };
 use bevy_utils::{HashMap, HashSet};
-use std::{any::TypeId, fmt, mem, ptr};
+use std::{
+    any::{type_name, TypeId},
+    fmt, mem,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use super::borrow::EntityRef;
 
@@ -30,15 +47,26 @@ use super::borrow::EntityRef;
 /// type, but far more efficient to traverse.
 ///
 /// The components of entities who have the same set of component types are stored in contiguous
-/// runs, allowing for extremely fast, cache-friendly iteration.
+/// runs, allowing for extremely fast, cache-friendly iteration. Component types registered with
+/// `StorageType::SparseSet` are instead stored per entity, outside of the archetypes.
 #[derive(Debug)]
 pub struct World {
     entities: Entities,
     index: HashMap<Vec<TypeId>, u32>,
-    removed_components: HashMap<TypeId, Vec<Entity>>,
+    removed_components: HashMap<TypeId, RemovedComponentLog>,
     #[allow(missing_docs)]
     pub archetypes: Vec<Archetype>,
+    sparse_sets: SparseSets,
+    hooks: HashMap<TypeId, ComponentHooks>,
+    hook_commands: Commands,
+    relations: HashMap<TypeId, RelationIndex>,
+    cloners: ComponentCloners,
+    entity_mappers: HashMap<TypeId, EntityMapper>,
     archetype_generation: u64,
+    storage_generation: u64,
+    change_tick: AtomicU32,
+    last_change_tick: u32,
+    last_check_tick: u32,
 }
 
 impl World {
@@ -53,8 +81,18 @@ impl World {
             entities: Entities::default(),
             index,
             archetypes,
+            sparse_sets: SparseSets::default(),
+            hooks: HashMap::default(),
+            hook_commands: Commands::default(),
+            relations: HashMap::default(),
+            cloners: ComponentCloners::default(),
+            entity_mappers: HashMap::default(),
             archetype_generation: 0,
+            storage_generation: 0,
             removed_components: HashMap::default(),
+            change_tick: AtomicU32::new(1),
+            last_change_tick: 0,
+            last_check_tick: 0,
         }
     }
 
@@ -82,21 +120,21 @@ impl World {
         self.flush();
 
         let entity = self.entities.alloc();
-        let archetype_id = bundle.with_ids(|ids| {
-            self.index.get(ids).copied().unwrap_or_else(|| {
-                let x = self.archetypes.len() as u32;
-                self.archetypes.push(Archetype::new(bundle.type_info()));
-                self.index.insert(ids.to_vec(), x);
-                self.archetype_generation += 1;
-                x
-            })
-        });
+        let archetype_id =
+            bundle.with_ids(|ids| self.get_or_insert_archetype(ids, || bundle.type_info()));
+        let hooked = self.hooked_types(|| bundle.with_ids(|ids| ids.to_vec()));
 
+        let change_tick = self.change_tick();
         let archetype = &mut self.archetypes[archetype_id as usize];
+        let sparse_sets = &mut self.sparse_sets;
         unsafe {
             let index = archetype.allocate(entity);
             bundle.put(|ptr, ty, size| {
-                archetype.put_dynamic(ptr, ty, size, index, ComponentFlags::ADDED);
+                if let Some(sparse_set) = sparse_sets.get_mut(ty) {
+                    sparse_set.insert(entity, ptr, change_tick);
+                } else {
+                    archetype.put_dynamic(ptr, ty, size, index, ComponentTicks::new(change_tick));
+                }
                 true
             });
             self.entities.meta[entity.id as usize].location = Location {
@@ -105,6 +143,9 @@ impl World {
             };
         }
 
+        if let Some(types) = hooked {
+            self.run_insert_hooks(entity, &types, &types);
+        }
         entity
     }
 
@@ -133,17 +174,22 @@ impl World {
         let iter = iter.into_iter();
         let (lower, upper) = iter.size_hint();
         let archetype_id = self.reserve_inner::<I::Item>(upper.unwrap_or(lower) as u32);
+        let hooked = self.hooked_types(|| I::Item::with_static_ids(|ids| ids.to_vec()));
 
         SpawnBatchIter {
             inner: iter,
-            entities: &mut self.entities,
             archetype_id,
-            archetype: &mut self.archetypes[archetype_id as usize],
+            change_tick: *self.change_tick.get_mut(),
+            hooked,
+            world: self,
         }
     }
 
     /// Destroy an entity and all its components
     pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        if let Some(types) = self.hooked_types(|| self.component_types(entity)) {
+            self.run_hooks(entity, &types, |hooks| hooks.on_remove.as_ref());
+        }
         self.flush();
 
         let loc = self.entities.free(entity)?;
@@ -152,11 +198,21 @@ impl World {
             self.entities.get_mut(moved).unwrap().index = loc.index;
         }
         for ty in archetype.types() {
-            let removed_entities = self
-                .removed_components
+            self.removed_components
                 .entry(ty.id())
-                .or_insert_with(Vec::new);
-            removed_entities.push(entity);
+                .or_default()
+                .push(entity);
+        }
+        for (ty, sparse_set) in self.sparse_sets.iter_mut() {
+            if sparse_set.remove(entity) {
+                self.removed_components.entry(ty).or_default().push(entity);
+            }
+        }
+        if !self.relations.is_empty() {
+            let relations = self.relations.values().cloned().collect::<Vec<_>>();
+            for relation in relations {
+                relation.remove_targeting(self, entity);
+            }
         }
         Ok(())
     }
@@ -175,34 +231,132 @@ impl World {
         self.flush();
         self.entities.reserve(additional);
 
-        let archetype_id = T::with_static_ids(|ids| {
-            self.index.get(ids).copied().unwrap_or_else(|| {
-                let x = self.archetypes.len() as u32;
-                self.archetypes.push(Archetype::new(T::static_type_info()));
-                self.index.insert(ids.to_vec(), x);
-                self.archetype_generation += 1;
-                x
-            })
-        });
+        let archetype_id =
+            T::with_static_ids(|ids| self.get_or_insert_archetype(ids, T::static_type_info));
 
         self.archetypes[archetype_id as usize].reserve(additional as usize);
         archetype_id
     }
 
+    /// Returns the archetype of entities with the components `ids`, creating it from `type_info`
+    /// if it doesn't exist yet. Components stored in sparse sets are left out of the archetype.
+    fn get_or_insert_archetype(
+        &mut self,
+        ids: &[TypeId],
+        type_info: impl FnOnce() -> Vec<TypeInfo>,
+    ) -> u32 {
+        if let Some(&archetype_id) = self.index.get(ids) {
+            return archetype_id;
+        }
+
+        let sparse_sets = &self.sparse_sets;
+        let table_ids = ids
+            .iter()
+            .copied()
+            .filter(|&ty| !sparse_sets.contains(ty))
+            .collect::<Vec<_>>();
+        if let Some(&archetype_id) = self.index.get(&table_ids) {
+            return archetype_id;
+        }
+
+        let info = type_info()
+            .into_iter()
+            .filter(|ty| !sparse_sets.contains(ty.id()))
+            .collect();
+        let archetype_id = self.archetypes.len() as u32;
+        self.archetypes.push(Archetype::new(info));
+        self.index.insert(table_ids, archetype_id);
+        self.archetype_generation += 1;
+        archetype_id
+    }
+
+    /// Returns the archetype an entity of archetype `source` moves to when the components `ids`
+    /// are inserted, caching the transition in `source`
+    fn insert_target(
+        &mut self,
+        source: u32,
+        ids: &[TypeId],
+        type_info: impl FnOnce() -> Vec<TypeInfo>,
+    ) -> u32 {
+        let arch = &self.archetypes[source as usize];
+        if let Some(target) = arch.edges().get_insert(ids) {
+            return target;
+        }
+        let mut info = arch.types().to_vec();
+        info.extend(
+            type_info()
+                .into_iter()
+                .filter(|ty| !arch.has_dynamic(ty.id())),
+        );
+        info.sort();
+        let elements = info.iter().map(|x| x.id()).collect::<Vec<_>>();
+        let target = self.get_or_insert_archetype(&elements, || info);
+        self.archetypes[source as usize]
+            .edges_mut()
+            .set_insert(ids, target);
+        target
+    }
+
+    /// Returns the archetype an entity of archetype `source` moves to when the components `ids`
+    /// are removed, caching the transition in `source`
+    fn remove_target(&mut self, source: u32, ids: &[TypeId]) -> u32 {
+        let arch = &self.archetypes[source as usize];
+        if let Some(target) = arch.edges().get_remove(ids) {
+            return target;
+        }
+        let info = arch
+            .types()
+            .iter()
+            .cloned()
+            .filter(|x| !ids.contains(&x.id()))
+            .collect::<Vec<_>>();
+        let elements = info.iter().map(|x| x.id()).collect::<Vec<_>>();
+        let target = self.get_or_insert_archetype(&elements, || info);
+        self.archetypes[source as usize]
+            .edges_mut()
+            .set_remove(ids, target);
+        target
+    }
+
     /// Despawn all entities
     ///
     /// Preserves allocated storage for reuse.
     pub fn clear(&mut self) {
+        if !self.hooks.is_empty() {
+            let mut removed = Vec::new();
+            for archetype in &self.archetypes {
+                for entity in archetype.iter_entities() {
+                    removed.push((
+                        *entity,
+                        archetype.types().iter().map(|ty| ty.id()).collect(),
+                    ));
+                }
+            }
+            for (ty, sparse_set) in self.sparse_sets.iter() {
+                for &entity in sparse_set.entities() {
+                    removed.push((entity, vec![ty]));
+                }
+            }
+            for (entity, types) in removed {
+                self.run_hooks(entity, &types, |hooks| hooks.on_remove.as_ref());
+            }
+        }
         for archetype in &mut self.archetypes {
             for ty in archetype.types() {
-                let removed_entities = self
-                    .removed_components
+                self.removed_components
                     .entry(ty.id())
-                    .or_insert_with(Vec::new);
-                removed_entities.extend(archetype.iter_entities().copied());
+                    .or_default()
+                    .extend(archetype.iter_entities().copied());
             }
             archetype.clear();
         }
+        for (ty, sparse_set) in self.sparse_sets.iter_mut() {
+            self.removed_components
+                .entry(ty)
+                .or_default()
+                .extend(sparse_set.entities().iter().copied());
+            sparse_set.clear();
+        }
         self.entities.clear();
     }
 
@@ -213,10 +367,92 @@ impl World {
 
     /// Returns true if the given entity has a component with the given type id.
     pub fn has_component_type(&self, entity: Entity, ty: TypeId) -> bool {
-        self.get_entity_location(entity)
-            .map(|location| &self.archetypes[location.archetype as usize])
-            .map(|archetype| archetype.has_type(ty))
-            .unwrap_or(false)
+        match self.get_entity_location(entity) {
+            Some(location) => match self.sparse_sets.get(ty) {
+                Some(sparse_set) => sparse_set.contains(entity),
+                None => self.archetypes[location.archetype as usize].has_type(ty),
+            },
+            None => false,
+        }
+    }
+
+    /// Chooses how components of type `T` are stored. Components are stored in archetype tables
+    /// unless registered otherwise.
+    ///
+    /// Adding or removing a component stored in a sparse set does not move its entity to another
+    /// archetype, which makes it a good fit for marker components that are inserted and removed
+    /// often. Iterating over such components is slower, as they are looked up per entity.
+    ///
+    /// # Panics
+    /// Panics if `T` is already stored with a different storage type, i.e. if it was added to an
+    /// entity before being registered, or if `T` is `Disabled`, which is always stored in tables.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::*;
+    /// struct Selected;
+    ///
+    /// let mut world = World::new();
+    /// world.register_component::<Selected>(StorageType::SparseSet);
+    /// let e = world.spawn((123,));
+    /// let archetypes = world.archetypes().len();
+    /// world.insert_one(e, Selected).unwrap();
+    /// assert!(world.get::<Selected>(e).is_ok());
+    /// assert_eq!(world.archetypes().len(), archetypes);
+    /// ```
+    pub fn register_component<T: Component>(&mut self, storage_type: StorageType) {
+        self.set_storage_type(TypeInfo::of::<T>(), storage_type);
+    }
+
+    fn set_storage_type(&mut self, info: TypeInfo, storage_type: StorageType) {
+        let ty = info.id();
+        if self.storage_type(ty) == storage_type {
+            return;
+        }
+        if ty == TypeId::of::<Disabled>() {
+            panic!(
+                "Disabled must be stored in archetype tables, as queries skip whole archetypes."
+            );
+        }
+        let in_use = match self.sparse_sets.get(ty) {
+            Some(sparse_set) => !sparse_set.is_empty(),
+            None => self
+                .archetypes
+                .iter()
+                .any(|archetype| archetype.has_type(ty)),
+        };
+        if in_use {
+            panic!(
+                "Cannot store {} in a {:?} storage, as it was already added to entities.",
+                info.type_name(),
+                storage_type
+            );
+        }
+        match storage_type {
+            StorageType::SparseSet => self.sparse_sets.insert(info),
+            StorageType::Table => self.sparse_sets.remove(ty),
+        }
+        // cached archetype transitions assume the previous storage of `T`
+        for archetype in &mut self.archetypes {
+            archetype.edges_mut().clear();
+        }
+        // queries compute their access per archetype, so they need to account for the new storage
+        self.archetype_generation += 1;
+        self.storage_generation += 1;
+    }
+
+    /// Returns how components with the given type id are stored
+    pub fn storage_type(&self, ty: TypeId) -> StorageType {
+        if self.sparse_sets.contains(ty) {
+            StorageType::SparseSet
+        } else {
+            StorageType::Table
+        }
+    }
+
+    /// Inspect the components stored in sparse sets
+    pub fn sparse_sets(&self) -> &SparseSets {
+        &self.sparse_sets
     }
 
     /// Efficiently iterate over all entities that have certain components
@@ -299,6 +535,9 @@ impl World {
 
     /// Like `query`, but instead of returning a single iterator it returns a "batched iterator",
     /// where each batch is `batch_size`. This is generally used for parallel iteration.
+    ///
+    /// # Panics
+    /// Panics if `batch_size` is zero.
     #[inline]
     pub fn query_batched<Q: WorldQuery>(&self, batch_size: usize) -> BatchedIter<'_, Q, ()>
     where
@@ -355,7 +594,27 @@ impl World {
     /// have unique access to the components they query.
     #[inline]
     pub unsafe fn query_unchecked<Q: WorldQuery, F: QueryFilter>(&self) -> QueryIter<'_, Q, F> {
-        QueryIter::new(&self.archetypes)
+        self.query_with_ticks_unchecked(self.last_change_tick, self.change_tick())
+    }
+
+    /// Like `query_unchecked`, but change detection filters report changes made after
+    /// `last_change_tick` and mutations are recorded at `change_tick`
+    ///
+    /// # Safety
+    /// This does not check for mutable query correctness. To be safe, make sure mutable queries
+    /// have unique access to the components they query.
+    #[inline]
+    pub(crate) unsafe fn query_with_ticks_unchecked<Q: WorldQuery, F: QueryFilter>(
+        &self,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> QueryIter<'_, Q, F> {
+        QueryIter::new(
+            &self.archetypes,
+            &self.sparse_sets,
+            last_change_tick,
+            change_tick,
+        )
     }
 
     /// Like `query`, but instead of returning a single iterator it returns a "batched iterator",
@@ -369,7 +628,33 @@ impl World {
         &self,
         batch_size: usize,
     ) -> BatchedIter<'_, Q, F> {
-        BatchedIter::new(&self.archetypes, batch_size)
+        self.query_batched_with_ticks_unchecked(
+            batch_size,
+            self.last_change_tick,
+            self.change_tick(),
+        )
+    }
+
+    /// Like `query_batched_unchecked`, but change detection filters report changes made after
+    /// `last_change_tick` and mutations are recorded at `change_tick`
+    ///
+    /// # Safety
+    /// This does not check for mutable query correctness. To be safe, make sure mutable queries
+    /// have unique access to the components they query.
+    #[inline]
+    pub(crate) unsafe fn query_batched_with_ticks_unchecked<Q: WorldQuery, F: QueryFilter>(
+        &self,
+        batch_size: usize,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> BatchedIter<'_, Q, F> {
+        BatchedIter::new(
+            &self.archetypes,
+            &self.sparse_sets,
+            batch_size,
+            last_change_tick,
+            change_tick,
+        )
     }
 
     /// Prepare a read only query against a single entity
@@ -452,34 +737,117 @@ impl World {
     pub unsafe fn query_one_unchecked<Q: WorldQuery, F: QueryFilter>(
         &self,
         entity: Entity,
+    ) -> Result<<Q::Fetch as Fetch>::Item, NoSuchEntity> {
+        self.query_one_with_ticks_unchecked::<Q, F>(
+            entity,
+            self.last_change_tick,
+            self.change_tick(),
+        )
+    }
+
+    /// Like `query_one_unchecked`, but change detection filters report changes made after
+    /// `last_change_tick` and mutations are recorded at `change_tick`
+    ///
+    /// # Safety
+    /// This does not check for mutable query correctness. To be safe, make sure mutable queries
+    /// have unique access to the components they query.
+    #[inline]
+    pub(crate) unsafe fn query_one_with_ticks_unchecked<Q: WorldQuery, F: QueryFilter>(
+        &self,
+        entity: Entity,
+        last_change_tick: u32,
+        change_tick: u32,
     ) -> Result<<Q::Fetch as Fetch>::Item, NoSuchEntity> {
         let loc = self.entities.get(entity)?;
         let archetype = &self.archetypes[loc.archetype as usize];
-        let matches_filter = F::get_entity_filter(archetype)
-            .map(|entity_filter| entity_filter.matches_entity(loc.index))
-            .unwrap_or(false);
+        if !visits_archetype::<Q, F>(archetype) {
+            return Err(NoSuchEntity);
+        }
+        let matches_filter = F::get_entity_filter(
+            archetype,
+            &self.sparse_sets,
+            0,
+            last_change_tick,
+            change_tick,
+        )
+        .map(|entity_filter| entity_filter.matches_entity(loc.index))
+        .unwrap_or(false);
         if matches_filter {
-            <Q::Fetch as Fetch>::get(archetype, 0)
-                .map(|fetch| fetch.fetch(loc.index))
-                .ok_or(NoSuchEntity)
+            match <Q::Fetch as Fetch>::get(
+                archetype,
+                &self.sparse_sets,
+                0,
+                last_change_tick,
+                change_tick,
+            ) {
+                Some(fetch) if fetch.matches_entity(loc.index) => Ok(fetch.fetch(loc.index)),
+                _ => Err(NoSuchEntity),
+            }
+        } else {
+            Err(NoSuchEntity)
+        }
+    }
+
+    /// Returns pointers to the `T` component of `entity` and its change ticks
+    ///
+    /// # Safety
+    /// `location` must be the location of `entity`
+    #[inline]
+    unsafe fn get_component_with_ticks<T: Component>(
+        &self,
+        entity: Entity,
+        location: Location,
+    ) -> Result<(NonNull<T>, NonNull<ComponentTicks>), MissingComponent> {
+        let archetype = &self.archetypes[location.archetype as usize];
+        if let Some((components, type_state)) = archetype.get_with_type_state::<T>() {
+            return Ok((
+                NonNull::new_unchecked(components.as_ptr().add(location.index)),
+                NonNull::new_unchecked(type_state.component_ticks().as_ptr().add(location.index)),
+            ));
+        }
+        self.sparse_sets
+            .get(TypeId::of::<T>())
+            .and_then(|sparse_set| sparse_set.get_with_ticks(entity))
+            .map(|(component, ticks)| (component.cast::<T>(), ticks))
+            .ok_or_else(MissingComponent::new::<T>)
+    }
+
+    /// Returns the entity at `location`, if `location` is in bounds of its archetype
+    fn entity_at(&self, location: Location) -> Result<Entity, NoSuchEntity> {
+        let archetype = &self.archetypes[location.archetype as usize];
+        if location.index < archetype.len() {
+            Ok(archetype.get_entity(location.index))
         } else {
             Err(NoSuchEntity)
         }
     }
 
+    /// Mutably borrow the `T` component of `entity`, recording mutations at `change_tick`
+    ///
+    /// # Safety
+    /// This does not check for mutable access correctness. To be safe, make sure this is the only
+    /// thing accessing this entity's T component.
+    #[inline]
+    pub(crate) unsafe fn get_mut_with_tick_unchecked<T: Component>(
+        &self,
+        entity: Entity,
+        change_tick: u32,
+    ) -> Result<Mut<'_, T>, ComponentError> {
+        let loc = self.entities.get(entity)?;
+        let (value, ticks) = self.get_component_with_ticks::<T>(entity, loc)?;
+        Ok(Mut {
+            value: &mut *value.as_ptr(),
+            ticks: &mut *ticks.as_ptr(),
+            change_tick,
+        })
+    }
+
     /// Borrow the `T` component of `entity`
     #[inline]
     pub fn get<T: Component>(&self, entity: Entity) -> Result<&'_ T, ComponentError> {
         unsafe {
             let loc = self.entities.get(entity)?;
-            if loc.archetype == 0 {
-                return Err(MissingComponent::new::<T>().into());
-            }
-            Ok(&*self.archetypes[loc.archetype as usize]
-                .get::<T>()
-                .ok_or_else(MissingComponent::new::<T>)?
-                .as_ptr()
-                .add(loc.index as usize))
+            Ok(&*self.get_component_with_ticks::<T>(entity, loc)?.0.as_ptr())
         }
     }
 
@@ -494,9 +862,15 @@ impl World {
     ///
     /// Does not immediately borrow any component.
     pub fn entity(&mut self, entity: Entity) -> Result<EntityRef<'_>, NoSuchEntity> {
-        Ok(match self.entities.get(entity)? {
-            Location { archetype: 0, .. } => EntityRef::empty(),
-            loc => unsafe { EntityRef::new(&self.archetypes[loc.archetype as usize], loc.index) },
+        let loc = self.entities.get(entity)?;
+        Ok(unsafe {
+            EntityRef::new(
+                entity,
+                &self.archetypes[loc.archetype as usize],
+                &self.sparse_sets,
+                loc.index,
+                self.change_tick(),
+            )
         })
     }
 
@@ -510,14 +884,7 @@ impl World {
         &self,
         entity: Entity,
     ) -> Result<Mut<'_, T>, ComponentError> {
-        let loc = self.entities.get(entity)?;
-        if loc.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
-        Ok(Mut::new(
-            &self.archetypes[loc.archetype as usize],
-            loc.index,
-        )?)
+        self.get_mut_with_tick_unchecked(entity, self.change_tick())
     }
 
     /// Iterate over all entities in the world
@@ -537,14 +904,38 @@ impl World {
     /// assert!(ids.contains(&b));
     /// ```
     pub fn iter(&mut self) -> Iter<'_> {
-        Iter::new(&self.archetypes, &self.entities)
+        Iter::new(
+            &self.archetypes,
+            &self.sparse_sets,
+            &self.entities,
+            self.change_tick(),
+        )
     }
 
-    #[allow(missing_docs)]
+    /// Returns the entities that had their `C` component removed, or were despawned while having
+    /// it, since the last call to `clear_trackers`
     pub fn removed<C: Component>(&self) -> &[Entity] {
         self.removed_components
             .get(&TypeId::of::<C>())
-            .map_or(&[], |entities| entities.as_slice())
+            .map_or(&[], |log| log.current_frame())
+    }
+
+    /// Registers a new reader of `C` component removals and returns its cursor. The reader starts
+    /// with the removals returned by `removed`, and removals are kept until every reader has read
+    /// them with `read_removed`. The reader is unregistered once the returned cursor is dropped.
+    pub fn removed_reader<C: Component>(&mut self) -> Arc<AtomicUsize> {
+        self.removed_components
+            .entry(TypeId::of::<C>())
+            .or_default()
+            .add_reader()
+    }
+
+    /// Returns the entities that had their `C` component removed since `reader` last read them,
+    /// and marks them as read. `reader` must have been returned by `removed_reader`.
+    pub fn read_removed<C: Component>(&self, reader: &AtomicUsize) -> &[Entity] {
+        self.removed_components
+            .get(&TypeId::of::<C>())
+            .map_or(&[], |log| log.read(reader))
     }
 
     /// Add `components` to `entity`
@@ -568,73 +959,82 @@ impl World {
         entity: Entity,
         bundle: impl DynamicBundle,
     ) -> Result<(), NoSuchEntity> {
-        use std::collections::hash_map::Entry;
-
         self.flush();
+        let source = self.entities.get(entity)?.archetype;
+        let hooked = self.hooked_types(|| bundle.with_ids(|ids| ids.to_vec()));
+        let added = hooked.as_ref().map(|types| {
+            types
+                .iter()
+                .copied()
+                .filter(|&ty| !self.has_component_type(entity, ty))
+                .collect::<Vec<_>>()
+        });
+        // Find the archetype it'll live in
+        let target = bundle.with_ids(|ids| self.insert_target(source, ids, || bundle.type_info()));
         let loc = self.entities.get_mut(entity)?;
+        let sparse_sets = &mut self.sparse_sets;
         unsafe {
-            // Assemble Vec<TypeInfo> for the final entity
+            // Drop the components that are replaced. Sparse set components are replaced when they
+            // are put.
             let arch = &mut self.archetypes[loc.archetype as usize];
-            let mut info = arch.types().to_vec();
-            for ty in bundle.type_info() {
-                if let Some(ptr) = arch.get_dynamic(ty.id(), ty.layout().size(), loc.index) {
-                    ty.drop(ptr.as_ptr());
-                } else {
-                    info.push(ty);
-                }
-            }
-            info.sort();
-
-            // Find the archetype it'll live in
-            let elements = info.iter().map(|x| x.id()).collect::<Vec<_>>();
-            let target = match self.index.entry(elements) {
-                Entry::Occupied(x) => *x.get(),
-                Entry::Vacant(x) => {
-                    let index = self.archetypes.len() as u32;
-                    self.archetypes.push(Archetype::new(info));
-                    x.insert(index);
-                    self.archetype_generation += 1;
-                    index
+            bundle.with_ids(|ids| {
+                for &ty in ids {
+                    arch.drop_dynamic(ty, loc.index);
                 }
-            };
+            });
 
+            let change_tick = *self.change_tick.get_mut();
             if target == loc.archetype {
                 // Update components in the current archetype
                 let arch = &mut self.archetypes[loc.archetype as usize];
                 bundle.put(|ptr, ty, size| {
-                    arch.put_dynamic(ptr, ty, size, loc.index, ComponentFlags::MUTATED);
+                    if let Some(sparse_set) = sparse_sets.get_mut(ty) {
+                        sparse_set.insert(entity, ptr, change_tick);
+                        return true;
+                    }
+                    let type_state = arch.get_type_state(ty).unwrap();
+                    let mut ticks = *type_state.component_ticks().as_ptr().add(loc.index);
+                    ticks.set_mutated(change_tick);
+                    arch.put_dynamic(ptr, ty, size, loc.index, ticks);
                     true
                 });
-                return Ok(());
-            }
+            } else {
+                // Move into a new archetype
+                let (source_arch, target_arch) = index2(
+                    &mut self.archetypes,
+                    loc.archetype as usize,
+                    target as usize,
+                );
+                let target_index = target_arch.allocate(entity);
+                loc.archetype = target;
+                let old_index = mem::replace(&mut loc.index, target_index);
+                if let Some(moved) = source_arch.move_to(old_index, |ptr, ty, size, ticks| {
+                    target_arch.put_dynamic(ptr, ty, size, target_index, ticks);
+                }) {
+                    self.entities.get_mut(moved).unwrap().index = old_index;
+                }
 
-            // Move into a new archetype
-            let (source_arch, target_arch) = index2(
-                &mut self.archetypes,
-                loc.archetype as usize,
-                target as usize,
-            );
-            let target_index = target_arch.allocate(entity);
-            loc.archetype = target;
-            let old_index = mem::replace(&mut loc.index, target_index);
-            if let Some(moved) = source_arch.move_to(old_index, |ptr, ty, size, flags| {
-                target_arch.put_dynamic(ptr, ty, size, target_index, ComponentFlags::empty());
-                let type_state = target_arch.get_type_state_mut(ty).unwrap();
-                *type_state.component_flags().as_ptr().add(target_index) = flags;
-            }) {
-                self.entities.get_mut(moved).unwrap().index = old_index;
+                bundle.put(|ptr, ty, size| {
+                    if let Some(sparse_set) = sparse_sets.get_mut(ty) {
+                        sparse_set.insert(entity, ptr, change_tick);
+                        return true;
+                    }
+                    let ticks = if source_arch.has_dynamic(ty) {
+                        let type_state = target_arch.get_type_state(ty).unwrap();
+                        let mut ticks = *type_state.component_ticks().as_ptr().add(target_index);
+                        ticks.set_mutated(change_tick);
+                        ticks
+                    } else {
+                        ComponentTicks::new(change_tick)
+                    };
+                    target_arch.put_dynamic(ptr, ty, size, target_index, ticks);
+                    true
+                });
             }
+        }
 
-            bundle.put(|ptr, ty, size| {
-                let had_component = source_arch.has_dynamic(ty);
-                let flags = if had_component {
-                    ComponentFlags::MUTATED
-                } else {
-                    ComponentFlags::ADDED
-                };
-                target_arch.put_dynamic(ptr, ty, size, target_index, flags);
-                true
-            });
+        if let (Some(added), Some(inserted)) = (added, hooked) {
+            self.run_insert_hooks(entity, &added, &inserted);
         }
         Ok(())
     }
@@ -670,46 +1070,51 @@ impl World {
     /// assert_eq!(*world.get::<bool>(e).unwrap(), true);
     /// ```
     pub fn remove<T: Bundle>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        if let Some(types) = self.hooked_types(|| T::with_static_ids(|ids| ids.to_vec())) {
+            // hooks only run if the whole bundle is removed
+            if types.iter().all(|&ty| self.has_component_type(entity, ty)) {
+                self.run_hooks(entity, &types, |hooks| hooks.on_remove.as_ref());
+            }
+        }
         self.flush();
         let loc = self.entities.get_mut(entity)?;
         unsafe {
-            let to_remove = T::with_static_ids(|ids| ids.iter().copied().collect::<HashSet<_>>());
-
             let old_index = loc.index;
             let source_arch = &self.archetypes[loc.archetype as usize];
-            let bundle = T::get(|ty, size| source_arch.get_dynamic(ty, size, old_index))?;
-            match self.remove_bundle_internal(entity, to_remove) {
+            let sparse_sets = &self.sparse_sets;
+            let bundle = T::get(|ty, size| match sparse_sets.get(ty) {
+                Some(sparse_set) => sparse_set.get(entity),
+                None => source_arch.get_dynamic(ty, size, old_index),
+            })?;
+            match T::with_static_ids(|ids| self.remove_bundle_internal(entity, ids)) {
                 Ok(_) => Ok(bundle),
                 Err(err) => Err(err),
             }
         }
     }
 
-    fn remove_bundle_internal<S: core::hash::BuildHasher>(
+    /// Removes the components `to_remove` from `entity` without dropping them. They must have been
+    /// moved out beforehand.
+    fn remove_bundle_internal(
         &mut self,
         entity: Entity,
-        to_remove: std::collections::HashSet<TypeId, S>,
+        to_remove: &[TypeId],
     ) -> Result<(), ComponentError> {
-        use std::collections::hash_map::Entry;
-
-        let loc = self.entities.get_mut(entity)?;
-        let info = self.archetypes[loc.archetype as usize]
-            .types()
-            .iter()
-            .cloned()
-            .filter(|x| !to_remove.contains(&x.id()))
-            .collect::<Vec<_>>();
-        let elements = info.iter().map(|x| x.id()).collect::<Vec<_>>();
-        let target = match self.index.entry(elements) {
-            Entry::Occupied(x) => *x.get(),
-            Entry::Vacant(x) => {
-                self.archetypes.push(Archetype::new(info));
-                let index = (self.archetypes.len() - 1) as u32;
-                x.insert(index);
-                self.archetype_generation += 1;
-                index
+        let source = self.entities.get(entity)?.archetype;
+        for &ty in to_remove {
+            if let Some(sparse_set) = self.sparse_sets.get_mut(ty) {
+                // SAFE: the caller moved the component out
+                if unsafe { sparse_set.forget(entity) } {
+                    self.removed_components.entry(ty).or_default().push(entity);
+                }
             }
-        };
+        }
+        let target = self.remove_target(source, to_remove);
+        let loc = self.entities.get_mut(entity)?;
+        if target == loc.archetype {
+            // only sparse set components were removed
+            return Ok(());
+        }
         let old_index = loc.index;
         let (source_arch, target_arch) = index2(
             &mut self.archetypes,
@@ -721,15 +1126,12 @@ impl World {
         loc.index = target_index;
         let removed_components = &mut self.removed_components;
         if let Some(moved) = unsafe {
-            source_arch.move_to(old_index, |src, ty, size, flags| {
+            source_arch.move_to(old_index, |src, ty, size, ticks| {
                 // Only move the components present in the target archetype, i.e. the non-removed ones.
-                if let Some(dst) = target_arch.get_dynamic(ty, size, target_index) {
-                    ptr::copy_nonoverlapping(src, dst.as_ptr(), size);
-                    let state = target_arch.get_type_state_mut(ty).unwrap();
-                    *state.component_flags().as_ptr().add(target_index) = flags;
+                if target_arch.has_dynamic(ty) {
+                    target_arch.put_dynamic(src, ty, size, target_index, ticks);
                 } else {
-                    let removed_entities = removed_components.entry(ty).or_insert_with(Vec::new);
-                    removed_entities.push(entity);
+                    removed_components.entry(ty).or_default().push(entity);
                 }
             })
         } {
@@ -745,18 +1147,29 @@ impl World {
     ///
     /// See `remove`.
     pub fn remove_one_by_one<T: Bundle>(&mut self, entity: Entity) -> Result<(), ComponentError> {
+        if let Some(mut types) = self.hooked_types(|| T::with_static_ids(|ids| ids.to_vec())) {
+            types.retain(|&ty| self.has_component_type(entity, ty));
+            self.run_hooks(entity, &types, |hooks| hooks.on_remove.as_ref());
+        }
         self.flush();
 
-        let to_remove = T::with_static_ids(|ids| ids.iter().copied().collect::<HashSet<_>>());
-        for component_to_remove in to_remove.into_iter() {
+        let to_remove = T::with_static_ids(|ids| ids.to_vec());
+        for component_to_remove in to_remove {
             let loc = self.entities.get(entity)?;
+            if let Some(sparse_set) = self.sparse_sets.get_mut(component_to_remove) {
+                if sparse_set.remove(entity) {
+                    self.removed_components
+                        .entry(component_to_remove)
+                        .or_default()
+                        .push(entity);
+                }
+                continue;
+            }
             if loc.archetype == 0 {
                 return Err(ComponentError::NoSuchEntity);
             }
             if self.archetypes[loc.archetype as usize].has_dynamic(component_to_remove) {
-                let mut single_component_hashset = std::collections::HashSet::new();
-                single_component_hashset.insert(component_to_remove);
-                match self.remove_bundle_internal(entity, single_component_hashset) {
+                match self.remove_bundle_internal(entity, &[component_to_remove]) {
                     Ok(_) | Err(ComponentError::MissingComponent(_)) => (),
                     Err(err) => return Err(err),
                 };
@@ -780,13 +1193,11 @@ impl World {
         &self,
         location: Location,
     ) -> Result<Ref<T>, ComponentError> {
-        if location.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
-        Ok(Ref::new(
-            &self.archetypes[location.archetype as usize],
-            location.index,
-        )?)
+        let archetype = &self.archetypes[location.archetype as usize];
+        Ok(match self.sparse_sets.get(TypeId::of::<T>()) {
+            Some(sparse_set) => Ref::from_sparse_set(sparse_set, self.entity_at(location)?)?,
+            None => Ref::new(archetype, location.index)?,
+        })
     }
 
     /// Borrow the `T` component at the given location, without safety checks
@@ -799,13 +1210,13 @@ impl World {
         &self,
         location: Location,
     ) -> Result<RefMut<T>, ComponentError> {
-        if location.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
-        Ok(RefMut::new(
-            &self.archetypes[location.archetype as usize],
-            location.index,
-        )?)
+        let archetype = &self.archetypes[location.archetype as usize];
+        Ok(match self.sparse_sets.get(TypeId::of::<T>()) {
+            Some(sparse_set) => {
+                RefMut::from_sparse_set(sparse_set, self.entity_at(location)?, self.change_tick())?
+            }
+            None => RefMut::new(archetype, location.index, self.change_tick())?,
+        })
     }
 
     /// Borrow the `T` component at the given location, without safety checks
@@ -815,14 +1226,11 @@ impl World {
         &self,
         location: Location,
     ) -> Result<&T, ComponentError> {
-        if location.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
-        Ok(&*self.archetypes[location.archetype as usize]
-            .get::<T>()
-            .ok_or_else(MissingComponent::new::<T>)?
-            .as_ptr()
-            .add(location.index as usize))
+        let entity = self.entity_at(location)?;
+        Ok(&*self
+            .get_component_with_ticks::<T>(entity, location)?
+            .0
+            .as_ptr())
     }
 
     /// Borrow the `T` component at the given location, without safety checks
@@ -834,13 +1242,7 @@ impl World {
         &self,
         location: Location,
     ) -> Result<Mut<T>, ComponentError> {
-        if location.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
-        Ok(Mut::new(
-            &self.archetypes[location.archetype as usize],
-            location.index,
-        )?)
+        self.get_mut_with_tick_unchecked(self.entity_at(location)?, self.change_tick())
     }
 
     /// Uniquely borrow the `T` component of `entity` without safety checks
@@ -856,14 +1258,7 @@ impl World {
         entity: Entity,
     ) -> Result<&mut T, ComponentError> {
         let loc = self.entities.get(entity)?;
-        if loc.archetype == 0 {
-            return Err(MissingComponent::new::<T>().into());
-        }
-        Ok(&mut *self.archetypes[loc.archetype as usize]
-            .get::<T>()
-            .ok_or_else(MissingComponent::new::<T>)?
-            .as_ptr()
-            .add(loc.index as usize))
+        Ok(&mut *self.get_component_with_ticks::<T>(entity, loc)?.0.as_ptr())
     }
 
     /// Convert all reserved entities into empty entities that can be iterated and accessed
@@ -921,24 +1316,472 @@ impl World {
         ArchetypesGeneration(self.archetype_generation)
     }
 
+    /// Returns a counter that changes whenever a component's storage type is registered, which
+    /// can change whether existing archetypes match a query
+    pub(crate) fn storage_generation(&self) -> u64 {
+        self.storage_generation
+    }
+
     /// Retrieves the entity's current location, if it exists
     pub fn get_entity_location(&self, entity: Entity) -> Option<Location> {
         self.entities.get(entity).ok()
     }
 
-    /// Clears each entity's tracker state. For example, each entity's component "mutated" state will be reset to `false`.
+    /// Returns the current change tick. Changes made directly through the `World`, outside of
+    /// systems, are recorded at this tick.
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick.load(Ordering::Acquire)
+    }
+
+    /// Advances the change tick and returns its previous value. Systems call this before they run
+    /// and record their changes at the returned tick.
+    pub fn increment_change_tick(&self) -> u32 {
+        self.change_tick.fetch_add(1, Ordering::AcqRel)
+    }
+
+    /// Returns the change tick that change detection filters compare against when querying the
+    /// `World` directly. It is advanced by `clear_trackers`.
+    pub fn last_change_tick(&self) -> u32 {
+        self.last_change_tick
+    }
+
+    /// Clears the world's tracker state. Queries made directly on the `World` will only report
+    /// components added or mutated after this call, and `removed` will only report components
+    /// removed after this call.
+    ///
+    /// This does not affect systems, which each track the changes made since they last ran.
     pub fn clear_trackers(&mut self) {
-        for archetype in self.archetypes.iter_mut() {
-            archetype.clear_trackers();
+        self.last_change_tick = self.increment_change_tick();
+        for log in self.removed_components.values_mut() {
+            log.update();
         }
 
-        self.removed_components.clear();
+        let change_tick = self.change_tick();
+        if change_tick.wrapping_sub(self.last_check_tick) >= CHECK_TICK_THRESHOLD {
+            for archetype in self.archetypes.iter_mut() {
+                archetype.check_change_ticks(change_tick);
+            }
+            for (_, sparse_set) in self.sparse_sets.iter_mut() {
+                sparse_set.check_change_ticks(change_tick);
+            }
+            self.last_check_tick = change_tick;
+        }
     }
 
     /// Gets an entity reserver, which can be used to reserve entity ids in a multi-threaded context.
     pub fn get_entity_reserver(&self) -> EntityReserver {
         self.entities.get_reserver()
     }
+
+    /// Returns the hooks of the component `T`, to register new ones
+    ///
+    /// Hooks run synchronously from `spawn`, `spawn_batch`, `insert`, `remove`, `despawn` and
+    /// `clear`, with shared access to the `World`. The `Commands` they queue are applied with
+    /// `apply_hook_commands`.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::*;
+    /// struct Health(u32);
+    /// struct Dead;
+    ///
+    /// let mut world = World::new();
+    /// let mut resources = Resources::default();
+    /// world
+    ///     .component_hooks_mut::<Health>()
+    ///     .on_remove(|_world, entity, commands| {
+    ///         commands.insert_one(entity, Dead);
+    ///     });
+    /// let e = world.spawn((Health(10),));
+    /// world.remove_one::<Health>(e).unwrap();
+    /// world.apply_hook_commands(&mut resources);
+    /// assert!(world.get::<Dead>(e).is_ok());
+    /// ```
+    pub fn component_hooks_mut<T: Component>(&mut self) -> &mut ComponentHooks {
+        self.hooks
+            .entry(TypeId::of::<T>())
+            .or_insert_with(ComponentHooks::of::<T>)
+    }
+
+    /// Registers the relation kind `K`, so that the world keeps a reverse index of its
+    /// `Relation<K>` components
+    ///
+    /// Relations pointing at an entity are removed when it is despawned. Registering a kind more
+    /// than once has no effect.
+    ///
+    /// # Panics
+    /// Panics if `Relation<K>` already has an `on_insert` or `on_remove` hook.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::*;
+    /// struct Owns;
+    ///
+    /// let mut world = World::new();
+    /// world.register_relation::<Owns>();
+    /// let player = world.spawn(());
+    /// let sword = world.spawn((Relation::<Owns>::new(player),));
+    /// assert_eq!(world.relation_sources::<Owns>(player), vec![sword]);
+    /// world.despawn(player).unwrap();
+    /// assert!(world.get::<Relation<Owns>>(sword).is_err());
+    /// ```
+    pub fn register_relation<K: 'static>(&mut self) {
+        if self.relations.contains_key(&TypeId::of::<K>()) {
+            return;
+        }
+        let relation = RelationIndex::new::<K>();
+        relation.add_hooks::<K>(self);
+        relation.add_existing::<K>(self);
+        self.register_map_entities::<Relation<K>>();
+        self.relations.insert(TypeId::of::<K>(), relation);
+    }
+
+    /// Returns the entities with a `Relation<K>` pointing at `target`, in the order the relations
+    /// were inserted
+    ///
+    /// # Panics
+    /// Panics if the relation kind `K` isn't registered with `register_relation`.
+    pub fn relation_sources<K: 'static>(&self, target: Entity) -> Vec<Entity> {
+        let relation = self.relations.get(&TypeId::of::<K>()).unwrap_or_else(|| {
+            panic!(
+                "relation kind {} is not registered, see World::register_relation",
+                type_name::<K>()
+            )
+        });
+        relation.edges.read().sources(target).to_vec()
+    }
+
+    /// Makes `clone_entity` copy components of type `T` with `Clone`
+    ///
+    /// Replaces any cloner previously registered for `T`.
+    pub fn register_clone<T: Component + Clone>(&mut self) {
+        self.register_component_cloner::<T>(|world, _resources, source, destination| {
+            let component = world.get::<T>(source).unwrap().clone();
+            world.insert_one(destination, component).unwrap();
+        });
+    }
+
+    /// Makes `clone_entity` copy components of type `T` with `cloner`, which is given the world,
+    /// the resources, the source entity and the destination entity
+    ///
+    /// Replaces any cloner previously registered for `T`.
+    pub fn register_component_cloner<T: Component>(
+        &mut self,
+        cloner: impl Fn(&mut World, &Resources, Entity, Entity) + Send + Sync + 'static,
+    ) {
+        self.cloners
+            .insert(TypeId::of::<T>(), Some(Arc::new(cloner)));
+    }
+
+    /// Makes `clone_entity` leave out components of type `T`, even if they could be cloned
+    ///
+    /// This is meant for components that must stay unique, like the list of children of an
+    /// entity.
+    pub fn ignore_on_clone<T: Component>(&mut self) {
+        self.cloners.insert(TypeId::of::<T>(), None);
+    }
+
+    /// Whether components of type `ty` have a cloner, or are ignored by `clone_entity`
+    pub fn has_clone_registration(&self, ty: TypeId) -> bool {
+        self.cloners.contains(ty)
+    }
+
+    /// Spawns a copy of `entity`, with a clone of each of its components that has a cloner
+    /// registered with `register_clone` or `register_component_cloner`
+    ///
+    /// Other components are left out. Returns the ID of the new entity.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::*;
+    /// #[derive(Clone)]
+    /// struct Health(u32);
+    /// struct Player;
+    ///
+    /// let mut world = World::new();
+    /// let resources = Resources::default();
+    /// world.register_clone::<Health>();
+    /// let e = world.spawn((Health(10), Player));
+    /// let clone = world.clone_entity(&resources, e).unwrap();
+    /// assert_eq!(world.get::<Health>(clone).unwrap().0, 10);
+    /// assert!(world.get::<Player>(clone).is_err());
+    /// ```
+    pub fn clone_entity(
+        &mut self,
+        resources: &Resources,
+        entity: Entity,
+    ) -> Result<Entity, NoSuchEntity> {
+        if !self.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        let clone = self.spawn(());
+        self.clone_components(resources, entity, clone)?;
+        Ok(clone)
+    }
+
+    /// Inserts into `destination` a clone of each component of `source` that has a cloner, like
+    /// `clone_entity` does
+    pub fn clone_components(
+        &mut self,
+        resources: &Resources,
+        source: Entity,
+        destination: Entity,
+    ) -> Result<(), NoSuchEntity> {
+        self.flush();
+        if !self.contains(source) || !self.contains(destination) {
+            return Err(NoSuchEntity);
+        }
+        let cloners = self
+            .component_types(source)
+            .iter()
+            .filter_map(|&ty| self.cloners.get(ty))
+            .collect::<Vec<_>>();
+        for cloner in cloners {
+            cloner(self, resources, source, destination);
+        }
+        Ok(())
+    }
+
+    /// Makes `move_entities_from` and `append` remap the entities stored in components of type `T`
+    pub fn register_map_entities<T: Component + MapEntities>(&mut self) {
+        self.entity_mappers
+            .insert(TypeId::of::<T>(), map_component_entities::<T>);
+    }
+
+    /// Returns the entity mapper registered for the component type `ty`, if any
+    pub(crate) fn entity_mapper(&self, ty: TypeId) -> Option<EntityMapper> {
+        self.entity_mappers.get(&ty).copied()
+    }
+
+    /// Moves every entity of `other` into this world, leaving `other` empty
+    ///
+    /// Components are moved a whole archetype column at a time, without cloning them. Each moved
+    /// entity gets a new ID, and the mapping from its ID in `other` is added to `entity_map`.
+    /// Components registered with `register_map_entities` are then remapped through `entity_map`,
+    /// which can be prefilled to translate references to entities that are not part of `other`.
+    /// Finally, the `on_add` and `on_insert` hooks of this world run for the moved components.
+    ///
+    /// Moved components are marked as added. Components stored in sparse sets in `other` are
+    /// stored in sparse sets in this world as well.
+    ///
+    /// The entities are moved even if remapping fails, in which case the first error is returned.
+    ///
+    /// # Panics
+    /// Panics if a component type stored in a sparse set in `other` was already added to entities
+    /// of this world, stored in tables.
+    pub fn move_entities_from(
+        &mut self,
+        other: &mut World,
+        entity_map: &mut EntityMap,
+    ) -> Result<(), MapEntitiesError> {
+        self.flush();
+        other.flush();
+        for (_, sparse_set) in other.sparse_sets.iter() {
+            self.set_storage_type(*sparse_set.type_info(), StorageType::SparseSet);
+        }
+
+        let change_tick = self.change_tick();
+        let (mut sources, mut moved) = (Vec::new(), Vec::new());
+        for archetype in &mut other.archetypes {
+            if archetype.is_empty() {
+                continue;
+            }
+            let entities = archetype
+                .iter_entities()
+                .map(|&entity| {
+                    let new_entity = self.entities.alloc();
+                    entity_map.insert(entity, new_entity);
+                    sources.push(entity);
+                    new_entity
+                })
+                .collect::<Vec<_>>();
+            let ids = archetype
+                .types()
+                .iter()
+                .map(|ty| ty.id())
+                .collect::<Vec<_>>();
+            let target = self.get_or_insert_archetype(&ids, || archetype.types().to_vec());
+            let sparse_sets = &mut self.sparse_sets;
+            let start = unsafe {
+                self.archetypes[target as usize].append(
+                    archetype,
+                    &entities,
+                    ComponentTicks::new(change_tick),
+                    |component, ty, row| {
+                        let sparse_set = sparse_sets.get_mut(ty).unwrap();
+                        sparse_set.insert(entities[row], component, change_tick);
+                    },
+                )
+            };
+            for (i, entity) in entities.iter().enumerate() {
+                self.entities.meta[entity.id as usize].location = Location {
+                    archetype: target,
+                    index: start + i,
+                };
+            }
+            moved.extend(entities);
+        }
+        for (ty, sparse_set) in other.sparse_sets.iter_mut() {
+            let target = self.sparse_sets.get_mut(ty).unwrap();
+            for &entity in sparse_set.entities() {
+                let component = sparse_set.get(entity).unwrap();
+                let new_entity = entity_map.get(entity).unwrap();
+                unsafe { target.insert(new_entity, component.as_ptr(), change_tick) };
+            }
+            unsafe { sparse_set.forget_all() };
+        }
+        for entity in sources {
+            other.entities.free(entity).unwrap();
+        }
+        for relation in other.relations.values() {
+            *relation.edges.write() = Default::default();
+        }
+
+        let mut result = Ok(());
+        let entity_mappers = self.entity_mappers.values().copied().collect::<Vec<_>>();
+        for map_entities in entity_mappers {
+            if let Err(e) = map_entities(self, &moved, entity_map) {
+                result = result.and(Err(e));
+            }
+        }
+        if !self.hooks.is_empty() {
+            for entity in moved {
+                let types = self.component_types(entity);
+                self.run_insert_hooks(entity, &types, &types);
+            }
+        }
+        result
+    }
+
+    /// Moves every entity of `other` into this world, leaving `other` empty, and returns the
+    /// mapping from their IDs in `other` to their new IDs
+    ///
+    /// See `move_entities_from`, which can also remap references to entities of this world.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::*;
+    /// let mut world = World::new();
+    /// let mut chunk = World::new();
+    /// let tile = chunk.spawn((1u32, "grass"));
+    /// let entity_map = world.append(&mut chunk).unwrap();
+    /// let moved = entity_map.get(tile).unwrap();
+    /// assert_eq!(*world.get::<u32>(moved).unwrap(), 1);
+    /// assert!(!chunk.contains(tile));
+    /// ```
+    pub fn append(&mut self, other: &mut World) -> Result<EntityMap, MapEntitiesError> {
+        let mut entity_map = EntityMap::default();
+        self.move_entities_from(other, &mut entity_map)?;
+        Ok(entity_map)
+    }
+
+    /// Returns the state of the entity allocator, as returned by `Entities::allocator_state`, and
+    /// the entities alive sorted by ID
+    pub(crate) fn entities_state(&mut self) -> (Vec<u32>, Vec<u32>, Vec<Entity>) {
+        self.flush();
+        let (generations, free) = self.entities.allocator_state();
+        let mut alive = self
+            .archetypes
+            .iter()
+            .flat_map(|archetype| archetype.iter_entities().copied())
+            .collect::<Vec<_>>();
+        alive.sort_unstable_by_key(|entity| entity.id());
+        (generations, free, alive)
+    }
+
+    /// Restores a state returned by `entities_state`. Entities not in `alive` are despawned, and
+    /// those in `alive` that don't exist are spawned without components.
+    pub(crate) fn set_entities_state(
+        &mut self,
+        generations: &[u32],
+        free: &[u32],
+        alive: &[Entity],
+    ) {
+        self.flush();
+        let restored = alive.iter().copied().collect::<HashSet<_>>();
+        let current = self
+            .archetypes
+            .iter()
+            .flat_map(|archetype| archetype.iter_entities().copied())
+            .collect::<HashSet<_>>();
+        for &entity in current.iter().filter(|entity| !restored.contains(entity)) {
+            self.despawn(entity).unwrap();
+        }
+        self.entities.set_allocator_state(generations, free);
+        for &entity in alive.iter().filter(|entity| !current.contains(entity)) {
+            let index = unsafe { self.archetypes[0].allocate(entity) };
+            self.entities.meta[entity.id as usize].location = Location {
+                archetype: 0,
+                index,
+            };
+        }
+    }
+
+    /// Applies the `Commands` queued by component hooks
+    ///
+    /// This happens automatically when a `Commands` buffer is applied, e.g. at the end of a
+    /// stage, and after exclusive systems run. Commands queued while applying hook commands are
+    /// applied as well.
+    pub fn apply_hook_commands(&mut self, resources: &mut Resources) {
+        if !self.hook_commands.is_empty() {
+            let mut commands = mem::take(&mut self.hook_commands);
+            commands.apply(self, resources);
+        }
+    }
+
+    /// Returns the types computed by `types` if any component has hooks
+    fn hooked_types(&self, types: impl FnOnce() -> Vec<TypeId>) -> Option<Vec<TypeId>> {
+        if self.hooks.is_empty() {
+            None
+        } else {
+            Some(types())
+        }
+    }
+
+    /// Returns the types of all components of `entity`
+    fn component_types(&self, entity: Entity) -> Vec<TypeId> {
+        let location = match self.get_entity_location(entity) {
+            Some(location) => location,
+            None => return Vec::new(),
+        };
+        let mut types = self.archetypes[location.archetype as usize]
+            .types()
+            .iter()
+            .map(|ty| ty.id())
+            .collect::<Vec<_>>();
+        for (ty, sparse_set) in self.sparse_sets.iter() {
+            if sparse_set.contains(entity) {
+                types.push(ty);
+            }
+        }
+        types
+    }
+
+    /// Runs the `on_add` hooks of the `added` components, then the `on_insert` hooks of the
+    /// `inserted` ones
+    fn run_insert_hooks(&mut self, entity: Entity, added: &[TypeId], inserted: &[TypeId]) {
+        self.run_hooks(entity, added, |hooks| hooks.on_add.as_ref());
+        self.run_hooks(entity, inserted, |hooks| hooks.on_insert.as_ref());
+    }
+
+    /// Runs the hook selected by `hook` for each of the component `types` of `entity`
+    fn run_hooks(
+        &mut self,
+        entity: Entity,
+        types: &[TypeId],
+        hook: fn(&ComponentHooks) -> Option<&ComponentHook>,
+    ) {
+        for ty in types {
+            let hook = match self.hooks.get(ty).and_then(hook) {
+                Some(hook) => hook.clone(),
+                None => continue,
+            };
+            let mut commands = mem::take(&mut self.hook_commands);
+            commands.set_entity_reserver(self.get_entity_reserver());
+            hook(self, entity, &mut commands);
+            self.hook_commands = commands;
+        }
+    }
 }
 
 unsafe impl Send for World {}
@@ -1011,18 +1854,27 @@ impl<T: Send + Sync + 'static> Component for T {}
 /// Iterator over all of a world's entities
 pub struct Iter<'a> {
     archetypes: core::slice::Iter<'a, Archetype>,
+    sparse_sets: &'a SparseSets,
     entities: &'a Entities,
     current: Option<&'a Archetype>,
     index: usize,
+    change_tick: u32,
 }
 
 impl<'a> Iter<'a> {
-    fn new(archetypes: &'a [Archetype], entities: &'a Entities) -> Self {
+    fn new(
+        archetypes: &'a [Archetype],
+        sparse_sets: &'a SparseSets,
+        entities: &'a Entities,
+        change_tick: u32,
+    ) -> Self {
         Self {
             archetypes: archetypes.iter(),
+            sparse_sets,
             entities,
             current: None,
             index: 0,
+            change_tick,
         }
     }
 }
@@ -1048,7 +1900,9 @@ impl<'a> Iterator for Iter<'a> {
                     let index = self.index;
                     self.index += 1;
                     let id = current.get_entity(index);
-                    return Some((id, unsafe { EntityRef::new(current, index) }));
+                    return Some((id, unsafe {
+                        EntityRef::new(id, current, self.sparse_sets, index, self.change_tick)
+                    }));
                 }
             }
         }
@@ -1089,9 +1943,10 @@ where
     I::Item: Bundle,
 {
     inner: I,
-    entities: &'a mut Entities,
+    world: &'a mut World,
     archetype_id: u32,
-    archetype: &'a mut Archetype,
+    change_tick: u32,
+    hooked: Option<Vec<TypeId>>,
 }
 
 impl<I> Drop for SpawnBatchIter<'_, I>
@@ -1113,19 +1968,33 @@ where
 
     fn next(&mut self) -> Option<Entity> {
         let components = self.inner.next()?;
-        let entity = self.entities.alloc();
+        let world = &mut *self.world;
+        if self.hooked.is_some() {
+            // hooks may have reserved entities
+            world.flush();
+        }
+        let entity = world.entities.alloc();
+        let archetype = &mut world.archetypes[self.archetype_id as usize];
+        let sparse_sets = &mut world.sparse_sets;
+        let change_tick = self.change_tick;
         unsafe {
-            let index = self.archetype.allocate(entity);
+            let index = archetype.allocate(entity);
             components.put(|ptr, ty, size| {
-                self.archetype
-                    .put_dynamic(ptr, ty, size, index, ComponentFlags::ADDED);
+                if let Some(sparse_set) = sparse_sets.get_mut(ty) {
+                    sparse_set.insert(entity, ptr, change_tick);
+                } else {
+                    archetype.put_dynamic(ptr, ty, size, index, ComponentTicks::new(change_tick));
+                }
                 true
             });
-            self.entities.meta[entity.id as usize].location = Location {
+            world.entities.meta[entity.id as usize].location = Location {
                 archetype: self.archetype_id,
                 index,
             };
         }
+        if let Some(types) = &self.hooked {
+            world.run_insert_hooks(entity, types, types);
+        }
         Some(entity)
     }
 
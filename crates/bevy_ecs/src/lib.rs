@@ -13,10 +13,12 @@ pub use system::{Query, *};
 pub mod prelude {
     pub use crate::{
         core::WorldBuilderSource,
-        resource::{ChangedRes, FromResources, Local, Res, ResMut, Resource, Resources},
-        schedule::{Schedule, State, StateStage, SystemStage},
-        system::{Commands, IntoSystem, Query, System},
-        Added, Bundle, Changed, Component, Entity, In, IntoChainSystem, Mut, Mutated, Or, QuerySet,
-        Ref, RefMut, With, Without, World,
+        resource::{
+            ChangedRes, FromResources, Local, NonSend, NonSendMut, Res, ResMut, Resource, Resources,
+        },
+        schedule::{Schedule, State, StateStage, SystemDescriptorCoercion, SystemSet, SystemStage},
+        system::{Commands, IntoSystem, Query, RemovedComponents, System},
+        Added, Bundle, Changed, Component, Disabled, Entity, In, IncludeDisabled, IntoChainSystem,
+        Mut, Mutated, Or, QuerySet, Ref, RefMut, Relation, StorageType, With, Without, World,
     };
 }
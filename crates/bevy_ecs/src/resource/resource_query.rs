@@ -39,6 +39,8 @@ impl<'a, T: Resource> Deref for ChangedRes<'a, T> {
 #[derive(Debug)]
 pub struct Res<'a, T: Resource> {
     value: &'a T,
+    added: bool,
+    mutated: bool,
 }
 
 impl<'a, T: Resource> Res<'a, T> {
@@ -46,11 +48,24 @@ impl<'a, T: Resource> Res<'a, T> {
     ///
     /// # Safety
     /// The pointer must have correct lifetime / storage
-    pub unsafe fn new(value: NonNull<T>) -> Self {
+    pub unsafe fn new(value: NonNull<T>, added: bool, mutated: bool) -> Self {
         Self {
             value: &*value.as_ptr(),
+            added,
+            mutated,
         }
     }
+
+    /// Returns true if the resource was added since the last time trackers were cleared
+    pub fn is_added(&self) -> bool {
+        self.added
+    }
+
+    /// Returns true if the resource was added or mutated since the last time trackers were
+    /// cleared
+    pub fn is_changed(&self) -> bool {
+        self.added || self.mutated
+    }
 }
 
 impl<'a, T: Resource> Deref for Res<'a, T> {
@@ -66,6 +81,7 @@ impl<'a, T: Resource> Deref for Res<'a, T> {
 pub struct ResMut<'a, T: Resource> {
     _marker: PhantomData<&'a T>,
     value: *mut T,
+    added: bool,
     mutated: *mut bool,
 }
 
@@ -74,13 +90,26 @@ impl<'a, T: Resource> ResMut<'a, T> {
     ///
     /// # Safety
     /// The pointer must have correct lifetime / storage / ownership
-    pub unsafe fn new(value: NonNull<T>, mutated: NonNull<bool>) -> Self {
+    pub unsafe fn new(value: NonNull<T>, added: bool, mutated: NonNull<bool>) -> Self {
         Self {
             value: value.as_ptr(),
+            added,
             mutated: mutated.as_ptr(),
             _marker: Default::default(),
         }
     }
+
+    /// Returns true if the resource was added since the last time trackers were cleared
+    pub fn is_added(&self) -> bool {
+        self.added
+    }
+
+    /// Returns true if the resource was added or mutated since the last time trackers were
+    /// cleared, including mutations made through this `ResMut`
+    pub fn is_changed(&self) -> bool {
+        // SAFE: the mutated flag is only written through this unique borrow
+        self.added || unsafe { *self.mutated }
+    }
 }
 
 impl<'a, T: Resource> Deref for ResMut<'a, T> {
@@ -100,6 +129,68 @@ impl<'a, T: Resource> DerefMut for ResMut<'a, T> {
     }
 }
 
+/// Shared borrow of a thread local resource, inserted with `Resources::insert_thread_local`
+///
+/// Systems with this parameter only run on the main thread.
+#[derive(Debug)]
+pub struct NonSend<'a, T: 'static> {
+    value: &'a T,
+}
+
+impl<'a, T: 'static> NonSend<'a, T> {
+    /// Creates a reference cell to a thread local resource from a pointer
+    ///
+    /// # Safety
+    /// The pointer must have correct lifetime / storage
+    pub unsafe fn new(value: NonNull<T>) -> Self {
+        Self {
+            value: &*value.as_ptr(),
+        }
+    }
+}
+
+impl<'a, T: 'static> Deref for NonSend<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+/// Unique borrow of a thread local resource, inserted with `Resources::insert_thread_local`
+///
+/// Systems with this parameter only run on the main thread.
+#[derive(Debug)]
+pub struct NonSendMut<'a, T: 'static> {
+    value: &'a mut T,
+}
+
+impl<'a, T: 'static> NonSendMut<'a, T> {
+    /// Creates a mutable reference cell to a thread local resource from a pointer
+    ///
+    /// # Safety
+    /// The pointer must have correct lifetime / storage / ownership
+    pub unsafe fn new(value: NonNull<T>) -> Self {
+        Self {
+            value: &mut *value.as_ptr(),
+        }
+    }
+}
+
+impl<'a, T: 'static> Deref for NonSendMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'a, T: 'static> DerefMut for NonSendMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
 /// Local<T> resources are unique per-system. Two instances of the same system will each have their own resource.
 /// Local resources are automatically initialized using the FromResources trait.
 #[derive(Debug)]
@@ -288,6 +288,18 @@ impl Resources {
         &self,
         resource_index: ResourceIndex,
     ) -> (NonNull<T>, NonNull<bool>, NonNull<bool>) {
+        self.try_get_unsafe_ref_with_added_and_mutated(resource_index)
+            .unwrap_or_else(|| panic!("Resource does not exist {}.", std::any::type_name::<T>()))
+    }
+
+    /// Like `get_unsafe_ref_with_added_and_mutated`, but returns `None` if the resource does not
+    /// exist
+    #[inline]
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn try_get_unsafe_ref_with_added_and_mutated<T: Resource>(
+        &self,
+        resource_index: ResourceIndex,
+    ) -> Option<(NonNull<T>, NonNull<bool>, NonNull<bool>)> {
         self.get_resource_data_index::<T>(resource_index)
             .map(|(data, index)| {
                 let resources = data
@@ -301,7 +313,30 @@ impl Resources {
                     NonNull::new_unchecked(resources.stored[index].mutated.get()),
                 )
             })
-            .unwrap_or_else(|| panic!("Resource does not exist {}.", std::any::type_name::<T>()))
+    }
+
+    /// # Safety
+    /// The caller must ensure the thread local resource is not aliased mutably while the
+    /// returned pointer is in use
+    ///
+    /// # Panics
+    /// Panics if called from a thread other than the main thread, or if the resource does not
+    /// exist
+    #[inline]
+    pub unsafe fn get_unsafe_thread_local_ref<T: 'static>(&self) -> NonNull<T> {
+        self.check_thread_local();
+        self.thread_local_data
+            .get(&TypeId::of::<T>())
+            .map(|storage| {
+                let resources = storage.downcast_ref::<VecResourceStorage<T>>().unwrap();
+                resources.get_unsafe_ref(0)
+            })
+            .unwrap_or_else(|| {
+                panic!(
+                    "Thread local resource does not exist {}.",
+                    std::any::type_name::<T>()
+                )
+            })
     }
 
     #[inline]
@@ -1,14 +1,23 @@
 mod stage;
 mod stage_executor;
 mod state;
+mod system_descriptor;
+mod system_set;
 
 pub use stage::*;
 pub use stage_executor::*;
 pub use state::*;
+pub use system_descriptor::*;
+pub use system_set::*;
 
 use crate::{IntoSystem, Resources, System, World};
 use bevy_utils::HashMap;
 
+/// An ordered collection of named stages, optionally gated by run criteria
+///
+/// A `Schedule` is itself a [Stage], so schedules can be nested inside other schedules. When its
+/// run criteria return [ShouldRun::YesAndLoop], all of its stages run again before the criteria
+/// are re-evaluated, which makes it possible to run a group of stages several times per update.
 #[derive(Default)]
 pub struct Schedule {
     stages: HashMap<String, Box<dyn Stage>>,
@@ -38,7 +47,7 @@ impl Schedule {
         self
     }
 
-    pub fn with_system_in_stage<S: System<In = (), Out = ()>>(
+    pub fn with_system_in_stage<S: Into<SystemDescriptor>>(
         mut self,
         stage_name: &'static str,
         system: S,
@@ -47,6 +56,15 @@ impl Schedule {
         self
     }
 
+    pub fn with_system_set_in_stage(
+        mut self,
+        stage_name: &'static str,
+        system_set: SystemSet,
+    ) -> Self {
+        self.add_system_set_to_stage(stage_name, system_set);
+        self
+    }
+
     pub fn set_run_criteria<S: System<In = (), Out = ShouldRun>>(
         &mut self,
         system: S,
@@ -98,7 +116,7 @@ impl Schedule {
         self
     }
 
-    pub fn add_system_to_stage<S: System<In = (), Out = ()>>(
+    pub fn add_system_to_stage<S: Into<SystemDescriptor>>(
         &mut self,
         stage_name: &'static str,
         system: S,
@@ -111,7 +129,24 @@ impl Schedule {
                     stage_name
                 )
             });
-        stage.add_system(system.system());
+        stage.add_system(system);
+        self
+    }
+
+    pub fn add_system_set_to_stage(
+        &mut self,
+        stage_name: &'static str,
+        system_set: SystemSet,
+    ) -> &mut Self {
+        let stage = self
+            .get_stage_mut::<SystemStage>(stage_name)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Stage '{}' does not exist or is not a SystemStage",
+                    stage_name
+                )
+            });
+        stage.add_system_set(system_set);
         self
     }
 
@@ -197,6 +232,108 @@ impl Stage for Schedule {
     }
 }
 
+/// Schedules stored by name in a resource, so they can be run from thread-local systems with
+/// [run_schedule]
+#[derive(Default)]
+pub struct Schedules {
+    schedules: HashMap<String, Schedule>,
+}
+
+impl Schedules {
+    /// Adds the schedule `name`, returning the schedule it replaced, if any
+    pub fn insert(&mut self, name: &str, schedule: Schedule) -> Option<Schedule> {
+        self.schedules.insert(name.to_string(), schedule)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Schedule> {
+        self.schedules.remove(name)
+    }
+
+    /// Returns the schedule `name`. Schedules are not available while they run.
+    pub fn get(&self, name: &str) -> Option<&Schedule> {
+        self.schedules.get(name)
+    }
+
+    /// Returns the schedule `name`. Schedules are not available while they run.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Schedule> {
+        self.schedules.get_mut(name)
+    }
+}
+
+/// Initializes and runs the schedule `name` of the [Schedules] resource, respecting its run
+/// criteria
+///
+/// This is meant to be called from thread-local systems, e.g. to run a physics schedule a
+/// variable number of times per update. The schedule is taken out of [Schedules] while it runs,
+/// so its systems can run other schedules, but not itself. It is put back afterwards, even if one
+/// of its systems panics.
+///
+/// # Panics
+/// Panics if there is no [Schedules] resource, or if it has no schedule called `name`.
+///
+/// # Example
+/// ```
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_ecs::{run_schedule, Schedules};
+/// fn physics_steps(world: &mut World, resources: &mut Resources) {
+///     let steps = *resources.get::<u32>().unwrap();
+///     for _ in 0..steps {
+///         run_schedule("physics", world, resources);
+///     }
+/// }
+///
+/// let mut world = World::default();
+/// let mut resources = Resources::default();
+/// let mut schedules = Schedules::default();
+/// schedules.insert("physics", Schedule::default());
+/// resources.insert(schedules);
+/// resources.insert(3u32);
+///
+/// let mut schedule = Schedule::default()
+///     .with_stage("update", SystemStage::single(physics_steps.system()));
+/// schedule.initialize_and_run(&mut world, &mut resources);
+/// ```
+pub fn run_schedule(name: &str, world: &mut World, resources: &mut Resources) {
+    let schedule = resources
+        .get_mut::<Schedules>()
+        .expect("Missing Schedules resource")
+        .remove(name)
+        .unwrap_or_else(|| panic!("schedule '{}' does not exist or is already running", name));
+    let mut running = RunningSchedule {
+        name,
+        schedule: Some(schedule),
+        resources,
+    };
+    running
+        .schedule
+        .as_mut()
+        .unwrap()
+        .initialize_and_run(world, running.resources);
+}
+
+/// Puts a schedule taken out by [run_schedule] back into [Schedules], even if one of its systems
+/// panics
+struct RunningSchedule<'a> {
+    name: &'a str,
+    schedule: Option<Schedule>,
+    resources: &'a mut Resources,
+}
+
+impl Drop for RunningSchedule<'_> {
+    fn drop(&mut self) {
+        if let (Some(schedule), Some(mut schedules)) =
+            (self.schedule.take(), self.resources.get_mut::<Schedules>())
+        {
+            let replaced = schedules.insert(self.name, schedule);
+            debug_assert!(
+                replaced.is_none() || std::thread::panicking(),
+                "schedule '{}' was replaced while it ran",
+                self.name
+            );
+        }
+    }
+}
+
 pub fn clear_trackers_system(world: &mut World, resources: &mut Resources) {
     world.clear_trackers();
     resources.clear_trackers();
@@ -206,7 +343,11 @@ pub fn clear_trackers_system(world: &mut World, resources: &mut Resources) {
 mod tests {
     use crate::{
         resource::{Res, ResMut, Resources},
-        schedule::{ParallelSystemStageExecutor, Schedule, SystemStage},
+        schedule::{
+            run_schedule, DeterministicExecution, ParallelSystemStageExecutor, Schedule, Schedules,
+            ShouldRun, Stage, StageError, SystemAmbiguity, SystemDescriptorCoercion, SystemSet,
+            SystemStage,
+        },
         system::Query,
         Commands, Entity, IntoSystem, World,
     };
@@ -518,4 +659,407 @@ mod tests {
             run_and_validate(&mut schedule, &mut world, &mut resources);
         }
     }
+
+    #[derive(Default)]
+    struct SystemOrder(Arc<Mutex<Vec<&'static str>>>);
+
+    fn input(order: Res<SystemOrder>) {
+        order.0.lock().push("input");
+    }
+
+    fn physics(order: Res<SystemOrder>) {
+        order.0.lock().push("physics");
+    }
+
+    fn render(order: Res<SystemOrder>) {
+        order.0.lock().push("render");
+    }
+
+    fn audio(order: Res<SystemOrder>) {
+        order.0.lock().push("audio");
+    }
+
+    #[test]
+    fn explicit_system_ordering() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(SystemOrder::default());
+
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(render.system().label("render"))
+            .add_system(
+                physics
+                    .system()
+                    .label("physics")
+                    .after("input")
+                    .before("render"),
+            )
+            .add_system(input.system().label("input"))
+            .add_system(audio.system().after("physics"));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", stage);
+
+        for _ in 0..100 {
+            schedule.initialize_and_run(&mut world, &mut resources);
+            let order = std::mem::take(&mut *resources.get::<SystemOrder>().unwrap().0.lock());
+            let position = |name| order.iter().position(|n| *n == name).unwrap();
+            assert_eq!(order.len(), 4);
+            assert!(position("input") < position("physics"));
+            assert!(position("physics") < position("render"));
+            assert!(position("physics") < position("audio"));
+        }
+
+        let stage = schedule.get_stage::<SystemStage>("update").unwrap();
+        let executor = stage.get_executor::<ParallelSystemStageExecutor>().unwrap();
+        // sorted order is input, physics, render, audio
+        assert_eq!(
+            executor.system_dependents(),
+            vec![vec![1], vec![2, 3], vec![], vec![]]
+        );
+    }
+
+    #[test]
+    fn deterministic_execution() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(SystemOrder::default());
+        resources.insert(DeterministicExecution);
+
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(audio.system().after("physics"))
+            .add_system(render.system().label("render"))
+            .add_system(physics.system().label("physics").before("render"))
+            .add_system(input.system().before("physics"));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", stage);
+
+        for _ in 0..10 {
+            schedule.initialize_and_run(&mut world, &mut resources);
+            let order = std::mem::take(&mut *resources.get::<SystemOrder>().unwrap().0.lock());
+            assert_eq!(order, vec!["input", "physics", "audio", "render"]);
+        }
+    }
+
+    #[test]
+    fn explicit_ordering_tie_break() {
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(render.system().after("physics"))
+            .add_system(input.system())
+            .add_system(physics.system().label("physics"));
+        stage.order_systems().unwrap();
+        let order = stage
+            .systems()
+            .iter()
+            .map(|system| system.name().rsplit("::").next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        // render and input are unrelated, but render waits for physics, which was inserted last
+        assert_eq!(order, vec!["input", "physics", "render"]);
+    }
+
+    #[test]
+    fn explicit_ordering_unknown_label() {
+        let mut stage = SystemStage::parallel();
+        stage.add_system(input.system().after("missing"));
+        match stage.order_systems() {
+            Err(StageError::UnknownLabel { label, .. }) => assert_eq!(label, "missing"),
+            _ => panic!("expected an unknown label error"),
+        }
+    }
+
+    #[test]
+    fn explicit_ordering_cycle() {
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(input.system().label("input").after("render"))
+            .add_system(physics.system().label("physics").after("input"))
+            .add_system(render.system().label("render").after("physics"))
+            .add_system(audio.system().after("render"));
+        match stage.order_systems() {
+            Err(StageError::DependencyCycle(cycle)) => assert_eq!(cycle.len(), 4),
+            _ => panic!("expected a dependency cycle error"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cyclic ordering dependency")]
+    fn explicit_ordering_cycle_panics_on_run() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(SystemOrder::default());
+
+        let mut stage = SystemStage::serial();
+        stage
+            .add_system(input.system().label("input").before("physics"))
+            .add_system(physics.system().label("physics").before("input"));
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", stage);
+        schedule.initialize_and_run(&mut world, &mut resources);
+    }
+
+    #[test]
+    fn system_set_run_criteria() {
+        fn run_if_enabled(enabled: Res<bool>) -> ShouldRun {
+            if *enabled {
+                ShouldRun::Yes
+            } else {
+                ShouldRun::No
+            }
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(SystemOrder::default());
+        resources.insert(false);
+
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(input.system().label("input"))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(run_if_enabled.system())
+                    .after("input")
+                    .before("render")
+                    .with_system(physics.system())
+                    .with_system(audio.system()),
+            )
+            .add_system(render.system().label("render"));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", stage);
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        let order = std::mem::take(&mut *resources.get::<SystemOrder>().unwrap().0.lock());
+        assert_eq!(order, vec!["input", "render"]);
+
+        *resources.get_mut::<bool>().unwrap() = true;
+        for _ in 0..100 {
+            schedule.initialize_and_run(&mut world, &mut resources);
+            let order = std::mem::take(&mut *resources.get::<SystemOrder>().unwrap().0.lock());
+            assert_eq!(order.len(), 4);
+            assert_eq!(order[0], "input");
+            assert_eq!(order[3], "render");
+        }
+    }
+
+    #[test]
+    fn system_set_looping_run_criteria() {
+        fn run_three_times(mut count: ResMut<u32>) -> ShouldRun {
+            if *count < 3 {
+                *count += 1;
+                ShouldRun::YesAndLoop
+            } else {
+                *count = 0;
+                ShouldRun::No
+            }
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(SystemOrder::default());
+        resources.insert(0u32);
+
+        let mut stage = SystemStage::serial();
+        stage
+            .add_system(input.system())
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(run_three_times.system())
+                    .with_system(physics.system()),
+            )
+            .add_system(render.system());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", stage);
+        schedule.initialize_and_run(&mut world, &mut resources);
+
+        let order = std::mem::take(&mut *resources.get::<SystemOrder>().unwrap().0.lock());
+        assert_eq!(
+            order,
+            vec!["input", "physics", "render", "physics", "physics"]
+        );
+    }
+
+    #[test]
+    fn ambiguity_detection() {
+        struct A;
+        struct B;
+
+        fn write_a(_query: Query<&mut A>) {}
+        fn read_a(_query: Query<&A>) {}
+        fn read_a_write_b(_query: Query<(&A, &mut B)>) {}
+        fn write_res(_res: ResMut<u32>) {}
+        fn read_res(_res: Res<u32>) {}
+
+        let short_names = |ambiguity: &SystemAmbiguity| {
+            let short_name = |name: &str| name.rsplit("::").next().unwrap().to_string();
+            (short_name(&ambiguity.first), short_name(&ambiguity.second))
+        };
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        world.spawn((A, B));
+
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(write_a.system().label("write_a"))
+            .add_system(read_a.system().after("write_a"))
+            .add_system(read_a_write_b.system())
+            .add_system(write_res.system())
+            .add_system(read_res.system());
+
+        stage.initialize(&mut world, &mut resources);
+        let ambiguities = stage.find_ambiguities(&world);
+        assert_eq!(ambiguities.len(), 2);
+        assert_eq!(
+            short_names(&ambiguities[0]),
+            ("write_a".to_string(), "read_a_write_b".to_string())
+        );
+        assert_eq!(ambiguities[0].conflicts.len(), 1);
+        assert!(ambiguities[0].conflicts[0].ends_with("::A"));
+        assert_eq!(
+            short_names(&ambiguities[1]),
+            ("write_res".to_string(), "read_res".to_string())
+        );
+        assert_eq!(ambiguities[1].conflicts, vec!["u32"]);
+
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(write_a.system().label("write_a"))
+            .add_system(read_a.system().after("write_a"))
+            .add_system(read_a_write_b.system().label("read_a_write_b"))
+            .add_system(write_res.system().label("write_res"))
+            .add_system(read_res.system().before("write_res"))
+            .add_system_set(
+                SystemSet::new()
+                    .label("set")
+                    .after("write_a")
+                    .with_system(read_a_write_b.system().before("read_a_write_b")),
+            );
+        // the first read_a_write_b is ordered after write_a through the set
+        stage.initialize(&mut world, &mut resources);
+        assert!(stage.find_ambiguities(&world).is_empty());
+    }
+
+    #[test]
+    fn nested_schedules() {
+        fn count(mut count: ResMut<u32>) {
+            *count += 1;
+        }
+        fn run_twice(mut remaining: ResMut<usize>) -> ShouldRun {
+            if *remaining == 0 {
+                ShouldRun::No
+            } else {
+                *remaining -= 1;
+                ShouldRun::YesAndLoop
+            }
+        }
+        fn run_physics(world: &mut World, resources: &mut Resources) {
+            for _ in 0..3 {
+                run_schedule("physics", world, resources);
+            }
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(0u32);
+        resources.insert(0usize);
+        let mut schedules = Schedules::default();
+        schedules.insert(
+            "physics",
+            Schedule::default().with_stage("step", SystemStage::single(count.system())),
+        );
+        resources.insert(schedules);
+
+        let mut schedule = Schedule::default()
+            .with_stage(
+                "looping",
+                Schedule::default()
+                    .with_run_criteria(run_twice.system())
+                    .with_stage("count", SystemStage::single(count.system())),
+            )
+            .with_stage("physics", SystemStage::single(run_physics.system()));
+
+        *resources.get_mut::<usize>().unwrap() = 2;
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<u32>().unwrap(), 5);
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<u32>().unwrap(), 8);
+        assert!(resources
+            .get::<Schedules>()
+            .unwrap()
+            .get("physics")
+            .is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "already running")]
+    fn schedule_cannot_run_itself() {
+        fn run_self(world: &mut World, resources: &mut Resources) {
+            run_schedule("recursive", world, resources);
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut schedules = Schedules::default();
+        schedules.insert(
+            "recursive",
+            Schedule::default().with_stage("run", SystemStage::single(run_self.system())),
+        );
+        resources.insert(schedules);
+        run_schedule("recursive", &mut world, &mut resources);
+    }
+
+    #[test]
+    fn schedule_survives_panicking_system() {
+        fn fail() {
+            panic!("system failed");
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut schedules = Schedules::default();
+        schedules.insert(
+            "failing",
+            Schedule::default().with_stage("run", SystemStage::single(fail.system())),
+        );
+        resources.insert(schedules);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            run_schedule("failing", &mut world, &mut resources)
+        }));
+        assert!(result.is_err());
+        assert!(resources
+            .get::<Schedules>()
+            .unwrap()
+            .get("failing")
+            .is_some());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "was replaced while it ran")]
+    fn schedule_replaced_while_running() {
+        fn replace(mut schedules: ResMut<Schedules>) {
+            schedules.insert("replaced", Schedule::default());
+        }
+
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut schedules = Schedules::default();
+        schedules.insert(
+            "replaced",
+            Schedule::default().with_stage("run", SystemStage::single(replace.system())),
+        );
+        resources.insert(schedules);
+        run_schedule("replaced", &mut world, &mut resources);
+    }
 }
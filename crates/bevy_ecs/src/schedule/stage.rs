@@ -1,15 +1,30 @@
-use std::{any::TypeId, borrow::Cow};
+use std::{any::TypeId, borrow::Cow, cmp::Reverse, collections::BinaryHeap};
 
 use crate::{
-    ArchetypeComponent, Resources, System, SystemId, ThreadLocalExecution, TypeAccess, World,
+    ArchetypeComponent, ArchetypesGeneration, Resources, System, SystemId, ThreadLocalExecution,
+    TypeAccess, World,
 };
-use bevy_utils::HashSet;
+use bevy_utils::{tracing::warn, HashMap, HashSet};
 use downcast_rs::{impl_downcast, Downcast};
+use fixedbitset::FixedBitSet;
+use thiserror::Error;
 
-use super::{ParallelSystemStageExecutor, SerialSystemStageExecutor, SystemStageExecutor};
+use super::{
+    ParallelSystemStageExecutor, SerialSystemStageExecutor, SystemDescriptor, SystemSet,
+    SystemStageExecutor,
+};
 
+#[derive(Debug, Error)]
 pub enum StageError {
+    #[error("System with id {0:?} already exists.")]
     SystemAlreadyExists(SystemId),
+    #[error("System {system} is ordered relative to the label \"{label}\", but no system in the stage has that label.")]
+    UnknownLabel {
+        system: Cow<'static, str>,
+        label: Cow<'static, str>,
+    },
+    #[error("Systems have a cyclic ordering dependency: {}.", .0.join(" -> "))]
+    DependencyCycle(Vec<Cow<'static, str>>),
 }
 
 pub trait Stage: Downcast + Send + Sync {
@@ -23,14 +38,107 @@ pub trait Stage: Downcast + Send + Sync {
 
 impl_downcast!(Stage);
 
+/// The labels, ordering constraints and [SystemSet] of a system in a [SystemStage]
+struct SystemInfo {
+    labels: Vec<Cow<'static, str>>,
+    before: Vec<Cow<'static, str>>,
+    after: Vec<Cow<'static, str>>,
+    set: usize,
+}
+
+/// The run criteria of a [SystemSet] that has been added to a [SystemStage]
+struct SystemSetRunCriteria {
+    run_criteria: Option<Box<dyn System<In = (), Out = ShouldRun>>>,
+    run_criteria_initialized: bool,
+    should_run: ShouldRun,
+}
+
+impl SystemSetRunCriteria {
+    fn new(run_criteria: Option<Box<dyn System<In = (), Out = ShouldRun>>>) -> Self {
+        SystemSetRunCriteria {
+            run_criteria,
+            run_criteria_initialized: false,
+            should_run: ShouldRun::No,
+        }
+    }
+
+    fn initialize(&mut self, world: &mut World, resources: &mut Resources) {
+        if let Some(ref mut run_criteria) = self.run_criteria {
+            if !self.run_criteria_initialized {
+                run_criteria.initialize(world, resources);
+                self.run_criteria_initialized = true;
+            }
+        }
+    }
+
+    fn evaluate(&mut self, world: &mut World, resources: &mut Resources) -> ShouldRun {
+        self.should_run = if let Some(ref mut run_criteria) = self.run_criteria {
+            evaluate_run_criteria(run_criteria.as_mut(), world, resources)
+        } else {
+            ShouldRun::Yes
+        };
+        self.should_run
+    }
+}
+
+/// Runs the given run criteria system, treating a missing result as [ShouldRun::No]
+fn evaluate_run_criteria(
+    run_criteria: &mut dyn System<In = (), Out = ShouldRun>,
+    world: &mut World,
+    resources: &mut Resources,
+) -> ShouldRun {
+    let should_run = run_criteria.run((), world, resources);
+    run_criteria.run_thread_local(world, resources);
+    // don't run when no result is returned or false is returned
+    should_run.unwrap_or(ShouldRun::No)
+}
+
+/// Two systems in a [SystemStage] that access the same component or resource, with at least one of
+/// them mutating it, but that have no declared execution order relative to each other
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SystemAmbiguity {
+    /// The system that currently runs first, based on insertion order
+    pub first: Cow<'static, str>,
+    pub second: Cow<'static, str>,
+    /// The names of the component and resource types both systems access
+    pub conflicts: Vec<&'static str>,
+}
+
+/// Inserting this resource makes every [SystemStage] log a warning for each pair of its systems
+/// that has an ambiguous execution order. See [SystemStage::find_ambiguities].
+#[derive(Debug, Default)]
+pub struct ReportExecutionOrderAmbiguities;
+
+/// Inserting this resource makes every [SystemStage] run its systems one at a time, with a
+/// [SerialSystemStageExecutor], instead of using the stage's own executor.
+///
+/// Systems then run in the stage's topological order, which respects every constraint the
+/// [ParallelSystemStageExecutor] schedules around: explicit orderings, and the registration order
+/// of systems with conflicting accesses. The order is the same on every run, which makes updates
+/// reproducible, for example to replay recorded inputs or to run a lockstep simulation.
+#[derive(Debug, Default)]
+pub struct DeterministicExecution;
+
 pub struct SystemStage {
     systems: Vec<Box<dyn System<In = (), Out = ()>>>,
+    system_info: Vec<SystemInfo>,
+    /// the run criteria of each system set in this stage. The first set holds the systems that
+    /// were added outside of a [SystemSet]
+    system_sets: Vec<SystemSetRunCriteria>,
+    /// for each system, the indices of the systems it has been explicitly ordered after
+    ordering_dependencies: Vec<Vec<usize>>,
     system_ids: HashSet<SystemId>,
     executor: Box<dyn SystemStageExecutor>,
     run_criteria: Option<Box<dyn System<In = (), Out = ShouldRun>>>,
     run_criteria_initialized: bool,
     uninitialized_systems: Vec<usize>,
     unexecuted_systems: Vec<usize>,
+    systems_modified: bool,
+    /// the ids of system pairs whose ambiguous execution order has already been reported
+    reported_ambiguities: HashSet<(SystemId, SystemId)>,
+    /// the archetypes generation ambiguities were last reported for. `None` if systems were added
+    /// since then
+    ambiguities_reported_generation: Option<ArchetypesGeneration>,
 }
 
 impl SystemStage {
@@ -40,13 +148,19 @@ impl SystemStage {
             run_criteria: None,
             run_criteria_initialized: false,
             systems: Default::default(),
+            system_info: Default::default(),
+            system_sets: vec![SystemSetRunCriteria::new(None)],
+            ordering_dependencies: Default::default(),
             system_ids: Default::default(),
             uninitialized_systems: Default::default(),
             unexecuted_systems: Default::default(),
+            systems_modified: false,
+            reported_ambiguities: Default::default(),
+            ambiguities_reported_generation: None,
         }
     }
 
-    pub fn single<S: System<In = (), Out = ()>>(system: S) -> Self {
+    pub fn single<S: Into<SystemDescriptor>>(system: S) -> Self {
         Self::serial().with_system(system)
     }
 
@@ -58,8 +172,13 @@ impl SystemStage {
         Self::new(Box::new(ParallelSystemStageExecutor::default()))
     }
 
-    pub fn with_system<S: System<In = (), Out = ()>>(mut self, system: S) -> Self {
-        self.add_system_boxed(Box::new(system));
+    pub fn with_system<S: Into<SystemDescriptor>>(mut self, system: S) -> Self {
+        self.add_system_descriptor(system.into());
+        self
+    }
+
+    pub fn with_system_set(mut self, system_set: SystemSet) -> Self {
+        self.add_system_set(system_set);
         self
     }
 
@@ -69,12 +188,38 @@ impl SystemStage {
         self
     }
 
-    pub fn add_system<S: System<In = (), Out = ()>>(&mut self, system: S) -> &mut Self {
-        self.add_system_boxed(Box::new(system));
+    pub fn add_system<S: Into<SystemDescriptor>>(&mut self, system: S) -> &mut Self {
+        self.add_system_descriptor(system.into());
         self
     }
 
     pub fn add_system_boxed(&mut self, system: Box<dyn System<In = (), Out = ()>>) -> &mut Self {
+        self.add_system_descriptor(SystemDescriptor::new(system))
+    }
+
+    pub fn add_system_descriptor(&mut self, descriptor: SystemDescriptor) -> &mut Self {
+        self.add_system_to_set(descriptor, 0)
+    }
+
+    /// Adds the systems of the given [SystemSet], which will only run when the set's run criteria
+    /// allows it
+    pub fn add_system_set(&mut self, mut system_set: SystemSet) -> &mut Self {
+        let set = self.system_sets.len();
+        self.system_sets
+            .push(SystemSetRunCriteria::new(system_set.run_criteria.take()));
+        for descriptor in system_set.into_systems() {
+            self.add_system_to_set(descriptor, set);
+        }
+        self
+    }
+
+    fn add_system_to_set(&mut self, descriptor: SystemDescriptor, set: usize) -> &mut Self {
+        let SystemDescriptor {
+            system,
+            labels,
+            before,
+            after,
+        } = descriptor;
         if self.system_ids.contains(&system.id()) {
             panic!(
                 "System with id {:?} ({}) already exists",
@@ -86,6 +231,14 @@ impl SystemStage {
         self.unexecuted_systems.push(self.systems.len());
         self.uninitialized_systems.push(self.systems.len());
         self.systems.push(system);
+        self.system_info.push(SystemInfo {
+            labels,
+            before,
+            after,
+            set,
+        });
+        self.systems_modified = true;
+        self.ambiguities_reported_generation = None;
         self
     }
 
@@ -97,10 +250,316 @@ impl SystemStage {
         self.executor.downcast_mut()
     }
 
+    /// Returns the systems in this stage in the order they will be executed
+    pub fn systems(&self) -> &[Box<dyn System<In = (), Out = ()>>] {
+        &self.systems
+    }
+
+    /// Sorts the systems in this stage so that every system comes after the systems it has been
+    /// ordered after. Ties are broken by insertion order: the next system is always the earliest
+    /// inserted one whose dependencies are already sorted. The order is the same every time, and
+    /// a stage without ordering constraints keeps its insertion order, but a system can still end
+    /// up after an unrelated system inserted later, when it waits on a dependency.
+    pub fn order_systems(&mut self) -> Result<(), StageError> {
+        if !self.systems_modified {
+            return Ok(());
+        }
+
+        let system_count = self.systems.len();
+        let mut systems_by_label = HashMap::<&str, Vec<usize>>::default();
+        for (system_index, system_info) in self.system_info.iter().enumerate() {
+            for label in system_info.labels.iter() {
+                systems_by_label
+                    .entry(label.as_ref())
+                    .or_default()
+                    .push(system_index);
+            }
+        }
+
+        let mut dependencies = vec![FixedBitSet::with_capacity(system_count); system_count];
+        for (system_index, system_info) in self.system_info.iter().enumerate() {
+            let labelled_systems = |label: &Cow<'static, str>| {
+                systems_by_label
+                    .get(label.as_ref())
+                    .ok_or_else(|| StageError::UnknownLabel {
+                        system: self.systems[system_index].name(),
+                        label: label.clone(),
+                    })
+            };
+            for label in system_info.after.iter() {
+                for &other_index in labelled_systems(label)? {
+                    if other_index != system_index {
+                        dependencies[system_index].insert(other_index);
+                    }
+                }
+            }
+            for label in system_info.before.iter() {
+                for &other_index in labelled_systems(label)? {
+                    if other_index != system_index {
+                        dependencies[other_index].insert(system_index);
+                    }
+                }
+            }
+        }
+
+        // stable topological sort: always pick the earliest inserted system that is ready to run
+        let mut dependents = vec![Vec::new(); system_count];
+        let mut remaining_dependencies = Vec::with_capacity(system_count);
+        let mut ready = BinaryHeap::new();
+        for (system_index, system_dependencies) in dependencies.iter().enumerate() {
+            for dependency in system_dependencies.ones() {
+                dependents[dependency].push(system_index);
+            }
+            let count = system_dependencies.count_ones(..);
+            if count == 0 {
+                ready.push(Reverse(system_index));
+            }
+            remaining_dependencies.push(count);
+        }
+        let mut order = Vec::with_capacity(system_count);
+        while let Some(Reverse(system_index)) = ready.pop() {
+            order.push(system_index);
+            for &dependent in dependents[system_index].iter() {
+                remaining_dependencies[dependent] -= 1;
+                if remaining_dependencies[dependent] == 0 {
+                    ready.push(Reverse(dependent));
+                }
+            }
+        }
+
+        if order.len() != system_count {
+            // every unsorted system has an unsorted dependency, so following them must loop
+            let mut path = Vec::new();
+            let mut current = (0..system_count)
+                .find(|index| remaining_dependencies[*index] > 0)
+                .unwrap();
+            while !path.contains(&current) {
+                path.push(current);
+                current = dependencies[current]
+                    .ones()
+                    .find(|index| remaining_dependencies[*index] > 0)
+                    .unwrap();
+            }
+            let cycle_start = path.iter().position(|index| *index == current).unwrap();
+            let mut cycle = path[cycle_start..]
+                .iter()
+                .rev()
+                .map(|index| self.systems[*index].name())
+                .collect::<Vec<_>>();
+            cycle.push(self.systems[current].name());
+            return Err(StageError::DependencyCycle(cycle));
+        }
+
+        let mut new_indices = vec![0; system_count];
+        for (new_index, old_index) in order.iter().enumerate() {
+            new_indices[*old_index] = new_index;
+        }
+
+        let mut systems = std::mem::take(&mut self.systems)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        let mut system_info = std::mem::take(&mut self.system_info)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.ordering_dependencies.clear();
+        for old_index in order.iter() {
+            self.systems.push(systems[*old_index].take().unwrap());
+            self.system_info
+                .push(system_info[*old_index].take().unwrap());
+            self.ordering_dependencies.push(
+                dependencies[*old_index]
+                    .ones()
+                    .map(|dependency| new_indices[dependency])
+                    .collect(),
+            );
+        }
+        for index in self
+            .uninitialized_systems
+            .iter_mut()
+            .chain(self.unexecuted_systems.iter_mut())
+        {
+            *index = new_indices[*index];
+        }
+
+        self.systems_modified = false;
+        Ok(())
+    }
+
+    /// Finds every pair of systems in this stage that conflict on a component or resource, but are
+    /// not ordered relative to each other using labels. Such systems currently run in insertion
+    /// order, which makes their execution order depend on the order plugins add them in. Component
+    /// conflicts are computed from the archetypes that currently exist in `world`, so the stage
+    /// should be initialized first.
+    pub fn find_ambiguities(&mut self, world: &World) -> Vec<SystemAmbiguity> {
+        self.find_ambiguous_system_pairs(world)
+            .into_iter()
+            .map(|(first, second, conflicts)| SystemAmbiguity {
+                first: self.systems[first].name(),
+                second: self.systems[second].name(),
+                conflicts,
+            })
+            .collect()
+    }
+
+    fn find_ambiguous_system_pairs(
+        &mut self,
+        world: &World,
+    ) -> Vec<(usize, usize, Vec<&'static str>)> {
+        if let Err(err) = self.order_systems() {
+            panic!("{}", err);
+        }
+
+        // systems are sorted, so every system a system is (transitively) ordered after comes before it
+        let system_count = self.systems.len();
+        let mut ordered_after = Vec::<FixedBitSet>::with_capacity(system_count);
+        for dependencies in self.ordering_dependencies.iter() {
+            let mut system_ordered_after = FixedBitSet::with_capacity(system_count);
+            for &dependency in dependencies.iter() {
+                system_ordered_after.insert(dependency);
+                system_ordered_after.union_with(&ordered_after[dependency]);
+            }
+            ordered_after.push(system_ordered_after);
+        }
+
+        for system in self.systems.iter_mut() {
+            system.update(world);
+        }
+
+        let mut ambiguities = Vec::new();
+        for (second, second_system) in self.systems.iter().enumerate() {
+            // thread local systems always run exclusively
+            if second_system.thread_local_execution() == ThreadLocalExecution::Immediate {
+                continue;
+            }
+            for (first, first_system) in self.systems[..second].iter().enumerate() {
+                if first_system.thread_local_execution() == ThreadLocalExecution::Immediate
+                    || ordered_after[second].contains(first)
+                {
+                    continue;
+                }
+
+                let mut conflicts = Vec::new();
+                let mut add_conflict = |type_id: TypeId| {
+                    let name = first_system
+                        .get_type_name(type_id)
+                        .or_else(|| second_system.get_type_name(type_id))
+                        .unwrap_or("Unknown");
+                    if !conflicts.contains(&name) {
+                        conflicts.push(name);
+                    }
+                };
+                let first_access = first_system.archetype_component_access();
+                let second_access = second_system.archetype_component_access();
+                for archetype_component in first_access
+                    .iter_writes()
+                    .filter(|access| second_access.is_read_or_write(access))
+                    .chain(
+                        second_access
+                            .iter_writes()
+                            .filter(|access| first_access.is_read_or_write(access)),
+                    )
+                {
+                    add_conflict(archetype_component.component);
+                }
+                let first_access = first_system.resource_access();
+                let second_access = second_system.resource_access();
+                for resource in first_access
+                    .iter_writes()
+                    .filter(|access| second_access.is_read_or_write(access))
+                    .chain(
+                        second_access
+                            .iter_writes()
+                            .filter(|access| first_access.is_read_or_write(access)),
+                    )
+                {
+                    add_conflict(*resource);
+                }
+
+                if !conflicts.is_empty() {
+                    conflicts.sort_unstable();
+                    ambiguities.push((first, second, conflicts));
+                }
+            }
+        }
+        ambiguities
+    }
+
+    fn report_ambiguities(&mut self, world: &World) {
+        for (first, second, conflicts) in self.find_ambiguous_system_pairs(world) {
+            let first = &self.systems[first];
+            let second = &self.systems[second];
+            if self.reported_ambiguities.insert((first.id(), second.id())) {
+                warn!(
+                    "Systems `{}` and `{}` have no declared execution order, but both access [{}] and at least one of them mutates it.",
+                    first.name(),
+                    second.name(),
+                    conflicts.join(", ")
+                );
+            }
+        }
+    }
+
+    /// Runs the systems in this stage, ignoring the stage's run criteria. Systems in a
+    /// [SystemSet] only run if the set's run criteria allows it, and are run again for as long as
+    /// the set's run criteria returns [ShouldRun::YesAndLoop].
     pub fn run_once(&mut self, world: &mut World, resources: &mut Resources) {
-        let unexecuted_systems = std::mem::take(&mut self.unexecuted_systems);
-        self.executor
-            .execute_stage(&mut self.systems, &unexecuted_systems, world, resources);
+        if let Err(err) = self.order_systems() {
+            panic!("{}", err);
+        }
+
+        if resources.contains::<ReportExecutionOrderAmbiguities>()
+            && self.ambiguities_reported_generation != Some(world.archetypes_generation())
+        {
+            self.report_ambiguities(world);
+            self.ambiguities_reported_generation = Some(world.archetypes_generation());
+        }
+
+        for system_set in self.system_sets.iter_mut() {
+            system_set.evaluate(world, resources);
+        }
+
+        let deterministic = resources.contains::<DeterministicExecution>();
+        loop {
+            let mut systems_to_run = FixedBitSet::with_capacity(self.systems.len());
+            for (system_index, system_info) in self.system_info.iter().enumerate() {
+                if self.system_sets[system_info.set].should_run != ShouldRun::No {
+                    systems_to_run.insert(system_index);
+                }
+            }
+
+            if systems_to_run.count_ones(..) > 0 {
+                let unexecuted_systems = std::mem::take(&mut self.unexecuted_systems);
+                let executor: &mut dyn SystemStageExecutor = if deterministic {
+                    &mut SerialSystemStageExecutor
+                } else {
+                    self.executor.as_mut()
+                };
+                executor.execute_stage(
+                    &mut self.systems,
+                    &unexecuted_systems,
+                    &self.ordering_dependencies,
+                    &systems_to_run,
+                    world,
+                    resources,
+                );
+            }
+
+            // only sets that asked to loop are checked again
+            let mut looping = false;
+            for system_set in self.system_sets.iter_mut() {
+                if system_set.should_run == ShouldRun::YesAndLoop {
+                    looping |= system_set.evaluate(world, resources) != ShouldRun::No;
+                } else {
+                    system_set.should_run = ShouldRun::No;
+                }
+            }
+
+            if !looping {
+                break;
+            }
+        }
     }
 }
 
@@ -113,6 +572,10 @@ impl Stage for SystemStage {
             }
         }
 
+        for system_set in self.system_sets.iter_mut() {
+            system_set.initialize(world, resources);
+        }
+
         let uninitialized_systems = std::mem::take(&mut self.uninitialized_systems);
         for system_index in uninitialized_systems.iter() {
             self.systems[*system_index].initialize(world, resources);
@@ -122,10 +585,7 @@ impl Stage for SystemStage {
     fn run(&mut self, world: &mut World, resources: &mut Resources) {
         loop {
             let should_run = if let Some(ref mut run_criteria) = self.run_criteria {
-                let should_run = run_criteria.run((), world, resources);
-                run_criteria.run_thread_local(world, resources);
-                // don't run when no result is returned or false is returned
-                should_run.unwrap_or(ShouldRun::No)
+                evaluate_run_criteria(run_criteria.as_mut(), world, resources)
             } else {
                 ShouldRun::Yes
             };
@@ -144,6 +604,7 @@ impl Stage for SystemStage {
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ShouldRun {
     /// No, the system should not run
     No,
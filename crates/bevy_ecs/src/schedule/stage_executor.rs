@@ -8,10 +8,15 @@ use fixedbitset::FixedBitSet;
 use crate::{ArchetypesGeneration, Resources, System, ThreadLocalExecution, TypeAccess, World};
 
 pub trait SystemStageExecutor: Downcast + Send + Sync {
+    /// Runs the systems whose indices are in `systems_to_run`. `systems` is topologically sorted,
+    /// and `ordering_dependencies` contains, for each system, the indices of the systems it has
+    /// been explicitly ordered after.
     fn execute_stage(
         &mut self,
         systems: &mut [Box<dyn System<In = (), Out = ()>>],
         changed_systems: &[usize],
+        ordering_dependencies: &[Vec<usize>],
+        systems_to_run: &FixedBitSet,
         world: &mut World,
         resources: &mut Resources,
     );
@@ -19,6 +24,7 @@ pub trait SystemStageExecutor: Downcast + Send + Sync {
 
 impl_downcast!(SystemStageExecutor);
 
+/// Executes the systems of a stage one at a time, in the stage's topological order
 #[derive(Default)]
 pub struct SerialSystemStageExecutor;
 
@@ -27,10 +33,17 @@ impl SystemStageExecutor for SerialSystemStageExecutor {
         &mut self,
         systems: &mut [Box<dyn System<In = (), Out = ()>>],
         _changed_systems: &[usize],
+        _ordering_dependencies: &[Vec<usize>],
+        systems_to_run: &FixedBitSet,
         world: &mut World,
         resources: &mut Resources,
     ) {
-        for system in systems.iter_mut() {
+        for system in systems
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| systems_to_run.contains(*index))
+            .map(|(_, system)| system)
+        {
             system.update(world);
             match system.thread_local_execution() {
                 ThreadLocalExecution::NextFlush => {
@@ -44,7 +57,12 @@ impl SystemStageExecutor for SerialSystemStageExecutor {
         }
 
         // "flush"
-        for system in systems.iter_mut() {
+        for system in systems
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| systems_to_run.contains(*index))
+            .map(|(_, system)| system)
+        {
             match system.thread_local_execution() {
                 ThreadLocalExecution::NextFlush => system.run_thread_local(world, resources),
                 ThreadLocalExecution::Immediate => { /* already ran immediate */ }
@@ -60,6 +78,7 @@ impl SystemStageExecutor for SerialSystemStageExecutor {
 /// * in a given stage, systems the read [archetype+component] X cannot run before systems registered before them that write [archetype+component] X
 /// * in a given stage, systems that mutate resource Y cannot run before systems registered before them that read/write resource Y
 /// * in a given stage, systems the read resource Y cannot run before systems registered before them that write resource Y
+/// * in a given stage, systems cannot run before the systems they have been explicitly ordered after
 pub struct ParallelSystemStageExecutor {
     /// each system's set of dependencies
     system_dependencies: Vec<FixedBitSet>,
@@ -110,6 +129,7 @@ impl ParallelSystemStageExecutor {
         &mut self,
         world: &World,
         systems: &mut [Box<dyn System<In = (), Out = ()>>],
+        ordering_dependencies: &[Vec<usize>],
         stage_changed: bool,
         next_thread_local_index: usize,
     ) -> Range<usize> {
@@ -191,6 +211,16 @@ impl ParallelSystemStageExecutor {
                             }
                         }
 
+                        // explicit orderings on systems before this batch are already satisfied
+                        for &dependency in ordering_dependencies[system_index].iter() {
+                            if dependency >= prepare_system_index_range.start
+                                && !self.system_dependencies[system_index].contains(dependency)
+                            {
+                                self.system_dependents[dependency].push(system_index);
+                                self.system_dependencies[system_index].insert(dependency);
+                            }
+                        }
+
                         current_archetype_access.union(archetype_access);
                         current_resource_access.union(resource_access);
 
@@ -286,12 +316,15 @@ impl ParallelSystemStageExecutor {
         }
     }
 
-    /// Runs the non-thread-local systems in the given prepared_system_range range
+    /// Runs the non-thread-local systems in the given prepared_system_range range. Systems that
+    /// are not in `systems_to_run` are skipped, but still wait for their dependencies before
+    /// signaling their dependents.
     pub fn run_systems(
         &self,
         world: &World,
         resources: &Resources,
         systems: &mut [Box<dyn System<In = (), Out = ()>>],
+        systems_to_run: &FixedBitSet,
         prepared_system_range: Range<usize>,
         compute_pool: &TaskPool,
     ) {
@@ -330,6 +363,7 @@ impl ParallelSystemStageExecutor {
                 let resources_ref = &*resources;
 
                 let trigger_events = &self.ready_events_of_dependents[system_index];
+                let should_run = systems_to_run.contains(system_index);
 
                 // Verify that any dependent task has a > 0 count. If a dependent task has > 0
                 // count, then the current system we are starting now isn't blocking it from running
@@ -349,8 +383,8 @@ impl ParallelSystemStageExecutor {
                     }
                 }
 
-                // Spawn the task
-                scope.spawn(async move {
+                let is_non_send = system.is_non_send();
+                let task = async move {
                     // Wait until our dependencies are done
                     if let Some(ready_event) = ready_event {
                         ready_event.listen().await;
@@ -358,7 +392,7 @@ impl ParallelSystemStageExecutor {
 
                     // Execute the system - in a scope to ensure the system lock is dropped before
                     // triggering dependents
-                    {
+                    if should_run {
                         #[cfg(feature = "trace")]
                         let system_span = bevy_utils::tracing::info_span!(
                             "system",
@@ -377,7 +411,14 @@ impl ParallelSystemStageExecutor {
                     for trigger_event in trigger_events {
                         trigger_event.decrement();
                     }
-                });
+                };
+
+                // Spawn the task. Systems accessing non-send data stay on the main thread.
+                if is_non_send {
+                    scope.spawn_local(task);
+                } else {
+                    scope.spawn(task);
+                }
                 system_index += 1;
             }
         });
@@ -389,6 +430,8 @@ impl SystemStageExecutor for ParallelSystemStageExecutor {
         &mut self,
         systems: &mut [Box<dyn System<In = (), Out = ()>>],
         changed_systems: &[usize],
+        ordering_dependencies: &[Vec<usize>],
+        systems_to_run: &FixedBitSet,
         world: &mut World,
         resources: &mut Resources,
     ) {
@@ -442,6 +485,7 @@ impl SystemStageExecutor for ParallelSystemStageExecutor {
             let prepared_system_range = self.prepare_to_next_thread_local(
                 world,
                 systems,
+                ordering_dependencies,
                 stage_changed,
                 next_thread_local_index,
             );
@@ -451,6 +495,7 @@ impl SystemStageExecutor for ParallelSystemStageExecutor {
                 world,
                 resources,
                 systems,
+                systems_to_run,
                 prepared_system_range,
                 &*compute_pool,
             );
@@ -465,7 +510,7 @@ impl SystemStageExecutor for ParallelSystemStageExecutor {
             // Run the thread local system at the end of the range of systems we just processed
             let thread_local_system_index =
                 self.thread_local_system_indices[next_thread_local_index];
-            {
+            if systems_to_run.contains(thread_local_system_index) {
                 // if a thread local system is ready to run, run it exclusively on the main thread
                 let system = systems[thread_local_system_index].as_mut();
 
@@ -489,6 +534,7 @@ impl SystemStageExecutor for ParallelSystemStageExecutor {
             let run_ready_system_index_range = self.prepare_to_next_thread_local(
                 world,
                 systems,
+                ordering_dependencies,
                 stage_changed,
                 next_thread_local_index,
             );
@@ -497,13 +543,19 @@ impl SystemStageExecutor for ParallelSystemStageExecutor {
                 world,
                 resources,
                 systems,
+                systems_to_run,
                 run_ready_system_index_range,
                 &*compute_pool,
             );
         }
 
         // "flush"
-        for system in systems.iter_mut() {
+        for system in systems
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| systems_to_run.contains(*index))
+            .map(|(_, system)| system)
+        {
             match system.thread_local_execution() {
                 ThreadLocalExecution::NextFlush => {
                     #[cfg(feature = "trace")]
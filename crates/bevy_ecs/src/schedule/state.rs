@@ -1,4 +1,7 @@
-use crate::{Resource, Resources, Stage, System, SystemStage, World};
+use crate::{
+    IntoSystem, Res, ResMut, Resource, Resources, ShouldRun, Stage, System, SystemDescriptor,
+    SystemStage, World,
+};
 use bevy_utils::HashMap;
 use std::{mem::Discriminant, ops::Deref};
 use thiserror::Error;
@@ -7,6 +10,9 @@ pub(crate) struct StateStages {
     update: Box<dyn Stage>,
     enter: Box<dyn Stage>,
     exit: Box<dyn Stage>,
+    pause: Box<dyn Stage>,
+    resume: Box<dyn Stage>,
+    update_while_paused: bool,
 }
 
 impl Default for StateStages {
@@ -15,6 +21,9 @@ impl Default for StateStages {
             enter: Box::new(SystemStage::parallel()),
             update: Box::new(SystemStage::parallel()),
             exit: Box::new(SystemStage::parallel()),
+            pause: Box::new(SystemStage::parallel()),
+            resume: Box::new(SystemStage::parallel()),
+            update_while_paused: false,
         }
     }
 }
@@ -51,36 +60,58 @@ impl<T> StateStage<T> {
         self
     }
 
-    pub fn on_state_enter<S: System<In = (), Out = ()>>(
-        &mut self,
-        state: T,
-        system: S,
-    ) -> &mut Self {
+    pub fn set_pause_stage<S: Stage>(&mut self, state: T, stage: S) -> &mut Self {
+        let stages = self.state_stages(state);
+        stages.pause = Box::new(stage);
+        self
+    }
+
+    pub fn set_resume_stage<S: Stage>(&mut self, state: T, stage: S) -> &mut Self {
+        let stages = self.state_stages(state);
+        stages.resume = Box::new(stage);
+        self
+    }
+
+    /// Sets whether the update stage of `state` keeps running while `state` is paused, i.e.
+    /// covered by a state pushed on top of it. It doesn't by default.
+    pub fn set_update_while_paused(&mut self, state: T, update_while_paused: bool) -> &mut Self {
+        let stages = self.state_stages(state);
+        stages.update_while_paused = update_while_paused;
+        self
+    }
+
+    pub fn on_state_enter<S: Into<SystemDescriptor>>(&mut self, state: T, system: S) -> &mut Self {
         self.enter_stage(state, |system_stage: &mut SystemStage| {
             system_stage.add_system(system)
         })
     }
 
-    pub fn on_state_exit<S: System<In = (), Out = ()>>(
-        &mut self,
-        state: T,
-        system: S,
-    ) -> &mut Self {
+    pub fn on_state_exit<S: Into<SystemDescriptor>>(&mut self, state: T, system: S) -> &mut Self {
         self.exit_stage(state, |system_stage: &mut SystemStage| {
             system_stage.add_system(system)
         })
     }
 
-    pub fn on_state_update<S: System<In = (), Out = ()>>(
-        &mut self,
-        state: T,
-        system: S,
-    ) -> &mut Self {
+    pub fn on_state_update<S: Into<SystemDescriptor>>(&mut self, state: T, system: S) -> &mut Self {
         self.update_stage(state, |system_stage: &mut SystemStage| {
             system_stage.add_system(system)
         })
     }
 
+    /// Adds a system that runs when another state is pushed on top of `state`
+    pub fn on_state_pause<S: Into<SystemDescriptor>>(&mut self, state: T, system: S) -> &mut Self {
+        self.pause_stage(state, |system_stage: &mut SystemStage| {
+            system_stage.add_system(system)
+        })
+    }
+
+    /// Adds a system that runs when the state pushed on top of `state` is popped
+    pub fn on_state_resume<S: Into<SystemDescriptor>>(&mut self, state: T, system: S) -> &mut Self {
+        self.resume_stage(state, |system_stage: &mut SystemStage| {
+            system_stage.add_system(system)
+        })
+    }
+
     pub fn enter_stage<S: Stage, F: FnOnce(&mut S) -> &mut S>(
         &mut self,
         state: T,
@@ -126,6 +157,48 @@ impl<T> StateStage<T> {
         self
     }
 
+    pub fn pause_stage<S: Stage, F: FnOnce(&mut S) -> &mut S>(
+        &mut self,
+        state: T,
+        func: F,
+    ) -> &mut Self {
+        let stages = self.state_stages(state);
+        func(
+            stages
+                .pause
+                .downcast_mut()
+                .expect("'Pause' stage does not match the given type"),
+        );
+        self
+    }
+
+    pub fn resume_stage<S: Stage, F: FnOnce(&mut S) -> &mut S>(
+        &mut self,
+        state: T,
+        func: F,
+    ) -> &mut Self {
+        let stages = self.state_stages(state);
+        func(
+            stages
+                .resume
+                .downcast_mut()
+                .expect("'Resume' stage does not match the given type"),
+        );
+        self
+    }
+
+    fn run_stage(
+        &mut self,
+        state: Discriminant<T>,
+        world: &mut World,
+        resources: &mut Resources,
+        stage: fn(&mut StateStages) -> &mut Box<dyn Stage>,
+    ) {
+        if let Some(state_stages) = self.stages.get_mut(&state) {
+            stage(state_stages).run(world, resources);
+        }
+    }
+
     fn state_stages(&mut self, state: T) -> &mut StateStages {
         self.stages
             .entry(std::mem::discriminant(&state))
@@ -140,59 +213,131 @@ impl<T: Resource + Clone> Stage for StateStage<T> {
             state_stages.enter.initialize(world, resources);
             state_stages.update.initialize(world, resources);
             state_stages.exit.initialize(world, resources);
+            state_stages.pause.initialize(world, resources);
+            state_stages.resume.initialize(world, resources);
         }
     }
 
     fn run(&mut self, world: &mut World, resources: &mut Resources) {
-        let current_stage = loop {
-            let (next_stage, current_stage) = {
+        let (paused_stages, current_stage) = loop {
+            let transition = {
                 let mut state = resources
                     .get_mut::<State<T>>()
                     .expect("Missing state resource");
-                let result = (
-                    state.next.as_ref().map(|next| std::mem::discriminant(next)),
-                    std::mem::discriminant(&state.current),
-                );
-
-                state.apply_next();
-
-                result
+                match state.apply_next() {
+                    Some(transition) => transition,
+                    None => break (state.paused_discriminants(), state.current_discriminant()),
+                }
             };
 
-            // if next_stage is Some, we just applied a new state
-            if let Some(next_stage) = next_stage {
-                if next_stage != current_stage {
-                    if let Some(current_state_stages) = self.stages.get_mut(&current_stage) {
-                        current_state_stages.exit.run(world, resources);
+            match transition {
+                Transition::Set { from, to } => {
+                    if from != to {
+                        self.run_stage(from, world, resources, |stages| &mut stages.exit);
                     }
+                    self.run_stage(to, world, resources, |stages| &mut stages.enter);
                 }
-
-                if let Some(next_state_stages) = self.stages.get_mut(&next_stage) {
-                    next_state_stages.enter.run(world, resources);
+                Transition::Push { from, to } => {
+                    self.run_stage(from, world, resources, |stages| &mut stages.pause);
+                    self.run_stage(to, world, resources, |stages| &mut stages.enter);
+                }
+                Transition::Pop { from, to } => {
+                    self.run_stage(from, world, resources, |stages| &mut stages.exit);
+                    self.run_stage(to, world, resources, |stages| &mut stages.resume);
                 }
-            } else {
-                break current_stage;
             }
         };
 
-        if let Some(current_state_stages) = self.stages.get_mut(&current_stage) {
-            current_state_stages.update.run(world, resources);
+        for paused_stage in paused_stages {
+            if let Some(paused_state_stages) = self.stages.get_mut(&paused_stage) {
+                if paused_state_stages.update_while_paused {
+                    paused_state_stages.update.run(world, resources);
+                }
+            }
         }
+
+        self.run_stage(current_stage, world, resources, |stages| &mut stages.update);
     }
 }
+
+/// The state change applied by [State::apply_next], identified by the affected states' variants
+#[derive(Debug)]
+enum Transition<T> {
+    Set {
+        from: Discriminant<T>,
+        to: Discriminant<T>,
+    },
+    Push {
+        from: Discriminant<T>,
+        to: Discriminant<T>,
+    },
+    Pop {
+        from: Discriminant<T>,
+        to: Discriminant<T>,
+    },
+}
+
+impl<T> Transition<T> {
+    fn entered(&self) -> Option<Discriminant<T>> {
+        match *self {
+            Transition::Set { to, .. } | Transition::Push { to, .. } => Some(to),
+            Transition::Pop { .. } => None,
+        }
+    }
+
+    fn exited(&self) -> Option<Discriminant<T>> {
+        match *self {
+            Transition::Set { from, to } if from != to => Some(from),
+            Transition::Pop { from, .. } => Some(from),
+            _ => None,
+        }
+    }
+
+    fn paused(&self) -> Option<Discriminant<T>> {
+        match *self {
+            Transition::Push { from, .. } => Some(from),
+            _ => None,
+        }
+    }
+
+    fn resumed(&self) -> Option<Discriminant<T>> {
+        match *self {
+            Transition::Pop { to, .. } => Some(to),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum StateError {
     #[error("Attempted to change the state to the current state.")]
     AlreadyInState,
+    #[error("Attempted to change the state to a state that is paused.")]
+    AlreadyPaused,
     #[error("Attempted to queue a state change, but there was already a state queued.")]
     StateAlreadyQueued,
+    #[error("Attempted to pop the state, but there was no state below it.")]
+    StackEmpty,
 }
 
+#[derive(Debug)]
+enum QueuedChange<T> {
+    Set(T),
+    Push(T),
+    Pop,
+}
+
+/// The current value of a state machine, stored as a resource and driven by a [StateStage]
+///
+/// Besides replacing the current state, states can be pushed on top of each other: the covered
+/// states are paused until the states above them are popped.
 #[derive(Debug)]
 pub struct State<T: Clone> {
     previous: Option<T>,
     current: T,
-    next: Option<T>,
+    next: Option<QueuedChange<T>>,
+    paused: Vec<T>,
+    transition: Option<Transition<T>>,
 }
 
 #[allow(clippy::mem_discriminant_non_enum)]
@@ -202,7 +347,9 @@ impl<T: Clone> State<T> {
             current: state.clone(),
             previous: None,
             // add value to queue so that we "enter" the state
-            next: Some(state),
+            next: Some(QueuedChange::Set(state)),
+            paused: Vec::new(),
+            transition: None,
         }
     }
 
@@ -214,44 +361,192 @@ impl<T: Clone> State<T> {
         self.previous.as_ref()
     }
 
+    /// Returns the state that will be current once the queued change is applied
     pub fn next(&self) -> Option<&T> {
-        self.next.as_ref()
+        match self.next.as_ref()? {
+            QueuedChange::Set(state) | QueuedChange::Push(state) => Some(state),
+            QueuedChange::Pop => self.paused.last(),
+        }
+    }
+
+    /// Returns the paused states covered by the current state, from the bottom of the stack up
+    pub fn paused(&self) -> &[T] {
+        &self.paused
     }
 
     /// Queue a state change. This will fail if there is already a state in the queue, or if the given `state` matches the current state
+    /// or a paused state
+    ///
+    /// Only the current state is replaced; paused states stay on the stack.
     pub fn set_next(&mut self, state: T) -> Result<(), StateError> {
-        if std::mem::discriminant(&self.current) == std::mem::discriminant(&state) {
-            return Err(StateError::AlreadyInState);
+        self.queue(QueuedChange::Set(state), false)
+    }
+
+    /// Same as [Self::set_next], but there is already a next state, it will be overwritten instead of failing
+    pub fn overwrite_next(&mut self, state: T) -> Result<(), StateError> {
+        self.queue(QueuedChange::Set(state), true)
+    }
+
+    /// Queue pushing `state` on top of the current state, which is paused until `state` is popped.
+    /// This will fail if there is already a state in the queue, or if the given `state` matches
+    /// the current state or a paused state, as a state can only be on the stack once
+    pub fn push(&mut self, state: T) -> Result<(), StateError> {
+        self.queue(QueuedChange::Push(state), false)
+    }
+
+    /// Same as [Self::push], but there is already a next state, it will be overwritten instead of failing
+    pub fn overwrite_push(&mut self, state: T) -> Result<(), StateError> {
+        self.queue(QueuedChange::Push(state), true)
+    }
+
+    /// Queue exiting the current state and resuming the paused state below it. This will fail if
+    /// there is already a state in the queue, or if no state is paused
+    pub fn pop(&mut self) -> Result<(), StateError> {
+        self.queue(QueuedChange::Pop, false)
+    }
+
+    /// Same as [Self::pop], but there is already a next state, it will be overwritten instead of failing
+    pub fn overwrite_pop(&mut self) -> Result<(), StateError> {
+        self.queue(QueuedChange::Pop, true)
+    }
+
+    fn queue(&mut self, change: QueuedChange<T>, overwrite: bool) -> Result<(), StateError> {
+        match &change {
+            QueuedChange::Set(state) | QueuedChange::Push(state) => {
+                if std::mem::discriminant(&self.current) == std::mem::discriminant(state) {
+                    return Err(StateError::AlreadyInState);
+                }
+                if self
+                    .paused_discriminants()
+                    .contains(&std::mem::discriminant(state))
+                {
+                    return Err(StateError::AlreadyPaused);
+                }
+            }
+            QueuedChange::Pop => {
+                if self.paused.is_empty() {
+                    return Err(StateError::StackEmpty);
+                }
+            }
         }
 
-        if self.next.is_some() {
+        if !overwrite && self.next.is_some() {
             return Err(StateError::StateAlreadyQueued);
         }
 
-        self.next = Some(state);
+        self.next = Some(change);
         Ok(())
     }
 
-    /// Same as [Self::queue], but there is already a next state, it will be overwritten instead of failing
-    pub fn overwrite_next(&mut self, state: T) -> Result<(), StateError> {
-        if std::mem::discriminant(&self.current) == std::mem::discriminant(&state) {
-            return Err(StateError::AlreadyInState);
-        }
+    fn apply_next(&mut self) -> Option<Transition<T>> {
+        let from = std::mem::discriminant(&self.current);
+        Some(match self.next.take()? {
+            QueuedChange::Set(next) => {
+                let previous = std::mem::replace(&mut self.current, next);
+                if std::mem::discriminant(&previous) != std::mem::discriminant(&self.current) {
+                    self.previous = Some(previous)
+                }
+                Transition::Set {
+                    from,
+                    to: std::mem::discriminant(&self.current),
+                }
+            }
+            QueuedChange::Push(next) => {
+                let previous = std::mem::replace(&mut self.current, next);
+                self.paused.push(previous.clone());
+                self.previous = Some(previous);
+                Transition::Push {
+                    from,
+                    to: std::mem::discriminant(&self.current),
+                }
+            }
+            QueuedChange::Pop => {
+                let resumed = self
+                    .paused
+                    .pop()
+                    .expect("pop is only queued when a state is paused");
+                self.previous = Some(std::mem::replace(&mut self.current, resumed));
+                Transition::Pop {
+                    from,
+                    to: std::mem::discriminant(&self.current),
+                }
+            }
+        })
+    }
 
-        self.next = Some(state);
-        Ok(())
+    fn current_discriminant(&self) -> Discriminant<T> {
+        std::mem::discriminant(&self.current)
+    }
+
+    fn paused_discriminants(&self) -> Vec<Discriminant<T>> {
+        self.paused.iter().map(std::mem::discriminant).collect()
+    }
+}
+
+/// Run criteria gating systems on a [State]. They can be used by any stage, as long as the
+/// state's transitions are applied by [apply_state_transition].
+#[allow(clippy::mem_discriminant_non_enum)]
+impl<T: Resource + Clone> State<T> {
+    /// Runs while the current state is `state`
+    pub fn in_state(state: T) -> impl System<In = (), Out = ShouldRun> {
+        let state = std::mem::discriminant(&state);
+        Self::run_if(move |current: &State<T>| current.current_discriminant() == state)
+    }
+
+    /// Runs once after `state` became the current state, whether it was set or pushed
+    pub fn on_enter(state: T) -> impl System<In = (), Out = ShouldRun> {
+        Self::on_transition(state, Transition::entered)
+    }
+
+    /// Runs once after `state` stopped being the current state, whether it was replaced or popped
+    pub fn on_exit(state: T) -> impl System<In = (), Out = ShouldRun> {
+        Self::on_transition(state, Transition::exited)
+    }
+
+    /// Runs once after another state was pushed on top of `state`
+    pub fn on_pause(state: T) -> impl System<In = (), Out = ShouldRun> {
+        Self::on_transition(state, Transition::paused)
+    }
+
+    /// Runs once after the state pushed on top of `state` was popped
+    pub fn on_resume(state: T) -> impl System<In = (), Out = ShouldRun> {
+        Self::on_transition(state, Transition::resumed)
     }
 
-    fn apply_next(&mut self) {
-        if let Some(next) = self.next.take() {
-            let previous = std::mem::replace(&mut self.current, next);
-            if std::mem::discriminant(&previous) != std::mem::discriminant(&self.current) {
-                self.previous = Some(previous)
+    fn on_transition(
+        state: T,
+        affected: fn(&Transition<T>) -> Option<Discriminant<T>>,
+    ) -> impl System<In = (), Out = ShouldRun> {
+        let state = std::mem::discriminant(&state);
+        Self::run_if(move |current: &State<T>| {
+            current.transition.as_ref().and_then(affected) == Some(state)
+        })
+    }
+
+    fn run_if(
+        condition: impl Fn(&State<T>) -> bool + Send + Sync + 'static,
+    ) -> impl System<In = (), Out = ShouldRun> {
+        (move |state: Res<State<T>>| {
+            if condition(&state) {
+                ShouldRun::Yes
+            } else {
+                ShouldRun::No
             }
-        }
+        })
+        .system()
     }
 }
 
+/// Applies the change queued in [State<T>], making it visible to the run criteria of [State] until
+/// this system runs again
+///
+/// Adding this system once per frame, before the stages gated by the state, makes every stage of
+/// a frame see the same state. Don't combine it with a [StateStage] for the same state, as the
+/// stage applies changes on its own.
+pub fn apply_state_transition<T: Resource + Clone>(mut state: ResMut<State<T>>) {
+    state.transition = state.apply_next();
+}
+
 impl<T: Clone> Deref for State<T> {
     type Target = T;
 
@@ -259,3 +554,196 @@ impl<T: Clone> Deref for State<T> {
         &self.current
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_state_transition, State, StateError, StateStage};
+    use crate::{IntoSystem, ResMut, Resources, Schedule, System, SystemSet, SystemStage, World};
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    enum AppState {
+        Loading,
+        Game,
+        Menu,
+    }
+
+    type Log = Vec<&'static str>;
+
+    fn log(message: &'static str) -> impl System<In = (), Out = ()> {
+        (move |mut log: ResMut<Log>| log.push(message)).system()
+    }
+
+    #[test]
+    fn state_stack() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(State::new(AppState::Loading));
+        resources.insert(Log::default());
+
+        let mut stage = StateStage::<AppState>::default();
+        stage
+            .on_state_enter(AppState::Loading, log("loading enter"))
+            .on_state_update(AppState::Loading, log("loading update"))
+            .on_state_pause(AppState::Loading, log("loading pause"))
+            .on_state_resume(AppState::Loading, log("loading resume"))
+            .on_state_exit(AppState::Loading, log("loading exit"))
+            .set_update_while_paused(AppState::Loading, true)
+            .on_state_enter(AppState::Game, log("game enter"))
+            .on_state_update(AppState::Game, log("game update"))
+            .on_state_pause(AppState::Game, log("game pause"))
+            .on_state_resume(AppState::Game, log("game resume"))
+            .on_state_enter(AppState::Menu, log("menu enter"))
+            .on_state_update(AppState::Menu, log("menu update"))
+            .on_state_exit(AppState::Menu, log("menu exit"));
+        let mut schedule = Schedule::default();
+        schedule.add_stage("state", stage);
+        let mut run = |resources: &mut Resources| {
+            schedule.initialize_and_run(&mut world, resources);
+            std::mem::take(&mut *resources.get_mut::<Log>().unwrap())
+        };
+
+        assert_eq!(run(&mut resources), vec!["loading enter", "loading update"]);
+
+        resources
+            .get_mut::<State<AppState>>()
+            .unwrap()
+            .push(AppState::Game)
+            .unwrap();
+        assert_eq!(
+            run(&mut resources),
+            vec![
+                "loading pause",
+                "game enter",
+                "loading update",
+                "game update"
+            ]
+        );
+
+        {
+            let mut state = resources.get_mut::<State<AppState>>().unwrap();
+            assert_eq!(state.paused(), &[AppState::Loading]);
+            assert!(matches!(
+                state.push(AppState::Game),
+                Err(StateError::AlreadyInState)
+            ));
+            state.push(AppState::Menu).unwrap();
+            assert!(matches!(state.pop(), Err(StateError::StateAlreadyQueued)));
+            assert_eq!(state.next(), Some(&AppState::Menu));
+        }
+        assert_eq!(
+            run(&mut resources),
+            vec!["game pause", "menu enter", "loading update", "menu update"]
+        );
+
+        {
+            let mut state = resources.get_mut::<State<AppState>>().unwrap();
+            state.pop().unwrap();
+            assert_eq!(state.next(), Some(&AppState::Game));
+        }
+        assert_eq!(
+            run(&mut resources),
+            vec!["menu exit", "game resume", "loading update", "game update"]
+        );
+
+        resources
+            .get_mut::<State<AppState>>()
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(
+            run(&mut resources),
+            vec!["loading resume", "loading update"]
+        );
+
+        let mut state = resources.get_mut::<State<AppState>>().unwrap();
+        assert_eq!(state.current(), &AppState::Loading);
+        assert_eq!(state.previous(), Some(&AppState::Game));
+        assert!(matches!(state.pop(), Err(StateError::StackEmpty)));
+    }
+
+    #[test]
+    fn paused_state_cannot_be_entered_again() {
+        let mut state = State::new(AppState::Loading);
+        state.apply_next();
+        state.push(AppState::Game).unwrap();
+        state.apply_next();
+        assert!(matches!(
+            state.push(AppState::Loading),
+            Err(StateError::AlreadyPaused)
+        ));
+        assert!(matches!(
+            state.set_next(AppState::Loading),
+            Err(StateError::AlreadyPaused)
+        ));
+        state.push(AppState::Menu).unwrap();
+        state.apply_next();
+        assert_eq!(state.paused(), &[AppState::Loading, AppState::Game]);
+    }
+
+    #[test]
+    fn state_run_criteria() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(State::new(AppState::Loading));
+        resources.insert(Log::default());
+
+        fn start_game(mut state: ResMut<State<AppState>>) {
+            state.set_next(AppState::Game).unwrap();
+        }
+
+        let mut schedule = Schedule::default()
+            .with_stage(
+                "transitions",
+                SystemStage::single(apply_state_transition::<AppState>.system()),
+            )
+            .with_stage(
+                "update",
+                SystemStage::parallel()
+                    .with_system_set(
+                        SystemSet::new()
+                            .with_run_criteria(State::on_enter(AppState::Loading))
+                            .with_system(log("loading enter")),
+                    )
+                    .with_system_set(
+                        SystemSet::new()
+                            .with_run_criteria(State::in_state(AppState::Loading))
+                            .with_system(start_game.system()),
+                    )
+                    .with_system_set(
+                        SystemSet::new()
+                            .with_run_criteria(State::on_exit(AppState::Loading))
+                            .with_system(log("loading exit")),
+                    )
+                    .with_system_set(
+                        SystemSet::new()
+                            .with_run_criteria(State::on_pause(AppState::Game))
+                            .with_system(log("game pause")),
+                    ),
+            )
+            .with_stage(
+                "post_update",
+                SystemStage::single(log("in game"))
+                    .with_run_criteria(State::in_state(AppState::Game)),
+            );
+        let mut run = |resources: &mut Resources| {
+            schedule.initialize_and_run(&mut world, resources);
+            std::mem::take(&mut *resources.get_mut::<Log>().unwrap())
+        };
+
+        assert_eq!(run(&mut resources), vec!["loading enter"]);
+        // the state change is queued, and only applied at the start of the next frame
+        assert_eq!(
+            resources.get::<State<AppState>>().unwrap().next(),
+            Some(&AppState::Game)
+        );
+        assert_eq!(run(&mut resources), vec!["loading exit", "in game"]);
+        assert_eq!(run(&mut resources), vec!["in game"]);
+
+        resources
+            .get_mut::<State<AppState>>()
+            .unwrap()
+            .push(AppState::Menu)
+            .unwrap();
+        assert_eq!(run(&mut resources), vec!["game pause"]);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{FallibleSystem, FuncSystem, System};
+use std::borrow::Cow;
+
+/// A system along with the labels and ordering constraints it should be added to a
+/// [SystemStage](crate::SystemStage) with.
+pub struct SystemDescriptor {
+    pub(crate) system: Box<dyn System<In = (), Out = ()>>,
+    pub(crate) labels: Vec<Cow<'static, str>>,
+    pub(crate) before: Vec<Cow<'static, str>>,
+    pub(crate) after: Vec<Cow<'static, str>>,
+}
+
+impl SystemDescriptor {
+    pub fn new(system: Box<dyn System<In = (), Out = ()>>) -> Self {
+        SystemDescriptor {
+            system,
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+}
+
+impl<S: System<In = (), Out = ()>> From<S> for SystemDescriptor {
+    fn from(system: S) -> Self {
+        SystemDescriptor::new(Box::new(system))
+    }
+}
+
+impl From<FuncSystem<anyhow::Result<()>>> for SystemDescriptor {
+    fn from(system: FuncSystem<anyhow::Result<()>>) -> Self {
+        SystemDescriptor::new(Box::new(FallibleSystem::new(system)))
+    }
+}
+
+/// Attaches labels and ordering constraints to systems
+pub trait SystemDescriptorCoercion {
+    /// Assigns a label to the system. Other systems in the same stage can be ordered relative to
+    /// every system that has this label.
+    fn label(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor;
+
+    /// The system will run before every system in the stage with the given label
+    fn before(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor;
+
+    /// The system will run after every system in the stage with the given label
+    fn after(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor;
+}
+
+impl SystemDescriptorCoercion for SystemDescriptor {
+    fn label(mut self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        self.labels.push(label.into());
+        self
+    }
+
+    fn before(mut self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        self.before.push(label.into());
+        self
+    }
+
+    fn after(mut self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        self.after.push(label.into());
+        self
+    }
+}
+
+impl<S: System<In = (), Out = ()>> SystemDescriptorCoercion for S {
+    fn label(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        SystemDescriptor::from(self).label(label)
+    }
+
+    fn before(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        SystemDescriptor::from(self).before(label)
+    }
+
+    fn after(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        SystemDescriptor::from(self).after(label)
+    }
+}
+
+impl SystemDescriptorCoercion for FuncSystem<anyhow::Result<()>> {
+    fn label(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        SystemDescriptor::from(self).label(label)
+    }
+
+    fn before(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        SystemDescriptor::from(self).before(label)
+    }
+
+    fn after(self, label: impl Into<Cow<'static, str>>) -> SystemDescriptor {
+        SystemDescriptor::from(self).after(label)
+    }
+}
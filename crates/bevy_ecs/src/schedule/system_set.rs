@@ -0,0 +1,75 @@
+use crate::{ShouldRun, System, SystemDescriptor, SystemDescriptorCoercion};
+use std::borrow::Cow;
+
+/// A group of systems that share run criteria and ordering constraints. Systems in a set only run
+/// when the set's run criteria allows it, independently of the other systems in the
+/// [SystemStage](crate::SystemStage) the set is added to.
+#[derive(Default)]
+pub struct SystemSet {
+    pub(crate) run_criteria: Option<Box<dyn System<In = (), Out = ShouldRun>>>,
+    pub(crate) systems: Vec<SystemDescriptor>,
+    labels: Vec<Cow<'static, str>>,
+    before: Vec<Cow<'static, str>>,
+    after: Vec<Cow<'static, str>>,
+}
+
+impl SystemSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_run_criteria<S: System<In = (), Out = ShouldRun>>(mut self, system: S) -> Self {
+        self.run_criteria = Some(Box::new(system));
+        self
+    }
+
+    pub fn with_system<S: Into<SystemDescriptor>>(mut self, system: S) -> Self {
+        self.systems.push(system.into());
+        self
+    }
+
+    /// Assigns a label to every system in the set
+    pub fn label(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+
+    /// Every system in the set will run before every system in the stage with the given label
+    pub fn before(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.before.push(label.into());
+        self
+    }
+
+    /// Every system in the set will run after every system in the stage with the given label
+    pub fn after(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.after.push(label.into());
+        self
+    }
+
+    /// Returns the systems of this set, with the set's labels and ordering constraints applied to
+    /// each system
+    pub(crate) fn into_systems(self) -> Vec<SystemDescriptor> {
+        let SystemSet {
+            systems,
+            labels,
+            before,
+            after,
+            ..
+        } = self;
+        systems
+            .into_iter()
+            .map(|mut descriptor| {
+                for label in labels.iter() {
+                    descriptor = descriptor.label(label.clone());
+                }
+                for label in before.iter() {
+                    descriptor = descriptor.before(label.clone());
+                }
+                for label in after.iter() {
+                    descriptor = descriptor.after(label.clone());
+                }
+                descriptor
+            })
+            .collect()
+    }
+}
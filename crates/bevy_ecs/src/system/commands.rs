@@ -4,7 +4,7 @@ use crate::{
     Bundle, Component, ComponentError, DynamicBundle, Entity, EntityReserver, World,
 };
 use bevy_utils::tracing::{debug, warn};
-use std::marker::PhantomData;
+use std::{fmt, marker::PhantomData};
 
 /// A [World] mutation
 pub trait Command: Send + Sync {
@@ -46,6 +46,20 @@ where
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct CloneEntity {
+    source: Entity,
+    destination: Entity,
+}
+
+impl Command for CloneEntity {
+    fn write(self: Box<Self>, world: &mut World, resources: &mut Resources) {
+        if let Err(e) = world.clone_components(resources, self.source, self.destination) {
+            debug!("Failed to clone entity {:?}: {}", self.source, e);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Despawn {
     entity: Entity,
@@ -189,6 +203,16 @@ pub struct Commands {
     entity_reserver: Option<EntityReserver>,
 }
 
+impl fmt::Debug for Commands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Commands")
+            .field("commands", &self.commands.len())
+            .field("current_entity", &self.current_entity)
+            .field("entity_reserver", &self.entity_reserver)
+            .finish()
+    }
+}
+
 impl Commands {
     /// Creates a new entity with the components contained in `bundle`.
     ///
@@ -243,6 +267,23 @@ impl Commands {
         self.add_command(SpawnBatch { bundles_iter })
     }
 
+    /// Spawns a copy of `entity`, and makes it the current entity so that more components can be
+    /// added with [`Self::with`].
+    ///
+    /// See [`World::clone_entity`].
+    pub fn clone_entity(&mut self, entity: Entity) -> &mut Self {
+        let clone = self
+            .entity_reserver
+            .as_ref()
+            .expect("Entity reserver has not been set.")
+            .reserve_entity();
+        self.set_current_entity(clone);
+        self.add_command(CloneEntity {
+            source: entity,
+            destination: clone,
+        })
+    }
+
     /// Despawns only the specified entity, not including its children.
     pub fn despawn(&mut self, entity: Entity) -> &mut Self {
         self.add_command(Despawn { entity })
@@ -375,11 +416,18 @@ impl Commands {
         self
     }
 
-    /// Runs all the stored commands on `world` and `resources`. The command buffer is emptied as a part of this call.
+    /// Runs all the stored commands on `world` and `resources`, followed by the commands queued
+    /// by the component hooks they triggered. The command buffer is emptied as a part of this call.
     pub fn apply(&mut self, world: &mut World, resources: &mut Resources) {
         for command in self.commands.drain(..) {
             command.write(world, resources);
         }
+        world.apply_hook_commands(resources);
+    }
+
+    /// Returns true if no commands are stored
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
     }
 
     /// Returns the current entity, set by [`Self::spawn`] or with [`Self::set_current_entity`].
@@ -410,7 +458,7 @@ impl Commands {
 
 #[cfg(test)]
 mod tests {
-    use crate::{resource::Resources, Commands, World};
+    use crate::{resource::Resources, Commands, StorageType, World};
 
     #[test]
     fn command_buffer() {
@@ -466,4 +514,37 @@ mod tests {
         let results_after_u64 = world.query::<&u64>().map(|a| *a).collect::<Vec<_>>();
         assert_eq!(results_after_u64, vec![]);
     }
+
+    #[test]
+    fn sparse_set_components() {
+        let mut world = World::default();
+        world.register_component::<bool>(StorageType::SparseSet);
+        let mut resources = Resources::default();
+        let mut command_buffer = Commands::default();
+        command_buffer.set_entity_reserver(world.get_entity_reserver());
+        command_buffer.spawn((1u32,));
+        let entity = command_buffer.current_entity().unwrap();
+        command_buffer.apply(&mut world, &mut resources);
+        let location = world.get_entity_location(entity).unwrap();
+
+        command_buffer.insert_one(entity, true);
+        command_buffer.apply(&mut world, &mut resources);
+        let results = world
+            .query::<(&u32, &bool)>()
+            .map(|(a, b)| (*a, *b))
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec![(1u32, true)]);
+        assert_eq!(
+            world.get_entity_location(entity).unwrap().archetype,
+            location.archetype
+        );
+
+        command_buffer.remove_one::<bool>(entity);
+        command_buffer.apply(&mut world, &mut resources);
+        assert!(world.get::<bool>(entity).is_err());
+        assert_eq!(
+            world.get_entity_location(entity).unwrap().archetype,
+            location.archetype
+        );
+    }
 }
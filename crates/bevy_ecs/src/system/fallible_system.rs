@@ -0,0 +1,198 @@
+use crate::{
+    ArchetypeComponent, Resources, System, SystemId, ThreadLocalExecution, TypeAccess, World,
+};
+use bevy_utils::tracing::error;
+use std::{any::TypeId, borrow::Cow, fmt};
+
+/// An error returned by a fallible system, along with the name of that system
+#[derive(Debug)]
+pub struct SystemError {
+    pub system_name: Cow<'static, str>,
+    pub error: anyhow::Error,
+}
+
+impl fmt::Display for SystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "system `{}` failed: {}", self.system_name, self.error)
+    }
+}
+
+impl std::error::Error for SystemError {}
+
+/// The resource deciding what happens to the errors returned by fallible systems. Errors are
+/// handled when the stage of the failed system flushes. Without this resource, errors are logged.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemErrorHandler(pub fn(SystemError, &mut Resources));
+
+impl SystemErrorHandler {
+    /// Logs errors as they are handled
+    pub fn log() -> Self {
+        Self(|error, _| error!("{}", error))
+    }
+
+    /// Panics on the first error
+    pub fn panic() -> Self {
+        Self(|error, _| panic!("{}", error))
+    }
+}
+
+impl Default for SystemErrorHandler {
+    fn default() -> Self {
+        Self::log()
+    }
+}
+
+/// Runs a system returning an `anyhow::Result<()>` and passes its errors to the
+/// [SystemErrorHandler]. Function systems returning `anyhow::Result<()>` are wrapped in it when
+/// they are added to a stage.
+pub struct FallibleSystem<S> {
+    system: S,
+    errors: Vec<anyhow::Error>,
+}
+
+impl<S: System<In = (), Out = anyhow::Result<()>>> FallibleSystem<S> {
+    pub fn new(system: S) -> Self {
+        Self {
+            system,
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl<S: System<In = (), Out = anyhow::Result<()>>> System for FallibleSystem<S> {
+    type In = ();
+    type Out = ();
+
+    fn name(&self) -> Cow<'static, str> {
+        self.system.name()
+    }
+
+    fn id(&self) -> SystemId {
+        self.system.id()
+    }
+
+    fn update(&mut self, world: &World) {
+        self.system.update(world);
+    }
+
+    fn archetype_component_access(&self) -> &TypeAccess<ArchetypeComponent> {
+        self.system.archetype_component_access()
+    }
+
+    fn resource_access(&self) -> &TypeAccess<TypeId> {
+        self.system.resource_access()
+    }
+
+    fn thread_local_execution(&self) -> ThreadLocalExecution {
+        self.system.thread_local_execution()
+    }
+
+    fn is_non_send(&self) -> bool {
+        self.system.is_non_send()
+    }
+
+    fn get_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.system.get_type_name(type_id)
+    }
+
+    unsafe fn run_unsafe(
+        &mut self,
+        _input: Self::In,
+        world: &World,
+        resources: &Resources,
+    ) -> Option<Self::Out> {
+        if let Some(Err(error)) = self.system.run_unsafe((), world, resources) {
+            self.errors.push(error);
+        }
+        Some(())
+    }
+
+    fn run_thread_local(&mut self, world: &mut World, resources: &mut Resources) {
+        self.system.run_thread_local(world, resources);
+        if self.errors.is_empty() {
+            return;
+        }
+        let handler = resources
+            .get::<SystemErrorHandler>()
+            .map(|handler| *handler)
+            .unwrap_or_default();
+        for error in self.errors.drain(..) {
+            let system_error = SystemError {
+                system_name: self.system.name(),
+                error,
+            };
+            (handler.0)(system_error, resources);
+        }
+    }
+
+    fn initialize(&mut self, world: &mut World, resources: &mut Resources) {
+        self.system.initialize(world, resources);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SystemError, SystemErrorHandler};
+    use crate::{IntoSystem, Res, ResMut, Resources, Schedule, SystemStage, World};
+    use anyhow::{bail, Result};
+
+    #[derive(Default)]
+    struct Errors(Vec<String>);
+
+    fn collect_error(error: SystemError, resources: &mut Resources) {
+        resources
+            .get_mut::<Errors>()
+            .unwrap()
+            .0
+            .push(error.to_string());
+    }
+
+    fn fails_on_odd(frame: Res<u32>) -> Result<()> {
+        if *frame % 2 == 1 {
+            bail!("odd frame {}", *frame);
+        }
+        Ok(())
+    }
+
+    fn next_frame(mut frame: ResMut<u32>) {
+        *frame += 1;
+    }
+
+    #[test]
+    fn system_errors_are_handled() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(0u32);
+        resources.insert(Errors::default());
+        resources.insert(SystemErrorHandler(collect_error));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::single(fails_on_odd.system()));
+        schedule.add_stage("next_frame", SystemStage::single(next_frame.system()));
+        for _ in 0..4 {
+            schedule.initialize_and_run(&mut world, &mut resources);
+        }
+
+        assert_eq!(
+            resources.get::<Errors>().unwrap().0,
+            vec![
+                "system `bevy_ecs::system::fallible_system::tests::fails_on_odd` failed: odd frame 1",
+                "system `bevy_ecs::system::fallible_system::tests::fails_on_odd` failed: odd frame 3",
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "failed: odd frame 1")]
+    fn panic_error_handler() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(1u32);
+        resources.insert(SystemErrorHandler::panic());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::parallel());
+        schedule.add_system_to_stage("update", fails_on_odd.system());
+        schedule.initialize_and_run(&mut world, &mut resources);
+    }
+}
@@ -1,8 +1,9 @@
 use super::system_param::FetchSystemParam;
 use crate::{
-    ArchetypeComponent, Commands, QueryAccess, Resources, System, SystemId, SystemParam,
-    ThreadLocalExecution, TypeAccess, World,
+    ArchetypeComponent, ArchetypesGeneration, Commands, MatchedArchetypes, QueryAccess, Resources,
+    System, SystemId, SystemParam, ThreadLocalExecution, TypeAccess, World,
 };
+use bevy_utils::HashMap;
 use parking_lot::Mutex;
 use std::{any::TypeId, borrow::Cow, cell::UnsafeCell, sync::Arc};
 
@@ -11,13 +12,21 @@ pub struct SystemState {
     pub(crate) name: Cow<'static, str>,
     pub(crate) archetype_component_access: TypeAccess<ArchetypeComponent>,
     pub(crate) resource_access: TypeAccess<TypeId>,
+    pub(crate) resource_type_names: HashMap<TypeId, &'static str>,
     pub(crate) local_resource_access: TypeAccess<TypeId>,
     pub(crate) query_archetype_component_accesses: Vec<TypeAccess<ArchetypeComponent>>,
     pub(crate) query_accesses: Vec<Vec<QueryAccess>>,
     pub(crate) query_type_names: Vec<&'static str>,
+    pub(crate) query_matched_archetypes: Vec<Option<MatchedArchetypes>>,
+    pub(crate) archetypes_generation: Option<ArchetypesGeneration>,
+    pub(crate) storage_generation: u64,
+    pub(crate) archetypes_seen: usize,
+    pub(crate) is_non_send: bool,
     pub(crate) commands: UnsafeCell<Commands>,
     pub(crate) arc_commands: Option<Arc<Mutex<Commands>>>,
     pub(crate) current_query_index: UnsafeCell<usize>,
+    pub(crate) last_change_tick: u32,
+    pub(crate) change_tick: u32,
 }
 
 // SAFE: UnsafeCell<Commands> and UnsafeCell<usize> only accessed from the thread they are scheduled on
@@ -31,20 +40,74 @@ impl SystemState {
         }
     }
 
+    /// Returns the name of a component or resource type accessed by this system's parameters
+    pub fn get_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.query_accesses
+            .iter()
+            .flatten()
+            .find_map(|query_access| query_access.get_type_name(type_id))
+            .or_else(|| self.resource_type_names.get(&type_id).copied())
+    }
+
     pub fn update(&mut self, world: &World) {
+        let generation = world.archetypes_generation();
+        if self.archetypes_generation == Some(generation) {
+            return;
+        }
+        self.archetypes_generation = Some(generation);
+
+        // archetypes are never removed, so only new archetypes need to be checked unless a
+        // storage change affected which of the existing archetypes match
+        if self.storage_generation != world.storage_generation() {
+            self.storage_generation = world.storage_generation();
+            self.archetypes_seen = 0;
+            for component_access in self.query_archetype_component_accesses.iter_mut() {
+                component_access.clear();
+            }
+            for matched_archetypes in self.query_matched_archetypes.iter_mut().flatten() {
+                matched_archetypes.clear();
+            }
+        }
+        let sparse_sets = world.sparse_sets();
+        let new_archetypes = world
+            .archetypes
+            .iter()
+            .enumerate()
+            .skip(self.archetypes_seen);
+        for (index, archetype) in new_archetypes {
+            for ((query_accesses, component_access), matched_archetypes) in self
+                .query_accesses
+                .iter()
+                .zip(self.query_archetype_component_accesses.iter_mut())
+                .zip(self.query_matched_archetypes.iter_mut())
+            {
+                for query_access in query_accesses.iter() {
+                    let _ = query_access.get_access(
+                        archetype,
+                        index as u32,
+                        sparse_sets,
+                        Some(component_access),
+                    );
+                }
+                if let Some(matched_archetypes) = matched_archetypes {
+                    matched_archetypes.add_archetype(archetype, index as u32, sparse_sets);
+                }
+            }
+        }
+        self.archetypes_seen = world.archetypes.len();
+        for matched_archetypes in self.query_matched_archetypes.iter_mut().flatten() {
+            matched_archetypes.set_generation(generation);
+        }
+
         self.archetype_component_access.clear();
         let mut conflict_index = None;
         let mut conflict_name = None;
         for (i, (query_accesses, component_access)) in self
             .query_accesses
             .iter()
-            .zip(self.query_archetype_component_accesses.iter_mut())
+            .zip(self.query_archetype_component_accesses.iter())
             .enumerate()
         {
-            component_access.clear();
-            for query_access in query_accesses.iter() {
-                query_access.get_world_archetype_access(world, Some(component_access));
-            }
             if !component_access.is_compatible(&self.archetype_component_access) {
                 conflict_index = Some(i);
                 conflict_name = component_access
@@ -79,12 +142,14 @@ impl SystemState {
     }
 }
 
+type WorldAccessFn =
+    Box<dyn FnMut(&mut SystemState, &mut World, &mut Resources) + Send + Sync + 'static>;
+
 pub struct FuncSystem<Out> {
     func:
         Box<dyn FnMut(&mut SystemState, &World, &Resources) -> Option<Out> + Send + Sync + 'static>,
-    thread_local_func:
-        Box<dyn FnMut(&mut SystemState, &mut World, &mut Resources) + Send + Sync + 'static>,
-    init_func: Box<dyn FnMut(&mut SystemState, &World, &mut Resources) + Send + Sync + 'static>,
+    thread_local_func: WorldAccessFn,
+    init_func: WorldAccessFn,
     state: SystemState,
 }
 
@@ -116,6 +181,14 @@ impl<Out: 'static> System for FuncSystem<Out> {
         ThreadLocalExecution::NextFlush
     }
 
+    fn is_non_send(&self) -> bool {
+        self.state.is_non_send
+    }
+
+    fn get_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.state.get_type_name(type_id)
+    }
+
     unsafe fn run_unsafe(
         &mut self,
         _input: Self::In,
@@ -138,9 +211,8 @@ pub struct InputFuncSystem<In, Out> {
     func: Box<
         dyn FnMut(In, &mut SystemState, &World, &Resources) -> Option<Out> + Send + Sync + 'static,
     >,
-    thread_local_func:
-        Box<dyn FnMut(&mut SystemState, &mut World, &mut Resources) + Send + Sync + 'static>,
-    init_func: Box<dyn FnMut(&mut SystemState, &World, &mut Resources) + Send + Sync + 'static>,
+    thread_local_func: WorldAccessFn,
+    init_func: WorldAccessFn,
     state: SystemState,
 }
 
@@ -172,6 +244,14 @@ impl<In: 'static, Out: 'static> System for InputFuncSystem<In, Out> {
         ThreadLocalExecution::NextFlush
     }
 
+    fn is_non_send(&self) -> bool {
+        self.state.is_non_send
+    }
+
+    fn get_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.state.get_type_name(type_id)
+    }
+
     unsafe fn run_unsafe(
         &mut self,
         input: In,
@@ -220,6 +300,7 @@ macro_rules! impl_into_system {
                         name: std::any::type_name::<Self>().into(),
                         archetype_component_access: TypeAccess::default(),
                         resource_access: TypeAccess::default(),
+                        resource_type_names: HashMap::default(),
                         local_resource_access: TypeAccess::default(),
                         id: SystemId::new(),
                         commands: Default::default(),
@@ -228,17 +309,29 @@ macro_rules! impl_into_system {
                         query_archetype_component_accesses: Vec::new(),
                         query_accesses: Vec::new(),
                         query_type_names: Vec::new(),
+                        query_matched_archetypes: Vec::new(),
+                        archetypes_generation: None,
+                        storage_generation: 0,
+                        archetypes_seen: 0,
+                        is_non_send: false,
+                        last_change_tick: 0,
+                        change_tick: 0,
                     },
                     func: Box::new(move |state, world, resources| {
                         state.reset_indices();
+                        state.change_tick = world.increment_change_tick();
                         // let mut input = Some(input);
-                        unsafe {
+                        let out = unsafe {
                             if let Some(($($param,)*)) = <<($($param,)*) as SystemParam>::Fetch as FetchSystemParam>::get_param(state, world, resources) {
                                 Some(self($($param),*))
                             } else {
                                 None
                             }
+                        };
+                        if out.is_some() {
+                            state.last_change_tick = state.change_tick;
                         }
+                        out
                     }),
                     thread_local_func: Box::new(|state, world, resources| {
                         // SAFE: this is called with unique access to SystemState
@@ -272,6 +365,7 @@ macro_rules! impl_into_system {
                         name: std::any::type_name::<Self>().into(),
                         archetype_component_access: TypeAccess::default(),
                         resource_access: TypeAccess::default(),
+                        resource_type_names: HashMap::default(),
                         local_resource_access: TypeAccess::default(),
                         id: SystemId::new(),
                         commands: Default::default(),
@@ -280,17 +374,29 @@ macro_rules! impl_into_system {
                         query_archetype_component_accesses: Vec::new(),
                         query_accesses: Vec::new(),
                         query_type_names: Vec::new(),
+                        query_matched_archetypes: Vec::new(),
+                        archetypes_generation: None,
+                        storage_generation: 0,
+                        archetypes_seen: 0,
+                        is_non_send: false,
+                        last_change_tick: 0,
+                        change_tick: 0,
                     },
                     func: Box::new(move |input, state, world, resources| {
                         state.reset_indices();
+                        state.change_tick = world.increment_change_tick();
                         // let mut input = Some(input);
-                        unsafe {
+                        let out = unsafe {
                             if let Some(($($param,)*)) = <<($($param,)*) as SystemParam>::Fetch as FetchSystemParam>::get_param(state, world, resources) {
                                 Some(self(In(input), $($param),*))
                             } else {
                                 None
                             }
+                        };
+                        if out.is_some() {
+                            state.last_change_tick = state.change_tick;
                         }
+                        out
                     }),
                     thread_local_func: Box::new(|state, world, resources| {
                         // SAFE: this is called with unique access to SystemState
@@ -336,8 +442,11 @@ mod tests {
         clear_trackers_system,
         resource::{Res, ResMut, Resources},
         schedule::Schedule,
-        ChangedRes, Entity, Local, Or, Query, QuerySet, System, SystemStage, With, World,
+        Changed, ChangedRes, Commands, Entity, Local, Mutated, NonSend, NonSendMut, Or, Query,
+        QueryManyError, QuerySet, RemovedComponents, ShouldRun, StorageType, System, SystemSet,
+        SystemStage, With, World,
     };
+    use bevy_tasks::{ComputeTaskPool, ParallelIterator, TaskPool};
 
     #[derive(Debug, Eq, PartialEq, Default)]
     struct A;
@@ -404,6 +513,52 @@ mod tests {
         assert!(*resources.get::<bool>().unwrap(), "system ran");
     }
 
+    #[test]
+    fn query_get_many_system() {
+        fn query_system(
+            mut ran: ResMut<bool>,
+            entity_query: Query<Entity, With<A>>,
+            mut query: Query<&mut i32>,
+        ) {
+            let entities = entity_query.iter().collect::<Vec<Entity>>();
+            let [mut e0, mut e1] = query.get_many_mut([entities[0], entities[1]]).unwrap();
+            std::mem::swap(&mut *e0, &mut *e1);
+
+            assert_eq!(
+                query
+                    .get_many_mut([entities[0], entities[2], entities[0]])
+                    .err(),
+                Some(QueryManyError::AliasedEntity(entities[0]))
+            );
+            assert_eq!(
+                query.get_many_mut([entities[0], entities[3]]).err(),
+                Some(QueryManyError::NoSuchEntity(entities[3]))
+            );
+            let [e0, e1, e2] = query
+                .get_many_mut([entities[0], entities[1], entities[2]])
+                .unwrap();
+            assert_eq!([*e0, *e1, *e2], [2, 1, 3]);
+            assert_eq!(
+                entity_query.get_many([entities[1], entities[1]]),
+                Ok([entities[1], entities[1]])
+            );
+
+            *ran = true;
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(false);
+        world.spawn((A, 1));
+        world.spawn((A, 2));
+        world.spawn((A, 3));
+        world.spawn((A,));
+
+        run_system(&mut world, &mut resources, query_system.system());
+
+        assert!(*resources.get::<bool>().unwrap(), "system ran");
+    }
+
     #[test]
     fn or_query_set_system() {
         // Regression test for issue #762
@@ -437,6 +592,216 @@ mod tests {
         assert!(*resources.get::<bool>().unwrap(), "system ran");
     }
 
+    #[test]
+    fn par_iter_system() {
+        fn par_iter_system(
+            pool: Res<ComputeTaskPool>,
+            mut counted: ResMut<usize>,
+            mut numbers: Query<&mut i32>,
+            a_query: Query<&A>,
+        ) {
+            numbers
+                .par_iter_mut(7)
+                .for_each(&pool, |mut number| *number += 1);
+            *counted = a_query.par_iter(3).count(&pool);
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(0usize);
+        let entities = (0..100)
+            .map(|i| {
+                if i % 2 == 0 {
+                    world.spawn((i, A))
+                } else {
+                    world.spawn((i,))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        run_system(&mut world, &mut resources, par_iter_system.system());
+
+        for (i, &entity) in entities.iter().enumerate() {
+            assert_eq!(*world.get::<i32>(entity).unwrap(), i as i32 + 1);
+        }
+        assert_eq!(*resources.get::<usize>().unwrap(), 50);
+    }
+
+    #[test]
+    fn par_iter_filtered_system() {
+        #[derive(Default)]
+        struct Found {
+            with_a: Vec<i32>,
+            changed: Vec<i32>,
+        }
+
+        fn mutate(flip: Res<bool>, mut query: Query<&mut i32>) {
+            if *flip {
+                for mut i in query.iter_mut() {
+                    if *i >= 15 {
+                        *i += 100;
+                    }
+                }
+            }
+        }
+
+        // batches of 3 start in the middle of the archetype
+        fn find(
+            pool: Res<ComputeTaskPool>,
+            mut found: ResMut<Found>,
+            with_a: Query<&i32, With<A>>,
+            changed: Query<&i32, Changed<i32>>,
+        ) {
+            found.with_a = with_a.par_iter(3).copied().collect(&pool);
+            found.with_a.sort_unstable();
+            found.changed = changed.par_iter(3).copied().collect(&pool);
+            found.changed.sort_unstable();
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(false);
+        resources.insert(Found::default());
+        world.register_component::<A>(StorageType::SparseSet);
+        for i in 0..20 {
+            let entity = world.spawn((i,));
+            if i >= 10 {
+                world.insert_one(entity, A).unwrap();
+            }
+        }
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("mutate", SystemStage::single(mutate.system()));
+        schedule.add_stage("find", SystemStage::single(find.system()));
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        {
+            let found = resources.get::<Found>().unwrap();
+            assert_eq!(found.with_a, (10..20).collect::<Vec<_>>());
+            assert_eq!(found.changed, (0..20).collect::<Vec<_>>(), "all added");
+        }
+
+        *resources.get_mut::<bool>().unwrap() = true;
+        schedule.initialize_and_run(&mut world, &mut resources);
+        let found = resources.get::<Found>().unwrap();
+        assert_eq!(
+            found.with_a,
+            vec![10, 11, 12, 13, 14, 115, 116, 117, 118, 119]
+        );
+        assert_eq!(found.changed, vec![115, 116, 117, 118, 119]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn par_iter_zero_batch_size() {
+        let world = World::default();
+        world.query_batched::<&i32>(0);
+    }
+
+    #[test]
+    fn non_send_system() {
+        use std::{cell::Cell, rc::Rc, thread, thread::ThreadId};
+
+        fn count(counter: NonSend<Rc<Cell<usize>>>, mut numbers: Query<&mut i32>) {
+            counter.set(counter.get() + 1);
+            for mut number in numbers.iter_mut() {
+                *number += 1;
+            }
+        }
+        fn record_thread(mut threads: NonSendMut<Vec<ThreadId>>, _a: Query<&A>) {
+            threads.push(thread::current().id());
+        }
+        fn increment(mut value: ResMut<usize>, _a: Query<&A>) {
+            *value += 1;
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(0usize);
+        let counter = Rc::new(Cell::new(0usize));
+        resources.insert_thread_local(counter.clone());
+        resources.insert_thread_local(Vec::<ThreadId>::new());
+        let entity = world.spawn((0i32, A));
+
+        let mut schedule = Schedule::default();
+        let mut update = SystemStage::parallel();
+        update
+            .add_system(count.system())
+            .add_system(increment.system())
+            .add_system(record_thread.system());
+        schedule.add_stage("update", update);
+        for _ in 0..20 {
+            schedule.initialize_and_run(&mut world, &mut resources);
+        }
+
+        assert_eq!(counter.get(), 20);
+        assert_eq!(*world.get::<i32>(entity).unwrap(), 20);
+        assert_eq!(*resources.get::<usize>().unwrap(), 20);
+        let threads = resources.get_thread_local::<Vec<ThreadId>>().unwrap();
+        assert_eq!(threads.len(), 20);
+        assert!(threads.iter().all(|&id| id == thread::current().id()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn conflicting_non_send_params() {
+        fn sys(_a: NonSend<i32>, _b: NonSendMut<i32>) {}
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert_thread_local(0i32);
+        run_system(&mut world, &mut resources, sys.system());
+    }
+
+    #[test]
+    fn query_sees_new_archetypes() {
+        #[derive(Default)]
+        struct Counts(Vec<(usize, usize, usize)>);
+
+        fn count(
+            mut counts: ResMut<Counts>,
+            pool: Res<ComputeTaskPool>,
+            numbers: Query<&i32>,
+            selected: Query<&D, With<i32>>,
+        ) {
+            counts.0.push((
+                numbers.iter().len(),
+                numbers.par_iter(1).map(|_| 1usize).count(&pool),
+                selected.iter().count(),
+            ));
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(ComputeTaskPool(TaskPool::default()));
+        resources.insert(Counts::default());
+        let e = world.spawn((A, 1));
+        world.spawn((A, 1));
+
+        let mut schedule = Schedule::default();
+        let mut update = SystemStage::parallel();
+        update.add_system(count.system());
+        schedule.add_stage("update", update);
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        world.spawn((B, 2));
+        world.spawn((C, 3));
+        world.spawn((B,));
+        schedule.initialize_and_run(&mut world, &mut resources);
+        schedule.initialize_and_run(&mut world, &mut resources);
+        // registering a sparse component changes which of the existing archetypes can match
+        world.register_component::<D>(StorageType::SparseSet);
+        world.insert_one(e, D).unwrap();
+        schedule.initialize_and_run(&mut world, &mut resources);
+
+        assert_eq!(
+            resources.get::<Counts>().unwrap().0,
+            vec![(2, 2, 0), (4, 4, 0), (4, 4, 0), (4, 4, 1)]
+        );
+    }
+
     #[test]
     fn changed_resource_system() {
         fn incr_e_on_flip(_run_on_flip: ChangedRes<bool>, mut query: Query<&mut i32>) {
@@ -470,6 +835,63 @@ mod tests {
         assert_eq!(*(world.get::<i32>(ent).unwrap()), 2);
     }
 
+    #[test]
+    fn resource_change_detection_system() {
+        #[derive(Default)]
+        struct Log(Vec<(bool, bool, Option<u32>)>);
+
+        fn log(
+            flag: Res<bool>,
+            maybe_number: Option<Res<u32>>,
+            maybe_counter: Option<ResMut<usize>>,
+            mut log: ResMut<Log>,
+        ) {
+            log.0.push((
+                flag.is_added(),
+                flag.is_changed(),
+                maybe_number.map(|number| *number),
+            ));
+            if let Some(mut counter) = maybe_counter {
+                assert!(!counter.is_added());
+                assert!(!counter.is_changed());
+                *counter += 1;
+                assert!(counter.is_changed());
+            }
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(false);
+        resources.insert(Log::default());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", SystemStage::single(log.system()));
+        schedule.add_stage(
+            "clear_trackers",
+            SystemStage::single(clear_trackers_system.system()),
+        );
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        schedule.initialize_and_run(&mut world, &mut resources);
+        *resources.get_mut::<bool>().unwrap() = true;
+        resources.insert(3u32);
+        schedule.initialize_and_run(&mut world, &mut resources);
+        resources.insert(0usize);
+        resources.clear_trackers();
+        schedule.initialize_and_run(&mut world, &mut resources);
+
+        assert_eq!(
+            resources.get::<Log>().unwrap().0,
+            vec![
+                (true, true, None),
+                (false, false, None),
+                (false, true, Some(3)),
+                (false, false, Some(3))
+            ]
+        );
+        assert_eq!(*resources.get::<usize>().unwrap(), 1);
+    }
+
     #[test]
     fn changed_resource_or_system() {
         fn incr_e_on_flip(
@@ -514,6 +936,174 @@ mod tests {
         assert_eq!(*(world.get::<i32>(ent).unwrap()), 3);
     }
 
+    #[test]
+    fn changed_query_survives_clear_trackers() {
+        fn mutate(flip: Res<bool>, mut query: Query<&mut i32>) {
+            if *flip {
+                for mut i in query.iter_mut() {
+                    *i += 1;
+                }
+            }
+        }
+
+        fn count_changed(mut count: ResMut<usize>, query: Query<(), Changed<i32>>) {
+            *count += query.iter().count();
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(false);
+        resources.insert(0usize);
+        world.spawn((0,));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("mutate", SystemStage::single(mutate.system()));
+        schedule.add_stage(
+            "clear_trackers",
+            SystemStage::single(clear_trackers_system.system()),
+        );
+        schedule.add_stage("count", SystemStage::single(count_changed.system()));
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(
+            *resources.get::<usize>().unwrap(),
+            1,
+            "added before first run"
+        );
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(*resources.get::<usize>().unwrap(), 1, "nothing changed");
+
+        *resources.get_mut::<bool>().unwrap() = true;
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(
+            *resources.get::<usize>().unwrap(),
+            2,
+            "mutated before clear"
+        );
+    }
+
+    #[test]
+    fn changed_query_across_skipped_runs() {
+        fn every_other_frame(mut frame: Local<u32>) -> ShouldRun {
+            *frame += 1;
+            if *frame % 2 == 0 {
+                ShouldRun::Yes
+            } else {
+                ShouldRun::No
+            }
+        }
+
+        // only mutates on the frames where `count_mutated` doesn't run
+        fn mutate_on_odd_frames(mut frame: Local<u32>, mut query: Query<&mut i32>) {
+            *frame += 1;
+            if *frame % 2 == 1 {
+                for mut i in query.iter_mut() {
+                    *i += 1;
+                }
+            }
+        }
+
+        fn count_mutated(mut count: ResMut<usize>, query: Query<(), Mutated<i32>>) {
+            *count += query.iter().count();
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(0usize);
+        world.spawn((0,));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("mutate", SystemStage::single(mutate_on_odd_frames.system()));
+        schedule.add_stage(
+            "count",
+            SystemStage::serial().with_system_set(
+                SystemSet::new()
+                    .with_run_criteria(every_other_frame.system())
+                    .with_system(count_mutated.system()),
+            ),
+        );
+        schedule.add_stage(
+            "clear_trackers",
+            SystemStage::single(clear_trackers_system.system()),
+        );
+
+        for _ in 0..4 {
+            schedule.initialize_and_run(&mut world, &mut resources);
+        }
+        assert_eq!(*resources.get::<usize>().unwrap(), 2);
+        assert_eq!(*world.query::<&i32>().next().unwrap(), 2);
+    }
+
+    #[test]
+    fn removed_components_system() {
+        struct EveryFrame(Vec<Entity>);
+        struct EveryThirdFrame(Vec<Entity>);
+
+        fn read_every_frame(mut seen: ResMut<EveryFrame>, mut removed: RemovedComponents<A>) {
+            seen.0.extend(removed.iter());
+        }
+
+        fn read_every_third_frame(
+            mut frame: Local<u32>,
+            mut seen: ResMut<EveryThirdFrame>,
+            mut removed: RemovedComponents<A>,
+        ) {
+            *frame += 1;
+            if *frame % 3 == 0 {
+                seen.0.extend(removed.iter());
+            }
+        }
+
+        fn despawn_on_flip(
+            flip: ChangedRes<bool>,
+            commands: &mut Commands,
+            query: Query<Entity, With<A>>,
+        ) {
+            if *flip {
+                for entity in query.iter() {
+                    commands.despawn(entity);
+                }
+            }
+        }
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(false);
+        resources.insert(EveryFrame(Vec::new()));
+        resources.insert(EveryThirdFrame(Vec::new()));
+        let a = world.spawn((A,));
+        let b = world.spawn((A, B));
+        world.spawn((B,));
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage(
+            "read",
+            SystemStage::parallel()
+                .with_system(read_every_frame.system())
+                .with_system(read_every_third_frame.system()),
+        );
+        schedule.add_stage("despawn", SystemStage::single(despawn_on_flip.system()));
+        schedule.add_stage(
+            "clear_trackers",
+            SystemStage::single(clear_trackers_system.system()),
+        );
+
+        schedule.initialize_and_run(&mut world, &mut resources);
+        *resources.get_mut::<bool>().unwrap() = true;
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert!(
+            resources.get::<EveryFrame>().unwrap().0.is_empty(),
+            "removals happen after the reading stage"
+        );
+
+        for _ in 0..4 {
+            schedule.initialize_and_run(&mut world, &mut resources);
+        }
+        assert_eq!(resources.get::<EveryFrame>().unwrap().0, vec![a, b]);
+        assert_eq!(resources.get::<EveryThirdFrame>().unwrap().0, vec![a, b]);
+    }
+
     #[test]
     #[should_panic]
     fn conflicting_query_mut_system() {
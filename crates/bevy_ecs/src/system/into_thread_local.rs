@@ -47,6 +47,7 @@ impl System for ThreadLocalSystemFn {
 
     fn run_thread_local(&mut self, world: &mut World, resources: &mut Resources) {
         (self.func)(world, resources);
+        world.apply_hook_commands(resources);
     }
 
     fn initialize(&mut self, _world: &mut World, _resources: &mut Resources) {}
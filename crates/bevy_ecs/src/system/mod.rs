@@ -1,16 +1,20 @@
 mod commands;
+mod fallible_system;
 mod into_system;
 mod into_thread_local;
 mod query;
+mod removed_components;
 #[allow(clippy::module_inception)]
 mod system;
 mod system_chaining;
 mod system_param;
 
 pub use commands::*;
+pub use fallible_system::*;
 pub use into_system::*;
 pub use into_thread_local::*;
 pub use query::*;
+pub use removed_components::*;
 pub use system::*;
 pub use system_chaining::*;
 pub use system_param::*;
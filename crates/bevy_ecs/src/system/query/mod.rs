@@ -1,18 +1,24 @@
 mod query_set;
+mod targeting;
 pub use query_set::*;
+pub use targeting::*;
 
 use crate::{
     ArchetypeComponent, Batch, BatchedIter, Component, ComponentError, Entity, Fetch, Mut,
     QueryFilter, QueryIter, ReadOnlyFetch, TypeAccess, World, WorldQuery,
 };
 use bevy_tasks::ParallelIterator;
-use std::marker::PhantomData;
+use std::{convert::TryInto, marker::PhantomData};
+use thiserror::Error;
 
 /// Provides scoped access to a World according to a given [HecsQuery]
 #[derive(Debug)]
 pub struct Query<'a, Q: WorldQuery, F: QueryFilter = ()> {
     pub(crate) world: &'a World,
     pub(crate) component_access: &'a TypeAccess<ArchetypeComponent>,
+    matched_archetypes: Option<&'a [u32]>,
+    last_change_tick: u32,
+    change_tick: u32,
     _marker: PhantomData<(Q, F)>,
 }
 
@@ -25,7 +31,19 @@ pub enum QueryError {
     NoSuchEntity,
 }
 
+/// An error that occurs when using [Query::get_many] or [Query::get_many_mut]
+#[derive(Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum QueryManyError {
+    #[error("the entity {0:?} does not exist or does not match the query")]
+    NoSuchEntity(Entity),
+    #[error("the entity {0:?} was requested more than once")]
+    AliasedEntity(Entity),
+}
+
 impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
+    /// Creates a Query whose change detection filters report changes made after `last_change_tick`.
+    /// Mutations made through the query are recorded at `change_tick`.
+    ///
     /// # Safety
     /// This will create a Query that could violate memory safety rules. Make sure that this is only called in
     /// ways that ensure the Queries have unique mutable access.
@@ -33,14 +51,27 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
     pub(crate) unsafe fn new(
         world: &'a World,
         component_access: &'a TypeAccess<ArchetypeComponent>,
+        last_change_tick: u32,
+        change_tick: u32,
     ) -> Self {
         Self {
             world,
             component_access,
+            matched_archetypes: None,
+            last_change_tick,
+            change_tick,
             _marker: PhantomData::default(),
         }
     }
 
+    /// Restricts iteration to the archetypes at the given indices, which must include every
+    /// archetype in the world matched by this query
+    #[inline]
+    pub(crate) fn with_matched_archetypes(mut self, matched_archetypes: Option<&'a [u32]>) -> Self {
+        self.matched_archetypes = matched_archetypes;
+        self
+    }
+
     /// Iterates over the query results. This can only be called for read-only queries
     #[inline]
     pub fn iter(&self) -> QueryIter<'_, Q, F>
@@ -48,14 +79,22 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
         Q::Fetch: ReadOnlyFetch,
     {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
-        unsafe { self.world.query_unchecked() }
+        unsafe {
+            self.world
+                .query_with_ticks_unchecked(self.last_change_tick, self.change_tick)
+                .with_archetype_indices(self.matched_archetypes)
+        }
     }
 
     /// Iterates over the query results
     #[inline]
     pub fn iter_mut(&mut self) -> QueryIter<'_, Q, F> {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
-        unsafe { self.world.query_unchecked() }
+        unsafe {
+            self.world
+                .query_with_ticks_unchecked(self.last_change_tick, self.change_tick)
+                .with_archetype_indices(self.matched_archetypes)
+        }
     }
 
     /// Iterates over the query results
@@ -64,22 +103,194 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
     #[inline]
     pub unsafe fn iter_unsafe(&self) -> QueryIter<'_, Q, F> {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
-        self.world.query_unchecked()
+        self.world
+            .query_with_ticks_unchecked(self.last_change_tick, self.change_tick)
+            .with_archetype_indices(self.matched_archetypes)
     }
 
+    /// Iterates over the query results in parallel, in batches of at most `batch_size` entities.
+    /// This can only be called for read-only queries
+    ///
+    /// Each batch is processed as a single task on the `TaskPool` passed to the consuming
+    /// `ParallelIterator` method, usually the `ComputeTaskPool` resource. Small batches spread
+    /// the work more evenly across threads, at the cost of more scheduling overhead.
+    ///
+    /// # Panics
+    /// Panics if `batch_size` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::prelude::*;
+    /// # use bevy_tasks::{ComputeTaskPool, ParallelIterator};
+    /// struct Velocity(f32);
+    ///
+    /// fn count_fast(pool: Res<ComputeTaskPool>, query: Query<&Velocity>) {
+    ///     let fast = query
+    ///         .par_iter(64)
+    ///         .filter(|velocity| velocity.0.abs() > 10.0)
+    ///         .count(&pool);
+    ///     println!("{} entities are moving fast", fast);
+    /// }
+    /// # count_fast.system();
+    /// ```
     #[inline]
     pub fn par_iter(&self, batch_size: usize) -> ParIter<'_, Q, F>
     where
         Q::Fetch: ReadOnlyFetch,
     {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
-        unsafe { ParIter::new(self.world.query_batched_unchecked(batch_size)) }
+        unsafe {
+            ParIter::new(
+                self.world
+                    .query_batched_with_ticks_unchecked(
+                        batch_size,
+                        self.last_change_tick,
+                        self.change_tick,
+                    )
+                    .with_archetype_indices(self.matched_archetypes),
+            )
+        }
     }
 
+    /// Iterates over the query results in parallel, in batches of at most `batch_size` entities
+    ///
+    /// See `par_iter`.
+    ///
+    /// # Panics
+    /// Panics if `batch_size` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::prelude::*;
+    /// # use bevy_tasks::{ComputeTaskPool, ParallelIterator};
+    /// struct Position(f32);
+    /// struct Velocity(f32);
+    ///
+    /// fn movement(pool: Res<ComputeTaskPool>, mut query: Query<(&mut Position, &Velocity)>) {
+    ///     query
+    ///         .par_iter_mut(64)
+    ///         .for_each(&pool, |(mut position, velocity)| position.0 += velocity.0);
+    /// }
+    /// # movement.system();
+    /// ```
     #[inline]
     pub fn par_iter_mut(&mut self, batch_size: usize) -> ParIter<'_, Q, F> {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
-        unsafe { ParIter::new(self.world.query_batched_unchecked(batch_size)) }
+        unsafe {
+            ParIter::new(
+                self.world
+                    .query_batched_with_ticks_unchecked(
+                        batch_size,
+                        self.last_change_tick,
+                        self.change_tick,
+                    )
+                    .with_archetype_indices(self.matched_archetypes),
+            )
+        }
+    }
+
+    /// Iterates over the query results of the entities with a `Relation<K>` pointing at `target`.
+    /// This can only be called for read-only queries
+    ///
+    /// Only the archetypes with a `Relation<K>` are visited, and the filter of the query applies
+    /// as usual. The entities targeting `target` are looked up once, in the reverse index of the
+    /// relation.
+    ///
+    /// # Panics
+    /// Panics if the relation kind `K` isn't registered with `World::register_relation`.
+    pub fn iter_targeting<K: 'static>(&self, target: Entity) -> TargetingIter<'_, Q, F, K>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+        unsafe { self.iter_targeting_unsafe(target) }
+    }
+
+    /// Iterates over the query results of the entities with a `Relation<K>` pointing at `target`.
+    /// See [Query::iter_targeting].
+    ///
+    /// # Panics
+    /// Panics if the relation kind `K` isn't registered with `World::register_relation`.
+    pub fn iter_targeting_mut<K: 'static>(&mut self, target: Entity) -> TargetingIter<'_, Q, F, K> {
+        // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+        unsafe { self.iter_targeting_unsafe(target) }
+    }
+
+    /// # Safety
+    /// This allows aliased mutability. You must make sure this call does not result in multiple mutable references to the same component
+    unsafe fn iter_targeting_unsafe<K: 'static>(
+        &self,
+        target: Entity,
+    ) -> TargetingIter<'_, Q, F, K> {
+        TargetingIter::new(
+            self.world
+                .query_with_ticks_unchecked(self.last_change_tick, self.change_tick)
+                .with_archetype_indices(self.matched_archetypes),
+            self.world
+                .relation_sources::<K>(target)
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    /// Iterates in parallel over the query results of the entities with a `Relation<K>` pointing
+    /// at `target`, in batches of at most `batch_size` entities. This can only be called for
+    /// read-only queries
+    ///
+    /// See `par_iter` and `iter_targeting`.
+    ///
+    /// # Panics
+    /// Panics if `batch_size` is zero, or if the relation kind `K` isn't registered with
+    /// `World::register_relation`.
+    pub fn par_iter_targeting<K: 'static>(
+        &self,
+        batch_size: usize,
+        target: Entity,
+    ) -> ParTargetingIter<'_, Q, F, K>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+        unsafe { self.par_iter_targeting_unsafe(batch_size, target) }
+    }
+
+    /// Iterates in parallel over the query results of the entities with a `Relation<K>` pointing
+    /// at `target`, in batches of at most `batch_size` entities
+    ///
+    /// See `par_iter` and `iter_targeting`.
+    ///
+    /// # Panics
+    /// Panics if `batch_size` is zero, or if the relation kind `K` isn't registered with
+    /// `World::register_relation`.
+    pub fn par_iter_targeting_mut<K: 'static>(
+        &mut self,
+        batch_size: usize,
+        target: Entity,
+    ) -> ParTargetingIter<'_, Q, F, K> {
+        // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
+        unsafe { self.par_iter_targeting_unsafe(batch_size, target) }
+    }
+
+    /// # Safety
+    /// This allows aliased mutability. You must make sure this call does not result in multiple mutable references to the same component
+    unsafe fn par_iter_targeting_unsafe<K: 'static>(
+        &self,
+        batch_size: usize,
+        target: Entity,
+    ) -> ParTargetingIter<'_, Q, F, K> {
+        ParTargetingIter::new(
+            self.world
+                .query_batched_with_ticks_unchecked(
+                    batch_size,
+                    self.last_change_tick,
+                    self.change_tick,
+                )
+                .with_archetype_indices(self.matched_archetypes),
+            self.world
+                .relation_sources::<K>(target)
+                .into_iter()
+                .collect(),
+        )
     }
 
     /// Gets the query result for the given `entity`
@@ -91,7 +302,11 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
         unsafe {
             self.world
-                .query_one_unchecked::<Q, F>(entity)
+                .query_one_with_ticks_unchecked::<Q, F>(
+                    entity,
+                    self.last_change_tick,
+                    self.change_tick,
+                )
                 .map_err(|_err| QueryError::NoSuchEntity)
         }
     }
@@ -102,11 +317,84 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
         // SAFE: system runs without conflicts with other systems. same-system queries have runtime borrow checks when they conflict
         unsafe {
             self.world
-                .query_one_unchecked::<Q, F>(entity)
+                .query_one_with_ticks_unchecked::<Q, F>(
+                    entity,
+                    self.last_change_tick,
+                    self.change_tick,
+                )
                 .map_err(|_err| QueryError::NoSuchEntity)
         }
     }
 
+    /// Gets the query results for several entities at once. This can only be called for
+    /// read-only queries
+    ///
+    /// Results are returned in the order of `entities`. The same entity may be requested more
+    /// than once.
+    #[inline]
+    pub fn get_many<const N: usize>(
+        &self,
+        entities: [Entity; N],
+    ) -> Result<[<Q::Fetch as Fetch<'_>>::Item; N], QueryManyError>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: read-only queries can't create aliased mutable references
+        unsafe { self.get_many_unchecked(entities) }
+    }
+
+    /// Gets the query results for several distinct entities at once, allowing them to be mutated
+    /// simultaneously
+    ///
+    /// Results are returned in the order of `entities`. Fails with
+    /// [QueryManyError::AliasedEntity] if an entity is requested more than once.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_ecs::prelude::*;
+    /// struct Velocity(f32);
+    /// struct Collision(Entity, Entity);
+    ///
+    /// fn bounce(collision: Res<Collision>, mut query: Query<&mut Velocity>) {
+    ///     if let Ok([mut a, mut b]) = query.get_many_mut([collision.0, collision.1]) {
+    ///         std::mem::swap(&mut a.0, &mut b.0);
+    ///     }
+    /// }
+    /// # bounce.system();
+    /// ```
+    #[inline]
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        entities: [Entity; N],
+    ) -> Result<[<Q::Fetch as Fetch<'_>>::Item; N], QueryManyError> {
+        for (i, &entity) in entities.iter().enumerate() {
+            if entities[..i].contains(&entity) {
+                return Err(QueryManyError::AliasedEntity(entity));
+            }
+        }
+        // SAFE: the entities are distinct, so each component is borrowed mutably at most once
+        unsafe { self.get_many_unchecked(entities) }
+    }
+
+    /// # Safety
+    /// Mutable queries must not be given the same entity twice
+    unsafe fn get_many_unchecked<const N: usize>(
+        &self,
+        entities: [Entity; N],
+    ) -> Result<[<Q::Fetch as Fetch<'_>>::Item; N], QueryManyError> {
+        let mut items = Vec::with_capacity(N);
+        for &entity in entities.iter() {
+            let item = self
+                .get_unsafe(entity)
+                .map_err(|_err| QueryManyError::NoSuchEntity(entity))?;
+            items.push(item);
+        }
+        match items.try_into() {
+            Ok(items) => Ok(items),
+            Err(_) => unreachable!("one item is fetched per entity"),
+        }
+    }
+
     /// Gets the query result for the given `entity`
     /// # Safety
     /// This allows aliased mutability. You must make sure this call does not result in multiple mutable references to the same component
@@ -116,7 +404,7 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
         entity: Entity,
     ) -> Result<<Q::Fetch as Fetch>::Item, QueryError> {
         self.world
-            .query_one_unchecked::<Q, F>(entity)
+            .query_one_with_ticks_unchecked::<Q, F>(entity, self.last_change_tick, self.change_tick)
             .map_err(|_err| QueryError::NoSuchEntity)
     }
 
@@ -128,12 +416,8 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
                 .component_access
                 .is_read_or_write(&ArchetypeComponent::new::<T>(location.archetype))
             {
-                // SAFE: we have already checked that the entity/component matches our archetype access. and systems are scheduled to run with safe archetype access
-                unsafe {
-                    self.world
-                        .get_at_location_unchecked(location)
-                        .map_err(QueryError::ComponentError)
-                }
+                // we have already checked that the entity/component matches our archetype access. and systems are scheduled to run with safe archetype access
+                self.world.get(entity).map_err(QueryError::ComponentError)
             } else {
                 Err(QueryError::CannotReadArchetype)
             }
@@ -157,10 +441,10 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
             .component_access
             .is_write(&ArchetypeComponent::new::<T>(location.archetype))
         {
-            // SAFE: RefMut does exclusivity checks and we have already validated the entity
+            // SAFE: we have already checked that the entity/component matches our archetype access. and systems are scheduled to run with safe archetype access
             unsafe {
                 self.world
-                    .get_mut_at_location_unchecked(location)
+                    .get_mut_with_tick_unchecked(entity, self.change_tick)
                     .map_err(QueryError::ComponentError)
             }
         } else {
@@ -177,7 +461,7 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
         entity: Entity,
     ) -> Result<Mut<'_, T>, QueryError> {
         self.world
-            .get_mut_unchecked(entity)
+            .get_mut_with_tick_unchecked(entity, self.change_tick)
             .map_err(QueryError::ComponentError)
     }
 
@@ -196,6 +480,10 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
     /// Thread local systems manipulate the world directly, so removes are applied immediately. This
     /// means any system that runs after a thread local system in the same update will pick up
     /// removals that happened in the thread local system, regardless of stages.
+    ///
+    /// The returned entities are cleared by `clear_trackers` at the end of every update. Use the
+    /// `RemovedComponents<C>` system parameter to observe every removal exactly once, regardless of
+    /// when the system runs.
     pub fn removed<C: Component>(&self) -> &[Entity] {
         self.world.removed::<C>()
     }
@@ -209,7 +497,9 @@ impl<'a, Q: WorldQuery, F: QueryFilter> Query<'a, Q, F> {
     }
 }
 
-/// Parallel version of QueryIter
+/// Parallel version of QueryIter, yielding one `Batch` of query results per task
+///
+/// Created by `Query::par_iter` and `Query::par_iter_mut`.
 pub struct ParIter<'w, Q: WorldQuery, F: QueryFilter> {
     batched_iter: BatchedIter<'w, Q, F>,
 }
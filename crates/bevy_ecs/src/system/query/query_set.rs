@@ -12,7 +12,12 @@ impl_query_set!();
 pub trait QueryTuple {
     /// # Safety
     /// this might cast world and component access to the relevant Self lifetimes. verify that this is safe in each impl
-    unsafe fn new(world: &World, component_access: &TypeAccess<ArchetypeComponent>) -> Self;
+    unsafe fn new(
+        world: &World,
+        component_access: &TypeAccess<ArchetypeComponent>,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Self;
     fn get_accesses() -> Vec<QueryAccess>;
 }
 
@@ -23,9 +28,11 @@ impl<T: QueryTuple> QuerySet<T> {
     pub(crate) unsafe fn new(
         world: &World,
         component_access: &TypeAccess<ArchetypeComponent>,
+        last_change_tick: u32,
+        change_tick: u32,
     ) -> Self {
         QuerySet {
-            value: T::new(world, component_access),
+            value: T::new(world, component_access, last_change_tick, change_tick),
         }
     }
 }
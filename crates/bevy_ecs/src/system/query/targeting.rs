@@ -0,0 +1,93 @@
+use crate::{
+    Batch, BatchedIter, Entity, Fetch, QueryFilter, QueryIter, Relation, With, WorldQuery,
+};
+use bevy_tasks::ParallelIterator;
+use bevy_utils::HashSet;
+use std::sync::Arc;
+
+/// The filter of the queries iterating over the entities targeting another entity. Only archetypes
+/// with a `Relation<K>` are visited.
+type TargetingFilter<F, K> = (F, With<Relation<K>>);
+
+/// Iterator over the query results of the entities with a `Relation<K>` pointing at a target
+///
+/// Created by `Query::iter_targeting` and `Query::iter_targeting_mut`.
+pub struct TargetingIter<'w, Q: WorldQuery, F: QueryFilter, K: 'static> {
+    iter: QueryIter<'w, (Entity, Q), TargetingFilter<F, K>>,
+    sources: HashSet<Entity>,
+}
+
+impl<'w, Q: WorldQuery, F: QueryFilter, K: 'static> TargetingIter<'w, Q, F, K> {
+    pub(crate) fn new(
+        iter: QueryIter<'w, (Entity, Q), TargetingFilter<F, K>>,
+        sources: HashSet<Entity>,
+    ) -> Self {
+        Self { iter, sources }
+    }
+}
+
+impl<'w, Q: WorldQuery, F: QueryFilter, K: 'static> Iterator for TargetingIter<'w, Q, F, K> {
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let sources = &self.sources;
+        self.iter
+            .find(|(entity, _)| sources.contains(entity))
+            .map(|(_, item)| item)
+    }
+}
+
+/// Parallel version of `TargetingIter`, yielding one `TargetingBatch` of query results per task
+///
+/// Created by `Query::par_iter_targeting` and `Query::par_iter_targeting_mut`.
+pub struct ParTargetingIter<'w, Q: WorldQuery, F: QueryFilter, K: 'static> {
+    batched_iter: BatchedIter<'w, (Entity, Q), TargetingFilter<F, K>>,
+    sources: Arc<HashSet<Entity>>,
+}
+
+impl<'w, Q: WorldQuery, F: QueryFilter, K: 'static> ParTargetingIter<'w, Q, F, K> {
+    pub(crate) fn new(
+        batched_iter: BatchedIter<'w, (Entity, Q), TargetingFilter<F, K>>,
+        sources: HashSet<Entity>,
+    ) -> Self {
+        Self {
+            batched_iter,
+            sources: Arc::new(sources),
+        }
+    }
+}
+
+unsafe impl<'w, Q: WorldQuery, F: QueryFilter, K: 'static> Send for ParTargetingIter<'w, Q, F, K> {}
+
+impl<'w, Q: WorldQuery, F: QueryFilter, K: 'static> ParallelIterator<TargetingBatch<'w, Q, F, K>>
+    for ParTargetingIter<'w, Q, F, K>
+{
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+
+    #[inline]
+    fn next_batch(&mut self) -> Option<TargetingBatch<'w, Q, F, K>> {
+        Some(TargetingBatch {
+            batch: self.batched_iter.next()?,
+            sources: self.sources.clone(),
+        })
+    }
+}
+
+/// A sequence of entities targeting another entity, yielded by `ParTargetingIter`
+pub struct TargetingBatch<'w, Q: WorldQuery, F: QueryFilter, K: 'static> {
+    batch: Batch<'w, (Entity, Q), TargetingFilter<F, K>>,
+    sources: Arc<HashSet<Entity>>,
+}
+
+impl<'w, Q: WorldQuery, F: QueryFilter, K: 'static> Iterator for TargetingBatch<'w, Q, F, K> {
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let sources = &self.sources;
+        self.batch
+            .find(|(entity, _)| sources.contains(entity))
+            .map(|(_, item)| item)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{Component, Entity, World};
+use std::{
+    marker::PhantomData,
+    sync::{atomic::AtomicUsize, Arc},
+};
+
+/// A system parameter that reads the entities that had their `C` component removed, either
+/// directly or by being despawned.
+///
+/// Like an `EventReader`, each system has its own read cursor: every removal is returned exactly
+/// once to each system, regardless of stage order or run criteria. Removals are only kept for 64
+/// frames (calls to `World::clear_trackers`) though, so a system that doesn't run for longer than
+/// that misses the oldest ones.
+///
+/// Removals are recorded when they are applied to the `World`. `Commands` are only applied at the
+/// end of their stage, so a removal queued by a system is observed by systems that run after
+/// that point, including the same system on its next run.
+pub struct RemovedComponents<'a, C: Component> {
+    world: &'a World,
+    reader: &'a AtomicUsize,
+    _marker: PhantomData<C>,
+}
+
+impl<'a, C: Component> RemovedComponents<'a, C> {
+    pub(crate) fn new(world: &'a World, reader: &'a AtomicUsize) -> Self {
+        Self {
+            world,
+            reader,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the entities that had their `C` component removed since this system last read
+    /// them, and marks them as read
+    pub fn iter(&mut self) -> impl Iterator<Item = Entity> + 'a {
+        self.world.read_removed::<C>(self.reader).iter().copied()
+    }
+}
+
+/// The read cursor of a `RemovedComponents<C>` parameter, stored as a local resource
+pub(crate) struct RemovedComponentsReader<C: Component> {
+    pub(crate) reader: Arc<AtomicUsize>,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C: Component> RemovedComponentsReader<C> {
+    pub(crate) fn new(world: &mut World) -> Self {
+        Self {
+            reader: world.removed_reader::<C>(),
+            _marker: PhantomData,
+        }
+    }
+}
@@ -28,6 +28,15 @@ pub trait System: Send + Sync + 'static {
     fn archetype_component_access(&self) -> &TypeAccess<ArchetypeComponent>;
     fn resource_access(&self) -> &TypeAccess<TypeId>;
     fn thread_local_execution(&self) -> ThreadLocalExecution;
+    /// Returns true if this system accesses data that is not `Send`, such as thread local
+    /// resources. The parallel executor only runs these systems on the main thread.
+    fn is_non_send(&self) -> bool {
+        false
+    }
+    /// Returns the name of a component or resource type this system accesses, if it is known
+    fn get_type_name(&self, _type_id: TypeId) -> Option<&'static str> {
+        None
+    }
     /// # Safety
     /// This might access World and Resources in an unsafe manner. This should only be called in one of the following contexts:
     /// 1. This system is the only system running on the given World and Resources across all threads
@@ -47,6 +47,16 @@ impl<SystemA: System, SystemB: System<In = SystemA::Out>> System for ChainSystem
         ThreadLocalExecution::NextFlush
     }
 
+    fn is_non_send(&self) -> bool {
+        self.system_a.is_non_send() || self.system_b.is_non_send()
+    }
+
+    fn get_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.system_a
+            .get_type_name(type_id)
+            .or_else(|| self.system_b.get_type_name(type_id))
+    }
+
     unsafe fn run_unsafe(
         &mut self,
         input: Self::In,
@@ -1,7 +1,8 @@
 use crate::{
-    ArchetypeComponent, ChangedRes, Commands, Fetch, FromResources, Local, Or, Query, QueryAccess,
-    QueryFilter, QuerySet, QueryTuple, Res, ResMut, Resource, ResourceIndex, Resources,
-    SystemState, TypeAccess, World, WorldQuery,
+    ArchetypeComponent, ChangedRes, Commands, Component, Fetch, FromResources, Local,
+    MatchedArchetypes, NonSend, NonSendMut, Or, Query, QueryAccess, QueryFilter, QuerySet,
+    QueryTuple, RemovedComponents, RemovedComponentsReader, Res, ResMut, Resource, ResourceIndex,
+    Resources, SystemState, TypeAccess, World, WorldQuery,
 };
 use parking_lot::Mutex;
 use std::{any::TypeId, marker::PhantomData, sync::Arc};
@@ -11,7 +12,7 @@ pub trait SystemParam: Sized {
 
 pub trait FetchSystemParam<'a> {
     type Item;
-    fn init(system_state: &mut SystemState, world: &World, resources: &mut Resources);
+    fn init(system_state: &mut SystemState, world: &mut World, resources: &mut Resources);
     /// # Safety
     /// This call might access any of the input parameters in an unsafe way. Make sure the data access is safe in
     /// the context of the system scheduler
@@ -40,16 +41,30 @@ impl<'a, Q: WorldQuery, F: QueryFilter> FetchSystemParam<'a> for FetchQuery<Q, F
         let query_index = *system_state.current_query_index.get();
         let archetype_component_access: &'a TypeAccess<ArchetypeComponent> =
             &system_state.query_archetype_component_accesses[query_index];
+        let matched_archetypes = system_state.query_matched_archetypes[query_index]
+            .as_ref()
+            .and_then(|matched_archetypes| matched_archetypes.get(world));
         *system_state.current_query_index.get() += 1;
-        Some(Query::new(world, archetype_component_access))
+        Some(
+            Query::new(
+                world,
+                archetype_component_access,
+                system_state.last_change_tick,
+                system_state.change_tick,
+            )
+            .with_matched_archetypes(matched_archetypes),
+        )
     }
 
-    fn init(system_state: &mut SystemState, _world: &World, _resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, _world: &mut World, _resources: &mut Resources) {
         system_state
             .query_archetype_component_accesses
             .push(TypeAccess::default());
         let access = QueryAccess::union(vec![Q::Fetch::access(), F::access()]);
         system_state.query_accesses.push(vec![access]);
+        system_state
+            .query_matched_archetypes
+            .push(Some(MatchedArchetypes::new::<Q, F>()));
         system_state
             .query_type_names
             .push(std::any::type_name::<Q>());
@@ -76,14 +91,17 @@ impl<'a, T: QueryTuple> FetchSystemParam<'a> for FetchQuerySet<T> {
         Some(QuerySet::new(
             world,
             &system_state.query_archetype_component_accesses[query_index],
+            system_state.last_change_tick,
+            system_state.change_tick,
         ))
     }
 
-    fn init(system_state: &mut SystemState, _world: &World, _resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, _world: &mut World, _resources: &mut Resources) {
         system_state
             .query_archetype_component_accesses
             .push(TypeAccess::default());
         system_state.query_accesses.push(T::get_accesses());
+        system_state.query_matched_archetypes.push(None);
         system_state
             .query_type_names
             .push(std::any::type_name::<T>());
@@ -98,7 +116,7 @@ impl<'a> SystemParam for &'a mut Commands {
 impl<'a> FetchSystemParam<'a> for FetchCommands {
     type Item = &'a mut Commands;
 
-    fn init(system_state: &mut SystemState, world: &World, _resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, world: &mut World, _resources: &mut Resources) {
         // SAFE: this is called with unique access to SystemState
         unsafe {
             (&mut *system_state.commands.get()).set_entity_reserver(world.get_entity_reserver())
@@ -123,7 +141,7 @@ impl SystemParam for Arc<Mutex<Commands>> {
 impl<'a> FetchSystemParam<'a> for FetchArcCommands {
     type Item = Arc<Mutex<Commands>>;
 
-    fn init(system_state: &mut SystemState, world: &World, _resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, world: &mut World, _resources: &mut Resources) {
         system_state.arc_commands.get_or_insert_with(|| {
             let mut commands = Commands::default();
             commands.set_entity_reserver(world.get_entity_reserver());
@@ -150,7 +168,7 @@ impl<'a, T: Resource> SystemParam for Res<'a, T> {
 impl<'a, T: Resource> FetchSystemParam<'a> for FetchRes<T> {
     type Item = Res<'a, T>;
 
-    fn init(system_state: &mut SystemState, _world: &World, _resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, _world: &mut World, _resources: &mut Resources) {
         if system_state.resource_access.is_write(&TypeId::of::<T>()) {
             panic!(
                 "System `{}` has a `Res<{res}>` parameter that conflicts with \
@@ -160,6 +178,9 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchRes<T> {
             );
         }
         system_state.resource_access.add_read(TypeId::of::<T>());
+        system_state
+            .resource_type_names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
     }
 
     #[inline]
@@ -168,9 +189,36 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchRes<T> {
         _world: &'a World,
         resources: &'a Resources,
     ) -> Option<Self::Item> {
-        Some(Res::new(
-            resources.get_unsafe_ref::<T>(ResourceIndex::Global),
-        ))
+        let (value, added, mutated) =
+            resources.get_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global);
+        Some(Res::new(value, *added.as_ptr(), *mutated.as_ptr()))
+    }
+}
+
+pub struct FetchOptionRes<T>(PhantomData<T>);
+
+impl<'a, T: Resource> SystemParam for Option<Res<'a, T>> {
+    type Fetch = FetchOptionRes<T>;
+}
+
+impl<'a, T: Resource> FetchSystemParam<'a> for FetchOptionRes<T> {
+    type Item = Option<Res<'a, T>>;
+
+    fn init(system_state: &mut SystemState, world: &mut World, resources: &mut Resources) {
+        FetchRes::<T>::init(system_state, world, resources);
+    }
+
+    #[inline]
+    unsafe fn get_param(
+        _system_state: &'a SystemState,
+        _world: &'a World,
+        resources: &'a Resources,
+    ) -> Option<Self::Item> {
+        Some(
+            resources
+                .try_get_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global)
+                .map(|(value, added, mutated)| Res::new(value, *added.as_ptr(), *mutated.as_ptr())),
+        )
     }
 }
 
@@ -182,7 +230,7 @@ impl<'a, T: Resource> SystemParam for ResMut<'a, T> {
 impl<'a, T: Resource> FetchSystemParam<'a> for FetchResMut<T> {
     type Item = ResMut<'a, T>;
 
-    fn init(system_state: &mut SystemState, _world: &World, _resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, _world: &mut World, _resources: &mut Resources) {
         // If a system already has access to the resource in another parameter, then we fail early.
         // e.g. `fn(Res<Foo>, ResMut<Foo>)` or `fn(ResMut<Foo>, ResMut<Foo>)` must not be allowed.
         if system_state
@@ -197,6 +245,9 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchResMut<T> {
             );
         }
         system_state.resource_access.add_write(TypeId::of::<T>());
+        system_state
+            .resource_type_names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
     }
 
     #[inline]
@@ -205,9 +256,111 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchResMut<T> {
         _world: &'a World,
         resources: &'a Resources,
     ) -> Option<Self::Item> {
-        let (value, _added, mutated) =
+        let (value, added, mutated) =
             resources.get_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global);
-        Some(ResMut::new(value, mutated))
+        Some(ResMut::new(value, *added.as_ptr(), mutated))
+    }
+}
+
+pub struct FetchOptionResMut<T>(PhantomData<T>);
+
+impl<'a, T: Resource> SystemParam for Option<ResMut<'a, T>> {
+    type Fetch = FetchOptionResMut<T>;
+}
+
+impl<'a, T: Resource> FetchSystemParam<'a> for FetchOptionResMut<T> {
+    type Item = Option<ResMut<'a, T>>;
+
+    fn init(system_state: &mut SystemState, world: &mut World, resources: &mut Resources) {
+        FetchResMut::<T>::init(system_state, world, resources);
+    }
+
+    #[inline]
+    unsafe fn get_param(
+        _system_state: &'a SystemState,
+        _world: &'a World,
+        resources: &'a Resources,
+    ) -> Option<Self::Item> {
+        Some(
+            resources
+                .try_get_unsafe_ref_with_added_and_mutated::<T>(ResourceIndex::Global)
+                .map(|(value, added, mutated)| ResMut::new(value, *added.as_ptr(), mutated)),
+        )
+    }
+}
+
+pub struct FetchNonSend<T>(PhantomData<T>);
+
+impl<'a, T: 'static> SystemParam for NonSend<'a, T> {
+    type Fetch = FetchNonSend<T>;
+}
+
+impl<'a, T: 'static> FetchSystemParam<'a> for FetchNonSend<T> {
+    type Item = NonSend<'a, T>;
+
+    fn init(system_state: &mut SystemState, _world: &mut World, _resources: &mut Resources) {
+        if system_state.resource_access.is_write(&TypeId::of::<T>()) {
+            panic!(
+                "System `{}` has a `NonSend<{res}>` parameter that conflicts with \
+                another parameter with mutable access to the same `{res}` resource.",
+                system_state.name,
+                res = std::any::type_name::<T>()
+            );
+        }
+        system_state.resource_access.add_read(TypeId::of::<T>());
+        system_state
+            .resource_type_names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
+        system_state.is_non_send = true;
+    }
+
+    #[inline]
+    unsafe fn get_param(
+        _system_state: &'a SystemState,
+        _world: &'a World,
+        resources: &'a Resources,
+    ) -> Option<Self::Item> {
+        Some(NonSend::new(resources.get_unsafe_thread_local_ref::<T>()))
+    }
+}
+
+pub struct FetchNonSendMut<T>(PhantomData<T>);
+
+impl<'a, T: 'static> SystemParam for NonSendMut<'a, T> {
+    type Fetch = FetchNonSendMut<T>;
+}
+
+impl<'a, T: 'static> FetchSystemParam<'a> for FetchNonSendMut<T> {
+    type Item = NonSendMut<'a, T>;
+
+    fn init(system_state: &mut SystemState, _world: &mut World, _resources: &mut Resources) {
+        if system_state
+            .resource_access
+            .is_read_or_write(&TypeId::of::<T>())
+        {
+            panic!(
+                "System `{}` has a `NonSendMut<{res}>` parameter that conflicts with \
+                another parameter to the same `{res}` resource. `NonSendMut` must have unique access.",
+                system_state.name,
+                res = std::any::type_name::<T>()
+            );
+        }
+        system_state.resource_access.add_write(TypeId::of::<T>());
+        system_state
+            .resource_type_names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
+        system_state.is_non_send = true;
+    }
+
+    #[inline]
+    unsafe fn get_param(
+        _system_state: &'a SystemState,
+        _world: &'a World,
+        resources: &'a Resources,
+    ) -> Option<Self::Item> {
+        Some(NonSendMut::new(
+            resources.get_unsafe_thread_local_ref::<T>(),
+        ))
     }
 }
 
@@ -220,7 +373,7 @@ impl<'a, T: Resource> SystemParam for ChangedRes<'a, T> {
 impl<'a, T: Resource> FetchSystemParam<'a> for FetchChangedRes<T> {
     type Item = ChangedRes<'a, T>;
 
-    fn init(system_state: &mut SystemState, _world: &World, _resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, _world: &mut World, _resources: &mut Resources) {
         if system_state.resource_access.is_write(&TypeId::of::<T>()) {
             panic!(
                 "System `{}` has a `ChangedRes<{res}>` parameter that conflicts with \
@@ -230,6 +383,9 @@ impl<'a, T: Resource> FetchSystemParam<'a> for FetchChangedRes<T> {
             );
         }
         system_state.resource_access.add_read(TypeId::of::<T>());
+        system_state
+            .resource_type_names
+            .insert(TypeId::of::<T>(), std::any::type_name::<T>());
     }
 
     #[inline]
@@ -256,7 +412,7 @@ impl<'a, T: Resource + FromResources> SystemParam for Local<'a, T> {
 impl<'a, T: Resource + FromResources> FetchSystemParam<'a> for FetchLocal<T> {
     type Item = Local<'a, T>;
 
-    fn init(system_state: &mut SystemState, _world: &World, resources: &mut Resources) {
+    fn init(system_state: &mut SystemState, _world: &mut World, resources: &mut Resources) {
         if system_state
             .local_resource_access
             .is_read_or_write(&TypeId::of::<T>())
@@ -291,6 +447,37 @@ impl<'a, T: Resource + FromResources> FetchSystemParam<'a> for FetchLocal<T> {
     }
 }
 
+pub struct FetchRemovedComponents<C>(PhantomData<C>);
+
+impl<'a, C: Component> SystemParam for RemovedComponents<'a, C> {
+    type Fetch = FetchRemovedComponents<C>;
+}
+
+impl<'a, C: Component> FetchSystemParam<'a> for FetchRemovedComponents<C> {
+    type Item = RemovedComponents<'a, C>;
+
+    fn init(system_state: &mut SystemState, world: &mut World, resources: &mut Resources) {
+        if resources
+            .get_local::<RemovedComponentsReader<C>>(system_state.id)
+            .is_none()
+        {
+            let reader = RemovedComponentsReader::<C>::new(world);
+            resources.insert_local(system_state.id, reader);
+        }
+    }
+
+    #[inline]
+    unsafe fn get_param(
+        system_state: &'a SystemState,
+        world: &'a World,
+        resources: &'a Resources,
+    ) -> Option<Self::Item> {
+        let reader = resources
+            .get_unsafe_ref::<RemovedComponentsReader<C>>(ResourceIndex::System(system_state.id));
+        Some(RemovedComponents::new(world, &(*reader.as_ptr()).reader))
+    }
+}
+
 pub struct FetchParamTuple<T>(PhantomData<T>);
 pub struct FetchOr<T>(PhantomData<T>);
 
@@ -302,7 +489,7 @@ macro_rules! impl_system_param_tuple {
         #[allow(unused_variables)]
         impl<'a, $($param: FetchSystemParam<'a>),*> FetchSystemParam<'a> for FetchParamTuple<($($param,)*)> {
             type Item = ($($param::Item,)*);
-            fn init(system_state: &mut SystemState, world: &World, resources: &mut Resources) {
+            fn init(system_state: &mut SystemState, world: &mut World, resources: &mut Resources) {
                 $($param::init(system_state, world, resources);)*
             }
 
@@ -325,7 +512,7 @@ macro_rules! impl_system_param_tuple {
         #[allow(non_snake_case)]
         impl<'a, $($param: FetchSystemParam<'a>),*> FetchSystemParam<'a> for FetchOr<($($param,)*)> {
             type Item = Or<($(Option<$param::Item>,)*)>;
-            fn init(system_state: &mut SystemState, world: &World, resources: &mut Resources) {
+            fn init(system_state: &mut SystemState, world: &mut World, resources: &mut Resources) {
                 $($param::init(system_state, world, resources);)*
             }
 
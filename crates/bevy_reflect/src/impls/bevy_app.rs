@@ -35,7 +35,8 @@ impl Plugin for ReflectPlugin {
         }
         #[cfg(feature = "bevy_ecs")]
         {
-            app.register_type::<bevy_ecs::Entity>();
+            app.register_type::<bevy_ecs::Entity>()
+                .register_type::<bevy_ecs::Disabled>();
         }
     }
 }
@@ -49,8 +50,23 @@ pub trait RegisterTypeBuilder {
 impl RegisterTypeBuilder for AppBuilder {
     fn register_type<T: GetTypeRegistration>(&mut self) -> &mut Self {
         {
-            let registry = self.resources().get_mut::<TypeRegistryArc>().unwrap();
-            registry.write().register::<T>();
+            let registry = self.app.resources.get_mut::<TypeRegistryArc>().unwrap();
+            let registration = T::get_type_registration();
+            // reflected components can be cloned, unless a cloner was registered explicitly, and
+            // their entities are remapped when moved between worlds
+            #[cfg(feature = "bevy_ecs")]
+            {
+                let world = &mut self.app.world;
+                if let Some(reflect_component) = registration.data::<crate::ReflectComponent>() {
+                    if !world.has_clone_registration(registration.type_id()) {
+                        reflect_component.register_cloner(world);
+                    }
+                }
+                if let Some(map_entities) = registration.data::<crate::ReflectMapEntities>() {
+                    map_entities.register_map_entities(world);
+                }
+            }
+            registry.write().add_registration(registration);
         }
         self
     }
@@ -1,16 +1,20 @@
-use crate::{FromType, Reflect};
+use crate::{FromType, Reflect, ReflectDeserialize};
 use bevy_ecs::{
-    Archetype, Component, Entity, EntityMap, FromResources, MapEntities, MapEntitiesError,
-    Resources, World,
+    Archetype, Component, Disabled, Entity, EntityMap, FromResources, MapEntities,
+    MapEntitiesError, Resources, World,
 };
+use bevy_reflect_derive::impl_reflect_value;
 use std::marker::PhantomData;
 
+impl_reflect_value!(Disabled(Hash, PartialEq, Serialize, Deserialize, Component));
+
 #[derive(Clone)]
 pub struct ReflectComponent {
     add_component: fn(&mut World, resources: &Resources, Entity, &dyn Reflect),
     apply_component: fn(&mut World, Entity, &dyn Reflect),
     reflect_component: unsafe fn(&Archetype, usize) -> &dyn Reflect,
     copy_component: fn(&World, &mut World, &Resources, Entity, Entity),
+    register_cloner: fn(&mut World),
 }
 
 impl ReflectComponent {
@@ -54,6 +58,11 @@ impl ReflectComponent {
             destination_entity,
         );
     }
+
+    /// Makes `World::clone_entity` copy the component through reflection
+    pub fn register_cloner(&self, world: &mut World) {
+        (self.register_cloner)(world);
+    }
 }
 
 impl<C: Component + Reflect + FromResources> FromType<C> for ReflectComponent {
@@ -80,6 +89,13 @@ impl<C: Component + Reflect + FromResources> FromType<C> for ReflectComponent {
                     .insert_one(destination_entity, destination_component)
                     .unwrap();
             },
+            register_cloner: |world| {
+                world.register_component_cloner::<C>(|world, resources, source, destination| {
+                    let mut component = C::from_resources(resources);
+                    component.apply(world.get::<C>(source).unwrap());
+                    world.insert_one(destination, component).unwrap();
+                });
+            },
             reflect_component: |archetype, index| {
                 unsafe {
                     // the type has been looked up by the caller, so this is safe
@@ -186,6 +202,7 @@ impl<Runtime: Component + IntoComponent<Scene>, Scene: Component> FromType<Runti
 #[derive(Clone)]
 pub struct ReflectMapEntities {
     map_entities: fn(&mut World, &EntityMap) -> Result<(), MapEntitiesError>,
+    register_map_entities: fn(&mut World),
 }
 
 impl ReflectMapEntities {
@@ -196,6 +213,11 @@ impl ReflectMapEntities {
     ) -> Result<(), MapEntitiesError> {
         (self.map_entities)(world, entity_map)
     }
+
+    /// Makes `World::move_entities_from` remap the entities stored in the component
+    pub fn register_map_entities(&self, world: &mut World) {
+        (self.register_map_entities)(world);
+    }
 }
 
 impl<C: Component + MapEntities> FromType<C> for ReflectMapEntities {
@@ -210,6 +232,7 @@ impl<C: Component + MapEntities> FromType<C> for ReflectMapEntities {
 
                 Ok(())
             },
+            register_map_entities: |world| world.register_map_entities::<C>(),
         }
     }
 }
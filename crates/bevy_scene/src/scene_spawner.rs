@@ -331,3 +331,55 @@ pub fn scene_spawner_system(world: &mut World, resources: &mut Resources) {
         .unwrap();
     scene_spawner.set_scene_instance_parent_sync(world);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScenePlugin;
+    use bevy_asset::{AssetPlugin, AssetServer, FileAssetIo};
+    use bevy_ecs::{Disabled, IncludeDisabled, With};
+    use bevy_reflect::{Reflect, ReflectPlugin, RegisterTypeBuilder};
+    use bevy_tasks::TaskPool;
+
+    #[derive(Reflect, Default)]
+    #[reflect(Component)]
+    struct Marker(u32);
+
+    #[test]
+    fn disabled_scene_entities_spawn_disabled() {
+        let mut app = App::build();
+        app.add_resource(AssetServer::new(FileAssetIo::new("."), TaskPool::default()))
+            .add_plugin(ReflectPlugin)
+            .add_plugin(AssetPlugin)
+            .add_plugin(ScenePlugin)
+            .register_type::<Marker>();
+
+        let mut scene_world = World::default();
+        scene_world.spawn((Marker(1),));
+        scene_world.spawn((Marker(2), Disabled));
+        let scene_handle = app
+            .resources()
+            .get_mut::<Assets<Scene>>()
+            .unwrap()
+            .add(Scene::new(scene_world));
+
+        let mut scene_spawner = SceneSpawner::default();
+        let app = &mut app.app;
+        scene_spawner
+            .spawn_sync(&mut app.world, &app.resources, scene_handle)
+            .unwrap();
+
+        let enabled = app
+            .world
+            .query::<&Marker>()
+            .map(|m| m.0)
+            .collect::<Vec<_>>();
+        assert_eq!(enabled, vec![1]);
+        let disabled = app
+            .world
+            .query_filtered::<&Marker, (With<Disabled>, IncludeDisabled)>()
+            .map(|m| m.0)
+            .collect::<Vec<_>>();
+        assert_eq!(disabled, vec![2]);
+    }
+}
@@ -166,9 +166,15 @@ impl TaskPool {
         // validate safety.
         let executor: &async_executor::Executor = &*self.executor;
         let executor: &'scope async_executor::Executor = unsafe { mem::transmute(executor) };
+        let local_executor = async_executor::LocalExecutor::new();
+        // SAFETY: The local executor lives on this function's stack and all of its tasks complete
+        // before this function returns, for the same reason as above.
+        let local_executor: &'scope async_executor::LocalExecutor =
+            unsafe { mem::transmute(&local_executor) };
 
         let mut scope = Scope {
             executor,
+            local_executor,
             spawned: Vec::new(),
         };
 
@@ -177,11 +183,12 @@ impl TaskPool {
         if scope.spawned.is_empty() {
             Vec::default()
         } else if scope.spawned.len() == 1 {
-            vec![future::block_on(&mut scope.spawned[0])]
+            vec![future::block_on(local_executor.run(&mut scope.spawned[0]))]
         } else {
+            let spawned = scope.spawned;
             let fut = async move {
-                let mut results = Vec::with_capacity(scope.spawned.len());
-                for task in scope.spawned {
+                let mut results = Vec::with_capacity(spawned.len());
+                for task in spawned {
                     results.push(task.await);
                 }
 
@@ -202,7 +209,8 @@ impl TaskPool {
             // The thread that calls scope() will participate in driving tasks in the pool forward
             // until the tasks that are spawned by this scope() call complete. (If the caller of scope()
             // happens to be a thread in this thread pool, and we only have one thread in the pool, then
-            // simply calling future::block_on(spawned) would deadlock.)
+            // simply calling future::block_on(spawned) would deadlock.) It is also the only thread
+            // that runs the tasks spawned with `Scope::spawn_local`.
             let mut spawned = self.executor.spawn(fut);
             loop {
                 if let Some(result) = future::block_on(future::poll_once(&mut spawned)) {
@@ -210,6 +218,7 @@ impl TaskPool {
                 }
 
                 self.executor.try_tick();
+                local_executor.try_tick();
             }
         }
     }
@@ -234,6 +243,7 @@ impl Default for TaskPool {
 #[derive(Debug)]
 pub struct Scope<'scope, T> {
     executor: &'scope async_executor::Executor<'scope>,
+    local_executor: &'scope async_executor::LocalExecutor<'scope>,
     spawned: Vec<async_executor::Task<T>>,
 }
 
@@ -242,6 +252,12 @@ impl<'scope, T: Send + 'scope> Scope<'scope, T> {
         let task = self.executor.spawn(f);
         self.spawned.push(task);
     }
+
+    /// Spawns a future that only runs on the thread that called [`TaskPool::scope`]
+    pub fn spawn_local<Fut: Future<Output = T> + 'scope>(&mut self, f: Fut) {
+        let task = self.local_executor.spawn(f);
+        self.spawned.push(task);
+    }
 }
 
 #[cfg(test)]
@@ -279,4 +295,25 @@ mod tests {
         assert_eq!(outputs.len(), 100);
         assert_eq!(count.load(Ordering::Relaxed), 100);
     }
+
+    #[test]
+    pub fn test_spawn_local() {
+        let pool = TaskPool::new();
+        let scope_thread = thread::current().id();
+
+        let outputs = pool.scope(|scope| {
+            for i in 0..100 {
+                if i % 2 == 0 {
+                    scope.spawn_local(async move {
+                        assert_eq!(thread::current().id(), scope_thread);
+                        i
+                    });
+                } else {
+                    scope.spawn(async move { i });
+                }
+            }
+        });
+
+        assert_eq!(outputs, (0..100).collect::<Vec<_>>());
+    }
 }
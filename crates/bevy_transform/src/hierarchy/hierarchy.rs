@@ -1,6 +1,7 @@
-use crate::components::{Children, Parent};
-use bevy_ecs::{Command, Commands, Entity, Resources, World};
+use crate::components::{Children, Parent, PreviousParent};
+use bevy_ecs::{Command, Commands, Entity, NoSuchEntity, Resources, World};
 use bevy_utils::tracing::debug;
+use smallvec::SmallVec;
 
 #[derive(Debug)]
 pub struct DespawnRecursive {
@@ -50,10 +51,102 @@ impl DespawnRecursiveExt for Commands {
     }
 }
 
+#[derive(Debug)]
+pub struct CloneRecursive {
+    source: Entity,
+    destination: Entity,
+}
+
+/// Clones `entity` and all of its descendants with [World::clone_entity], and returns the clone.
+/// The clone gets the same parent as `entity`.
+pub fn clone_with_children_recursive(
+    world: &mut World,
+    resources: &Resources,
+    entity: Entity,
+) -> Result<Entity, NoSuchEntity> {
+    let clone = world.clone_entity(resources, entity)?;
+    clone_hierarchy(world, resources, entity, clone);
+    Ok(clone)
+}
+
+// Should only be called once the components of `source` were cloned into `clone`!
+fn clone_hierarchy(world: &mut World, resources: &Resources, source: Entity, clone: Entity) {
+    // the clone becomes a sibling of its source
+    if let Ok(parent) = world.get::<Parent>(source).map(|parent| parent.0) {
+        world
+            .insert(clone, (Parent(parent), PreviousParent(parent)))
+            .unwrap();
+        if let Ok(mut children) = world.get_mut::<Children>(parent) {
+            children.0.push(clone);
+        } else {
+            world.insert_one(parent, Children::with(&[clone])).unwrap();
+        }
+    }
+
+    clone_children_recursive(world, resources, source, clone);
+}
+
+fn clone_children_recursive(
+    world: &mut World,
+    resources: &Resources,
+    source: Entity,
+    clone: Entity,
+) {
+    let children = match world.get::<Children>(source) {
+        Ok(children) => children.0.clone(),
+        Err(_) => return,
+    };
+    let mut cloned_children = SmallVec::with_capacity(children.len());
+    for child in children {
+        let child_clone = match world.clone_entity(resources, child) {
+            Ok(child_clone) => child_clone,
+            Err(e) => {
+                debug!("Failed to clone child entity {:?}: {}", child, e);
+                continue;
+            }
+        };
+        world
+            .insert(child_clone, (Parent(clone), PreviousParent(clone)))
+            .unwrap();
+        clone_children_recursive(world, resources, child, child_clone);
+        cloned_children.push(child_clone);
+    }
+    world.insert_one(clone, Children(cloned_children)).unwrap();
+}
+
+impl Command for CloneRecursive {
+    fn write(self: Box<Self>, world: &mut World, resources: &mut Resources) {
+        // `Commands::clone_entity` already cloned the components of the source
+        if world.contains(self.source) && world.contains(self.destination) {
+            clone_hierarchy(world, resources, self.source, self.destination);
+        }
+    }
+}
+
+pub trait CloneRecursiveExt {
+    /// Clones the provided entity and its children, and makes the clone the current entity.
+    fn clone_recursive(&mut self, entity: Entity) -> &mut Self;
+}
+
+impl CloneRecursiveExt for Commands {
+    /// Clones the provided entity and its children, and makes the clone the current entity.
+    fn clone_recursive(&mut self, entity: Entity) -> &mut Self {
+        self.clone_entity(entity);
+        let destination = self.current_entity().unwrap();
+        self.add_command(CloneRecursive {
+            source: entity,
+            destination,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::DespawnRecursiveExt;
-    use crate::{components::Children, hierarchy::BuildChildren};
+    use super::{clone_with_children_recursive, CloneRecursiveExt, DespawnRecursiveExt};
+    use crate::{
+        components::{Children, Parent},
+        hierarchy::BuildChildren,
+    };
     use bevy_ecs::{Commands, Resources, World};
 
     #[test]
@@ -122,4 +215,59 @@ mod tests {
             ]
         );
     }
+
+    fn names(world: &World, entities: &[bevy_ecs::Entity]) -> Vec<String> {
+        entities
+            .iter()
+            .map(|&entity| world.get::<String>(entity).unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn clone_recursive() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        world.register_clone::<String>();
+        world.ignore_on_clone::<Children>();
+        let mut command_buffer = Commands::default();
+        command_buffer.set_entity_reserver(world.get_entity_reserver());
+
+        command_buffer.spawn(("Root".to_owned(),));
+        let root = command_buffer.current_entity().unwrap();
+        command_buffer.with_children(|parent| {
+            parent
+                .spawn(("Prefab".to_owned(),))
+                .with_children(|parent| {
+                    parent.spawn(("Arm".to_owned(),)).with_children(|parent| {
+                        parent.spawn(("Hand".to_owned(),));
+                    });
+                    parent.spawn(("Leg".to_owned(),));
+                });
+        });
+        command_buffer.apply(&mut world, &mut resources);
+        let prefab = world.get::<Children>(root).unwrap()[0];
+
+        let clone = clone_with_children_recursive(&mut world, &resources, prefab).unwrap();
+        command_buffer.clone_recursive(prefab).with(0u32);
+        let command_clone = command_buffer.current_entity().unwrap();
+        command_buffer.apply(&mut world, &mut resources);
+
+        assert_eq!(
+            names(&world, &world.get::<Children>(root).unwrap()),
+            vec!["Prefab", "Prefab", "Prefab"]
+        );
+        assert_eq!(*world.get::<u32>(command_clone).unwrap(), 0);
+        for instance in [clone, command_clone].iter().copied() {
+            assert_eq!(world.get::<Parent>(instance).unwrap().0, root);
+            let limbs = world.get::<Children>(instance).unwrap().to_vec();
+            assert_eq!(names(&world, &limbs), vec!["Arm", "Leg"]);
+            for &limb in limbs.iter() {
+                assert_eq!(world.get::<Parent>(limb).unwrap().0, instance);
+            }
+            let hands = world.get::<Children>(limbs[0]).unwrap().to_vec();
+            assert_eq!(names(&world, &hands), vec!["Hand"]);
+            assert_eq!(world.get::<Parent>(hands[0]).unwrap().0, limbs[0]);
+        }
+        assert_eq!(world.query::<&String>().count(), 13);
+    }
 }
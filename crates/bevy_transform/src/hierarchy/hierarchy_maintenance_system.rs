@@ -1,15 +1,17 @@
 use crate::components::*;
-use bevy_ecs::{Commands, Entity, Query, Without};
+use bevy_ecs::{Commands, Entity, IncludeDisabled, Query, Without};
 use bevy_utils::HashMap;
 use smallvec::SmallVec;
 
+/// Keeps `Children` in sync with `Parent`. Disabled entities are included, so the hierarchy stays
+/// consistent while they are disabled.
 pub fn parent_update_system(
     commands: &mut Commands,
-    removed_parent_query: Query<(Entity, &PreviousParent), Without<Parent>>,
+    removed_parent_query: Query<(Entity, &PreviousParent), (Without<Parent>, IncludeDisabled)>,
     // The next query could be run with a Changed<Parent> filter. However, this would mean that modifications later in the frame are lost.
     // See issue 891: https://github.com/bevyengine/bevy/issues/891
-    mut parent_query: Query<(Entity, &Parent, Option<&mut PreviousParent>)>,
-    mut children_query: Query<&mut Children>,
+    mut parent_query: Query<(Entity, &Parent, Option<&mut PreviousParent>), IncludeDisabled>,
+    mut children_query: Query<&mut Children, IncludeDisabled>,
 ) {
     // Entities with a missing `Parent` (ie. ones that have a `PreviousParent`), remove
     // them from the `Children` of the `PreviousParent`.
@@ -21,6 +21,9 @@ impl Plugin for TransformPlugin {
             .register_type::<PreviousParent>()
             .register_type::<Transform>()
             .register_type::<GlobalTransform>()
+            // cloned entities get their own hierarchy, see `clone_with_children_recursive`
+            .ignore_on_clone::<Children>()
+            .ignore_on_clone::<PreviousParent>()
             // add transform systems to startup so the first update is "correct"
             .add_startup_system_to_stage(startup_stage::POST_STARTUP, parent_update_system.system())
             .add_startup_system_to_stage(
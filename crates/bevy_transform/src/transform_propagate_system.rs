@@ -1,6 +1,10 @@
 use crate::components::*;
 use bevy_ecs::prelude::*;
+use bevy_utils::HashSet;
 
+/// Updates the `GlobalTransform` of entities from their `Transform` and their parent's
+/// `GlobalTransform`. Disabled entities and their descendants are skipped until they are enabled
+/// again.
 pub fn transform_propagate_system(
     mut root_query: Query<
         (Entity, Option<&Children>, &Transform, &mut GlobalTransform),
@@ -9,10 +13,13 @@ pub fn transform_propagate_system(
     mut transform_query: Query<(&Transform, &mut GlobalTransform), With<Parent>>,
     changed_transform_query: Query<Entity, Changed<Transform>>,
     children_query: Query<Option<&Children>, (With<Parent>, With<GlobalTransform>)>,
+    mut removed_disabled: RemovedComponents<Disabled>,
 ) {
+    // transforms may have changed while their entities were disabled
+    let enabled = removed_disabled.iter().collect::<HashSet<_>>();
     for (entity, children, transform, mut global_transform) in root_query.iter_mut() {
         let mut changed = false;
-        if changed_transform_query.get(entity).is_ok() {
+        if changed_transform_query.get(entity).is_ok() || enabled.contains(&entity) {
             *global_transform = GlobalTransform::from(*transform);
             changed = true;
         }
@@ -24,6 +31,7 @@ pub fn transform_propagate_system(
                     &changed_transform_query,
                     &mut transform_query,
                     &children_query,
+                    &enabled,
                     *child,
                     changed,
                 );
@@ -37,10 +45,11 @@ fn propagate_recursive(
     changed_transform_query: &Query<Entity, Changed<Transform>>,
     transform_query: &mut Query<(&Transform, &mut GlobalTransform), With<Parent>>,
     children_query: &Query<Option<&Children>, (With<Parent>, With<GlobalTransform>)>,
+    enabled: &HashSet<Entity>,
     entity: Entity,
     mut changed: bool,
 ) {
-    changed |= changed_transform_query.get(entity).is_ok();
+    changed |= changed_transform_query.get(entity).is_ok() || enabled.contains(&entity);
 
     let global_matrix = {
         if let Ok((transform, mut global_transform)) = transform_query.get_mut(entity) {
@@ -60,6 +69,7 @@ fn propagate_recursive(
                 changed_transform_query,
                 transform_query,
                 children_query,
+                enabled,
                 *child,
                 changed,
             );
@@ -176,4 +186,69 @@ mod test {
                 * Transform::from_translation(Vec3::new(0.0, 0.0, 3.0))
         );
     }
+
+    #[test]
+    fn skips_disabled_subtrees() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let mut update_stage = SystemStage::parallel();
+        update_stage.add_system(parent_update_system.system());
+        update_stage.add_system(transform_propagate_system.system());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update", update_stage);
+
+        let mut root = None;
+        let mut children = Vec::new();
+        world
+            .build()
+            .spawn((Transform::identity(), GlobalTransform::identity()))
+            .for_current_entity(|entity| root = Some(entity))
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        Transform::from_translation(Vec3::new(0.0, 2.0, 0.0)),
+                        GlobalTransform::identity(),
+                    ))
+                    .for_current_entity(|entity| children.push(entity))
+                    .with_children(|parent| {
+                        parent
+                            .spawn((
+                                Transform::from_translation(Vec3::new(0.0, 0.0, 3.0)),
+                                GlobalTransform::identity(),
+                            ))
+                            .for_current_entity(|entity| children.push(entity));
+                    });
+            });
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(
+            *world.get::<GlobalTransform>(children[1]).unwrap(),
+            GlobalTransform::from_translation(Vec3::new(0.0, 2.0, 3.0))
+        );
+
+        world.insert_one(children[0], Disabled).unwrap();
+        *world.get_mut::<Transform>(root.unwrap()).unwrap() =
+            Transform::from_translation(Vec3::new(1.0, 0.0, 0.0));
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(
+            *world.get::<GlobalTransform>(children[0]).unwrap(),
+            GlobalTransform::from_translation(Vec3::new(0.0, 2.0, 0.0))
+        );
+        assert_eq!(
+            *world.get::<GlobalTransform>(children[1]).unwrap(),
+            GlobalTransform::from_translation(Vec3::new(0.0, 2.0, 3.0))
+        );
+
+        world.remove_one::<Disabled>(children[0]).unwrap();
+        schedule.initialize_and_run(&mut world, &mut resources);
+        assert_eq!(
+            *world.get::<GlobalTransform>(children[0]).unwrap(),
+            GlobalTransform::from_translation(Vec3::new(1.0, 2.0, 0.0))
+        );
+        assert_eq!(
+            *world.get::<GlobalTransform>(children[1]).unwrap(),
+            GlobalTransform::from_translation(Vec3::new(1.0, 2.0, 3.0))
+        );
+    }
 }
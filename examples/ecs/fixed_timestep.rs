@@ -10,11 +10,9 @@ fn main() {
         .add_plugins(DefaultPlugins)
         // this system will run once every update (it should match your screen's refresh rate)
         .add_system(update.system())
-        // add a new stage that runs every two seconds
-        .add_stage_after(
-            stage::UPDATE,
-            "fixed_update",
-            SystemStage::parallel()
+        // add a new system set that runs every two seconds
+        .add_system_set(
+            SystemSet::new()
                 .with_run_criteria(
                     FixedTimestep::step(2.0)
                         // labels are optional. they provide a way to access the current FixedTimestep state from within a system